@@ -1,4 +1,14 @@
-use std::{env, str::FromStr, process::Command, fs};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    ffi::OsStr,
+    fs,
+    io::{ErrorKind, Write},
+    path::Path,
+    process::{Command, Stdio},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 fn run_python(file: &str, cwd: &str) -> bool {
     Command::new("python3")
@@ -11,81 +21,1638 @@ fn run_python(file: &str, cwd: &str) -> bool {
         .success()
 }
 
-fn rerun_if_dir_changed(dir: &str, recursive: bool) {
-    let directory = fs::read_dir(dir).unwrap();
-    for entry in directory {
+// Environment variables the build reads, besides cargo's own, which cargo tracks itself, and the compiler variables the
+// cc crate reports. The VULKAN_LOADER_SYS_ ones are the crate's, CMAKE and the toolchain files are read by the cmake
+// crate, and LIBCLANG_PATH and BINDGEN_EXTRA_CLANG_ARGS by bindgen.
+const BUILD_ENV_VARS: &[&str] = &[
+    "DOCS_RS",
+    "VULKAN_LOADER_SYS_SYSROOT",
+    "VULKAN_LOADER_SYS_LIB_DIR",
+    "VULKAN_LOADER_SYS_INCLUDE_DIR",
+    "VULKAN_LOADER_SYS_CACHE_DIR",
+    "VULKAN_LOADER_SYS_COMPARE_TARGETS",
+    "VULKAN_LOADER_SYS_FALLBACK_CONFIG_DIRS",
+    "VULKAN_LOADER_SYS_FALLBACK_DATA_DIRS",
+    "VULKAN_LOADER_SYS_SYSCONFDIR",
+    "VULKAN_LOADER_SYS_LOADER_USE_UNSAFE_FILE_SEARCH",
+    "CMAKE",
+    "CMAKE_GENERATOR",
+    "CMAKE_TOOLCHAIN_FILE",
+    "TARGET_CMAKE_TOOLCHAIN_FILE",
+    "PKG_CONFIG_PATH",
+    "PKG_CONFIG_SYSROOT_DIR",
+    "LIBCLANG_PATH",
+    "BINDGEN_EXTRA_CLANG_ARGS",
+];
+
+// Also read with the target appended, with its dashes or with underscores in their place.
+const BUILD_TARGET_ENV_VARS: &[&str] = &[
+    "VULKAN_LOADER_SYS_SYSROOT_",
+    "CMAKE_TOOLCHAIN_FILE_",
+    "BINDGEN_EXTRA_CLANG_ARGS_",
+];
+
+// The files and environment variables the build depends on. Directories are listed file by file, as cargo would look
+// at everything in them, including the __pycache__ Python leaves behind. tests/build_inputs.rs checks the list.
+#[derive(Default)]
+struct BuildInputs {
+    files: Vec<String>,
+    env_vars: Vec<String>,
+}
+
+impl BuildInputs {
+    fn new(triple: &str) -> BuildInputs {
+        let mut env_vars = BUILD_ENV_VARS.iter().map(|var| var.to_string()).collect::<Vec<_>>();
+        for prefix in BUILD_TARGET_ENV_VARS {
+            env_vars.push(format!("{}{}", prefix, triple));
+            env_vars.push(format!("{}{}", prefix, triple.replace('-', "_")));
+        }
+        env_vars.dedup();
+
+        BuildInputs {
+            files: Vec::new(),
+            env_vars,
+        }
+    }
+
+    // Adds a file, or every file below a directory except for those with one of the skipped extensions.
+    fn add(&mut self, path: &Path, skipped_extensions: &[&str]) {
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| !path.ends_with("__pycache__"))
+                .collect::<Vec<_>>();
+            entries.sort();
+            for entry in entries {
+                self.add(&entry, skipped_extensions);
+            }
+        } else if path.is_file() {
+            let skipped = skipped_extensions
+                .iter()
+                .any(|extension| path.extension() == Some(OsStr::new(extension)));
+            if !skipped {
+                self.files.push(path.display().to_string());
+            }
+        }
+    }
+
+    fn emit(&self, out_dir: &str) {
+        for file in &self.files {
+            println!("cargo:rerun-if-changed={}", file);
+        }
+        for var in &self.env_vars {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+
+        fs::write(
+            format!("{}/build_inputs.rs", out_dir),
+            format!(
+                "const BUILD_INPUT_FILES: &[&str] = &{:?};\nconst BUILD_INPUT_ENV_VARS: &[&str] = &{:?};\n",
+                self.files, self.env_vars
+            ),
+        )
+        .unwrap();
+    }
+}
+
+// Window system commands the loader exports on top of loader/vulkan-1.def when it is built with support for that window
+// system. The Windows ones are part of the .def file already.
+const PLATFORM_EXPORTS: &[(&str, &[&str])] = &[
+    (
+        "VK_USE_PLATFORM_XCB_KHR",
+        &["vkCreateXcbSurfaceKHR", "vkGetPhysicalDeviceXcbPresentationSupportKHR"],
+    ),
+    (
+        "VK_USE_PLATFORM_WAYLAND_KHR",
+        &["vkCreateWaylandSurfaceKHR", "vkGetPhysicalDeviceWaylandPresentationSupportKHR"],
+    ),
+    (
+        "VK_USE_PLATFORM_XLIB_KHR",
+        &["vkCreateXlibSurfaceKHR", "vkGetPhysicalDeviceXlibPresentationSupportKHR"],
+    ),
+];
+
+// The commands libvulkan exports, which are the only ones that can be declared as extern functions. Everything else
+// has to be looked up with vkGetInstanceProcAddr or vkGetDeviceProcAddr.
+fn exported_commands(platform_defines: &[&str]) -> Vec<String> {
+    let def = fs::read_to_string("loader/vulkan-1.def").unwrap();
+    let mut commands: Vec<String> = def
+        .lines()
+        .skip_while(|line| line.trim() != "EXPORTS")
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(';'))
+        .map(String::from)
+        .collect();
+
+    for (define, platform_commands) in PLATFORM_EXPORTS {
+        if platform_defines.contains(define) {
+            commands.extend(platform_commands.iter().map(|command| command.to_string()));
+        }
+    }
+
+    commands
+}
+
+// The names of the functions declared in the generated bindings' extern blocks. Function pointer types have no name
+// after `fn`, so they don't show up here.
+fn extern_functions(bindings: &str) -> Vec<String> {
+    let tokens = tokenize(bindings);
+    tokens
+        .windows(3)
+        .filter(|window| window[0] == "pub" && window[1] == "fn")
+        .map(|window| window[2].clone())
+        .collect()
+}
+
+// The target being built for, as clang and CMake need to be told about it.
+struct Target {
+    triple: String,
+    host: String,
+    os: String,
+    env: String,
+    arch: String,
+    sysroot: Option<String>,
+    sanitizers: Vec<String>,
+}
+
+impl Target {
+    fn from_env() -> Target {
+        let triple = env::var("TARGET").unwrap();
+
+        // The target specific variable wins, so that one sysroot can be set per cross target.
+        let sysroot_var = format!("VULKAN_LOADER_SYS_SYSROOT_{}", triple.replace('-', "_"));
+        let sysroot = env::var(&sysroot_var)
+            .or_else(|_| env::var("VULKAN_LOADER_SYS_SYSROOT"))
+            .ok();
+
+        Target {
+            triple,
+            host: env::var("HOST").unwrap(),
+            os: env::var("CARGO_CFG_TARGET_OS").unwrap(),
+            env: env::var("CARGO_CFG_TARGET_ENV").unwrap(),
+            arch: env::var("CARGO_CFG_TARGET_ARCH").unwrap(),
+            sysroot,
+            // Set by -Zsanitizer, as a comma separated list when there are several.
+            sanitizers: env::var("CARGO_CFG_SANITIZE")
+                .map(|sanitizers| sanitizers.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    // musl binaries are usually linked statically, so the loader is linked into them as well.
+    fn is_musl(&self) -> bool {
+        self.os == "linux" && self.env == "musl"
+    }
+
+    fn is_cross(&self) -> bool {
+        self.triple != self.host
+    }
+
+    // The variables the cmake crate reads a toolchain file from.
+    fn cmake_toolchain_file_vars(&self) -> Vec<String> {
+        vec![
+            format!("CMAKE_TOOLCHAIN_FILE_{}", self.triple),
+            format!("CMAKE_TOOLCHAIN_FILE_{}", self.triple.replace('-', "_")),
+            String::from("TARGET_CMAKE_TOOLCHAIN_FILE"),
+            String::from("CMAKE_TOOLCHAIN_FILE"),
+        ]
+    }
+
+    fn has_cmake_toolchain_file(&self) -> bool {
+        self.cmake_toolchain_file_vars()
+            .iter()
+            .any(|var| env::var_os(var).is_some())
+    }
+}
+
+// Instruments the loader with the sanitizers the Rust code is built with, so that its leaks and races are reported by
+// the same runtime. rustc links that runtime into the executable, and the loader has to use it instead of bringing its
+// own: clang leaves a shared library's sanitizer symbols to the executable, while GCC would add a dependency on its
+// libasan.so or libtsan.so, which refuses to run next to LLVM's.
+fn enable_sanitizers(target: &Target, cmake_defines: &mut BTreeMap<&str, String>) {
+    if target.os != "linux" || target.is_musl() {
+        println!(
+            "cargo:warning=The loader is only built with sanitizers on Linux with glibc, {} is left uninstrumented.",
+            target.triple
+        );
+        return;
+    }
+
+    let mut instrumented = false;
+    for sanitizer in &target.sanitizers {
+        match sanitizer.as_str() {
+            "address" => {
+                cmake_defines.insert("LOADER_ENABLE_ADDRESS_SANITIZER", String::from("ON"));
+                instrumented = true;
+            }
+            "thread" => {
+                cmake_defines.insert("LOADER_ENABLE_THREAD_SANITIZER", String::from("ON"));
+                instrumented = true;
+            }
+            // LeakSanitizer only intercepts the allocation functions, which works without instrumenting the loader.
+            "leak" => {}
+            other => println!(
+                "cargo:warning=The loader has no build option for the {} sanitizer and is left uninstrumented.",
+                other
+            ),
+        }
+    }
+
+    if instrumented && !cc::Build::new().get_compiler().is_like_clang() {
+        panic!(
+            "The loader has to be compiled with clang to share the sanitizer runtime with Rust. Set CC=clang and \
+             CXX=clang++, or CC_{triple}=clang and CXX_{triple}=clang++.",
+            triple = target.triple.replace('-', "_")
+        );
+    }
+}
+
+// The CMake cache variables that decide where the loader searches for manifests on Linux and the BSDs, each of which
+// VULKAN_LOADER_SYS_<variable> overrides. src/search_paths.rs exports the values the loader ends up with.
+struct SearchPaths {
+    fallback_config_dirs: Option<String>,
+    fallback_data_dirs: Option<String>,
+    sysconfdir: Option<String>,
+    unsafe_file_search: bool,
+}
+
+impl SearchPaths {
+    fn from_env() -> SearchPaths {
+        // An empty value means the CMake default, as it does for SYSCONFDIR in CMakeLists.txt.
+        let non_empty_var = |var| env::var(var).ok().filter(|value| !value.is_empty());
+
+        let unsafe_file_search = match non_empty_var("VULKAN_LOADER_SYS_LOADER_USE_UNSAFE_FILE_SEARCH") {
+            None => false,
+            Some(value) => match value.to_ascii_lowercase().as_str() {
+                "1" | "on" | "true" | "yes" => true,
+                "0" | "off" | "false" | "no" => false,
+                _ => panic!(
+                    "VULKAN_LOADER_SYS_LOADER_USE_UNSAFE_FILE_SEARCH should be ON or OFF, not {}.",
+                    value
+                ),
+            },
+        };
+
+        SearchPaths {
+            fallback_config_dirs: non_empty_var("VULKAN_LOADER_SYS_FALLBACK_CONFIG_DIRS"),
+            fallback_data_dirs: non_empty_var("VULKAN_LOADER_SYS_FALLBACK_DATA_DIRS"),
+            sysconfdir: non_empty_var("VULKAN_LOADER_SYS_SYSCONFDIR"),
+            unsafe_file_search,
+        }
+    }
+
+    // Only what was set, so that everything else keeps the defaults in CMakeLists.txt.
+    fn add_cmake_defines(&self, cmake_defines: &mut BTreeMap<&str, String>) {
+        if let Some(fallback_config_dirs) = &self.fallback_config_dirs {
+            cmake_defines.insert("FALLBACK_CONFIG_DIRS", fallback_config_dirs.clone());
+        }
+        if let Some(fallback_data_dirs) = &self.fallback_data_dirs {
+            cmake_defines.insert("FALLBACK_DATA_DIRS", fallback_data_dirs.clone());
+        }
+        if let Some(sysconfdir) = &self.sysconfdir {
+            cmake_defines.insert("SYSCONFDIR", sysconfdir.clone());
+        }
+        if self.unsafe_file_search {
+            cmake_defines.insert("LOADER_USE_UNSAFE_FILE_SEARCH", String::from("ON"));
+        }
+    }
+
+    // Without an explicit SYSCONFDIR the loader searches the install prefix's etc directory, followed by /etc when
+    // that is a different directory.
+    fn write(&self, out_dir: &str, install_sysconfdir: Option<&str>) {
+        let mut sysconf_dirs = Vec::new();
+        match (&self.sysconfdir, install_sysconfdir) {
+            (Some(sysconfdir), _) => sysconf_dirs.push(sysconfdir.as_str()),
+            (None, Some(install_sysconfdir)) => sysconf_dirs.push(install_sysconfdir),
+            (None, None) => {}
+        }
+        if self.sysconfdir.is_none() && !sysconf_dirs.contains(&"/etc") {
+            sysconf_dirs.push("/etc");
+        }
+
+        let dir = format!("{}/search_paths", out_dir);
+        fs::create_dir_all(&dir).unwrap();
+        let values = [
+            (
+                "fallback_config_dirs.rs",
+                format!("{:?}", self.fallback_config_dirs.as_deref().unwrap_or("/etc/xdg")),
+            ),
+            (
+                "fallback_data_dirs.rs",
+                format!(
+                    "{:?}",
+                    self.fallback_data_dirs.as_deref().unwrap_or("/usr/local/share:/usr/share")
+                ),
+            ),
+            ("sysconf_dirs.rs", format!("{:?}", sysconf_dirs)),
+            ("unsafe_file_search.rs", self.unsafe_file_search.to_string()),
+        ];
+        for (file, value) in values {
+            fs::write(format!("{}/{}", dir, file), value).unwrap();
+        }
+    }
+}
+
+// Where the loader and the headers the bindings are generated from ended up. The sysconfdir is the install prefix's etc
+// directory, which the loader searches unless SYSCONFDIR is set, and isn't known for a prebuilt loader.
+struct Loader {
+    lib_dir: String,
+    include_dir: String,
+    sysconfdir: Option<String>,
+    test_binary_dir: Option<String>,
+    // Whether the library exports LOG_HOOK_SYMBOLS, which only loaders built by this crate do.
+    has_log_hook: bool,
+}
+
+// The entry points loader/log.c adds when built with LOADER_SYS_ENTRY_POINTS, and that src/loader_log.rs declares.
+const LOG_HOOK_SYMBOLS: &[&str] = &[
+    "vulkan_loader_sys_set_log_callback",
+    "vulkan_loader_sys_set_debug_level",
+    "vulkan_loader_sys_get_debug_level",
+];
+
+// The file the linker picks up for the library linked in main.
+fn library_file_name(target: &Target) -> &'static str {
+    if target.os == "windows" {
+        "vulkan-1.lib"
+    } else if target.is_musl() {
+        "libvulkan.a"
+    } else if target.os == "macos" {
+        "libvulkan.dylib"
+    } else {
+        "libvulkan.so"
+    }
+}
+
+// The symbol names are looked for in the library file itself, which finds them in the dynamic symbol table of a shared
+// library as well as in the symbol table of an import library or a static archive, without a tool for each format.
+fn exports_log_hook(library: &Path) -> bool {
+    let Ok(contents) = fs::read(library) else {
+        return false;
+    };
+    LOG_HOOK_SYMBOLS.iter().all(|symbol| {
+        let symbol = symbol.as_bytes();
+        contents.windows(symbol.len()).any(|window| window == symbol)
+    })
+}
+
+// A loader built outside of this crate. The headers are expected next to the libraries, as in an install prefix,
+// unless VULKAN_LOADER_SYS_INCLUDE_DIR says otherwise.
+fn prebuilt_loader(target: &Target) -> Option<Loader> {
+    let lib_dir = env::var("VULKAN_LOADER_SYS_LIB_DIR").ok()?;
+    let include_dir =
+        env::var("VULKAN_LOADER_SYS_INCLUDE_DIR").unwrap_or_else(|_| format!("{}/../include", lib_dir));
+
+    if !Path::new(&include_dir).join("vulkan/vulkan.h").is_file() {
+        panic!(
+            "{}/vulkan/vulkan.h doesn't exist, set VULKAN_LOADER_SYS_INCLUDE_DIR to the headers the loader in {} was \
+             built with.",
+            include_dir, lib_dir
+        );
+    }
+
+    if !target.sanitizers.is_empty() {
+        println!("cargo:warning=The loader in VULKAN_LOADER_SYS_LIB_DIR is used as it is, without sanitizers.");
+    }
+
+    let has_log_hook = exports_log_hook(&Path::new(&lib_dir).join(library_file_name(target)));
+    if !has_log_hook {
+        println!(
+            "cargo:warning=The loader in VULKAN_LOADER_SYS_LIB_DIR doesn't export the vulkan_loader_sys_* log hook, so \
+             loader_log::install, set_debug_level and debug_level are left out."
+        );
+    }
+
+    Some(Loader {
+        lib_dir,
+        include_dir,
+        sysconfdir: None,
+        test_binary_dir: None,
+        has_log_hook,
+    })
+}
+
+// Builds the loader and its headers with CMake, unless an earlier build with the same inputs left them in
+// VULKAN_LOADER_SYS_CACHE_DIR.
+fn build_loader(target: &Target, out_dir: &str, build_loader_tests: bool, search_paths: &SearchPaths) -> Loader {
+    let mut cmake_defines = BTreeMap::new();
+    cmake_defines.insert("LOADER_SYS_ENTRY_POINTS", String::from("ON"));
+    search_paths.add_cmake_defines(&mut cmake_defines);
+
+    if build_loader_tests {
+        cmake_defines.insert("BUILD_TESTS", String::from("ON"));
+    }
+
+    // musl toolchains rarely come with the X11 and Wayland development packages, which only the matching features need.
+    if target.is_musl() {
+        let enabled_by = |feature| String::from(if env::var(feature).is_ok() { "ON" } else { "OFF" });
+        cmake_defines.insert("BUILD_STATIC_LOADER", String::from("ON"));
+        cmake_defines.insert("BUILD_WSI_XLIB_SUPPORT", String::from("OFF"));
+        cmake_defines.insert("BUILD_WSI_XCB_SUPPORT", enabled_by("CARGO_FEATURE_XCB_EXTENSIONS"));
+        cmake_defines.insert("BUILD_WSI_WAYLAND_SUPPORT", enabled_by("CARGO_FEATURE_WAYLAND_EXTENSIONS"));
+    }
+
+    if !target.sanitizers.is_empty() {
+        enable_sanitizers(target, &mut cmake_defines);
+    }
+
+    // Unless a toolchain file says otherwise, CMake has to be told that it is cross compiling. CMAKE_SYSTEM_PROCESSOR
+    // also picks the loader's assembly trampolines: unknown_ext_chain_gas_aarch64.S, unknown_ext_chain_gas_x86.S, or
+    // the C fallback for every other architecture.
+    if target.is_cross() && !target.has_cmake_toolchain_file() {
+        if let Some(system_name) = cmake_system_name(&target.os) {
+            cmake_defines.insert("CMAKE_SYSTEM_NAME", String::from(system_name));
+        }
+        cmake_defines.insert("CMAKE_SYSTEM_PROCESSOR", target.arch.clone());
+    }
+
+    if let Some(sysroot) = &target.sysroot {
+        cmake_defines.insert("CMAKE_SYSROOT", sysroot.clone());
+        cmake_defines.insert("CMAKE_FIND_ROOT_PATH_MODE_PROGRAM", String::from("NEVER"));
+        cmake_defines.insert("CMAKE_FIND_ROOT_PATH_MODE_LIBRARY", String::from("ONLY"));
+        cmake_defines.insert("CMAKE_FIND_ROOT_PATH_MODE_INCLUDE", String::from("ONLY"));
+    }
+
+    // The test executables refer to the build tree and the test driver and layers in it, so builds that include them
+    // aren't cached.
+    let cache_entry = env::var("VULKAN_LOADER_SYS_CACHE_DIR")
+        .ok()
+        .filter(|_| !build_loader_tests)
+        .map(|cache_dir| format!("{}/{}", cache_dir, cache_key(target, &cmake_defines)));
+
+    if let Some(cache_entry) = &cache_entry {
+        if Path::new(cache_entry).join(CACHE_COMPLETE_MARKER).exists() {
+            return cached_loader(cache_entry, out_dir);
+        }
+    }
+    let cache_lock = cache_entry.as_deref().and_then(CacheLock::acquire);
+
+    fs::create_dir_all(format!("{}/deps", out_dir)).unwrap();
+    run_python("./scripts/update_deps.py", format!("{}/deps", out_dir).as_str());
+
+    let mut cmake_config = cmake::Config::new(".");
+    cmake_config.configure_arg(format!("-C{}/deps/helper.cmake", out_dir));
+    for (name, value) in &cmake_defines {
+        cmake_config.define(name, value);
+    }
+
+    // The WSI libraries are found through pkg-config, which has to look in the sysroot as well.
+    if let Some(sysroot) = &target.sysroot {
+        if env::var_os("PKG_CONFIG_SYSROOT_DIR").is_none() {
+            cmake_config.env("PKG_CONFIG_SYSROOT_DIR", sysroot);
+        }
+    }
+
+    // The loader is installed straight into the cache, which also makes the cache entry its install prefix, and so the
+    // SYSCONFDIR it searches, instead of an OUT_DIR that is gone after the next cargo clean.
+    if let (Some(cache_entry), Some(_)) = (&cache_entry, &cache_lock) {
+        cmake_config.define("CMAKE_INSTALL_PREFIX", cache_entry);
+    }
+
+    let cmake_out_dir = cmake_config.build();
+    let include_dir = format!("{}/deps/Vulkan-Headers/build/install/include", out_dir);
+
+    if let (Some(cache_entry), Some(cache_lock)) = (&cache_entry, cache_lock) {
+        copy_dir(Path::new(&include_dir), &Path::new(cache_entry).join("include"));
+        fs::write(Path::new(cache_entry).join(CACHE_COMPLETE_MARKER), "").unwrap();
+        drop(cache_lock);
+        return cached_loader(cache_entry, out_dir);
+    }
+
+    Loader {
+        lib_dir: format!("{}/lib", out_dir),
+        include_dir,
+        sysconfdir: Some(format!("{}/etc", cmake_out_dir.display())),
+        test_binary_dir: build_loader_tests.then(|| format!("{}/build/tests", cmake_out_dir.display())),
+        has_log_hook: true,
+    }
+}
+
+// Written last, so that a cache entry without it is still being built or was left behind by a build that failed.
+const CACHE_COMPLETE_MARKER: &str = ".complete";
+
+// The files and directories the loader is built from, including the dependency versions in scripts/known_good.json. The
+// other scripts are only used by hand, and the Markdown files are documentation.
+const LOADER_SOURCES: &[&str] = &[
+    "CMakeLists.txt",
+    "loader",
+    "scripts/CMakeLists.txt",
+    "scripts/known_good.json",
+    "scripts/update_deps.py",
+    "scripts/parse_asm_values.py",
+];
+const LOADER_SOURCES_SKIPPED_EXTENSIONS: &[&str] = &["md"];
+
+// 64-bit FNV-1a, which unlike the standard library's hashers gives the same result with every Rust version.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    // Terminated, so that two strings can't run into each other.
+    fn write_str(&mut self, string: &str) {
+        self.write(string.as_bytes());
+        self.write(&[0]);
+    }
+}
+
+// Everything that decides what the build produces: the sources, the CMake options, the target, the build type cmake
+// picks from the profile, and the compilers along with their flags.
+fn cache_key(target: &Target, cmake_defines: &BTreeMap<&str, String>) -> String {
+    let mut hasher = Fnv1a(0xcbf29ce484222325);
+
+    let mut sources = BuildInputs::default();
+    for source in LOADER_SOURCES {
+        sources.add(Path::new(source), LOADER_SOURCES_SKIPPED_EXTENSIONS);
+    }
+    for file in &sources.files {
+        hasher.write_str(file);
+        hasher.write(&fs::read(file).unwrap());
+    }
+
+    for (name, value) in cmake_defines {
+        hasher.write_str(name);
+        hasher.write_str(value);
+    }
+
+    hasher.write_str(&target.triple);
+    let build_vars = ["PROFILE", "OPT_LEVEL", "DEBUG", "CMAKE_GENERATOR", "PKG_CONFIG_SYSROOT_DIR"];
+    for var in build_vars.iter().map(|var| var.to_string()).chain(target.cmake_toolchain_file_vars()) {
+        hasher.write_str(&var);
+        hasher.write_str(&env::var(&var).unwrap_or_default());
+    }
+
+    for compiler in [cc::Build::new().get_compiler(), cc::Build::new().cpp(true).get_compiler()] {
+        hasher.write_str(&compiler.path().display().to_string());
+        for arg in compiler.args() {
+            hasher.write_str(&arg.to_string_lossy());
+        }
+    }
+
+    format!("{:016x}", hasher.0)
+}
+
+// Makes sure only one build fills a cache entry. The others build the loader for themselves instead of waiting. The
+// lock file is removed when the guard is dropped, which includes a panicking build script, and holds the process id of
+// the build, so that a lock left behind by a build that was killed can be taken over.
+struct CacheLock {
+    path: String,
+}
+
+impl CacheLock {
+    fn acquire(cache_entry: &str) -> Option<CacheLock> {
+        let path = format!("{}.lock", cache_entry);
+        fs::create_dir_all(Path::new(cache_entry).parent().unwrap()).unwrap();
+
+        let mut error = match CacheLock::create(&path) {
+            Ok(lock) => return Some(lock),
+            Err(error) => error,
+        };
+
+        if error.kind() == ErrorKind::AlreadyExists {
+            let contents = fs::read_to_string(&path).unwrap_or_default();
+            let mut fields = contents.split_whitespace();
+            let owner = fields.next().and_then(|pid| pid.parse::<u32>().ok());
+            let since = fields.next().and_then(|since| since.parse::<u64>().ok());
+            let stale = match owner {
+                // The process id may have been reused by now, but no build of the loader takes an hour.
+                Some(pid) => {
+                    !process_is_running(pid) || since.is_none_or(|since| unix_time().saturating_sub(since) > 3600)
+                }
+                // Another build has only just created it, unless it has been empty for longer than that takes.
+                None => fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age > Duration::from_secs(60)),
+            };
+
+            if !stale {
+                let holder = match (owner, since) {
+                    (Some(pid), Some(since)) => format!(
+                        "process {} started filling the same cache entry {} seconds ago",
+                        pid,
+                        unix_time().saturating_sub(since)
+                    ),
+                    _ => String::from("another build has just started filling the same cache entry"),
+                };
+                println!("cargo:warning=Building the loader without the cache, as {}.", holder);
+                return None;
+            }
+
+            let _ = fs::remove_file(&path);
+            match CacheLock::create(&path) {
+                Ok(lock) => return Some(lock),
+                Err(retry_error) => error = retry_error,
+            }
+        }
+
+        println!("cargo:warning=Building the loader without the cache, as {} can't be created: {}.", path, error);
+        None
+    }
+
+    // Writes the process id and the time it was created at, in seconds since the Unix epoch, into the lock file.
+    fn create(path: &str) -> std::io::Result<CacheLock> {
+        let mut file = fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+        let lock = CacheLock { path: path.to_string() };
+        write!(file, "{} {}", std::process::id(), unix_time())?;
+        Ok(lock)
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+// Processes that can't be checked count as running, which only costs the build the cache.
+fn process_is_running(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        Path::new(&format!("/proc/{}", pid)).exists()
+    } else if cfg!(unix) {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(Stdio::null())
+            .status()
+            .map_or(true, |status| status.success())
+    } else if cfg!(windows) {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+            .output()
+            .map_or(true, |output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)))
+    } else {
+        true
+    }
+}
+
+// The headers are used from the cache, but the libraries are copied into OUT_DIR: cargo only adds library directories
+// inside the target directory to the library path of `cargo run` and `cargo test`.
+fn cached_loader(cache_entry: &str, out_dir: &str) -> Loader {
+    copy_dir(
+        &Path::new(cache_entry).join("lib"),
+        &Path::new(out_dir).join("lib"),
+    );
+
+    Loader {
+        lib_dir: format!("{}/lib", out_dir),
+        include_dir: format!("{}/include", cache_entry),
+        sysconfdir: Some(format!("{}/etc", cache_entry)),
+        test_binary_dir: None,
+        has_log_hook: true,
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
         let entry = entry.unwrap();
-        if recursive && entry.path().is_dir() {
-            rerun_if_dir_changed(entry.path().display().to_string().as_str(), recursive);
-        } else {
-            println!("cargo:rerun-if-changed={}", entry.path().display());
+        let file_type = entry.file_type().unwrap();
+        let destination = to.join(entry.file_name());
+
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &destination);
+            continue;
         }
+
+        // Keeps libvulkan.so and libvulkan.so.1 links instead of three copies of the library.
+        #[cfg(unix)]
+        if file_type.is_symlink() {
+            let _ = fs::remove_file(&destination);
+            std::os::unix::fs::symlink(fs::read_link(entry.path()).unwrap(), &destination).unwrap();
+            continue;
+        }
+
+        fs::copy(entry.path(), &destination).unwrap();
+    }
+}
+
+// Rust and clang spell a few targets differently.
+fn clang_target(triple: &str) -> String {
+    if let Some(rest) = triple.strip_prefix("aarch64-apple-") {
+        format!("arm64-apple-{}", rest)
+    } else if let Some(rest) = triple.strip_prefix("riscv64gc-") {
+        format!("riscv64-{}", rest)
+    } else {
+        triple.to_string()
+    }
+}
+
+fn cmake_system_name(target_os: &str) -> Option<&'static str> {
+    match target_os {
+        "linux" => Some("Linux"),
+        "android" => Some("Android"),
+        "windows" => Some("Windows"),
+        "macos" => Some("Darwin"),
+        "ios" => Some("iOS"),
+        "freebsd" => Some("FreeBSD"),
+        _ => None,
+    }
+}
+
+// The provisional extensions, and the video std headers only they include. Their items are generated into a module of
+// their own, so that the documentation can tell them apart. The rest of the bindings are generated without
+// VK_ENABLE_BETA_EXTENSIONS, and the values vulkan_core.h only declares with it go into the module as well.
+const BETA_HEADERS: &str = r".*(vulkan_beta|vulkan_video_codec_h264std_encode|vulkan_video_codec_h265std_encode)\.h";
+
+// Everything vulkan_beta.h declares or needs that the other bindings, given as their source, don't have already. The
+// module they end up in imports the rest: all of vulkan_core.h, and the video std types the decode extensions pulled in
+// from the headers shared with encode. libvulkan exports none of the provisional commands.
+fn beta_bindings_builder(include_dir: &str, platform_defines: &[&str], bindings: &str) -> bindgen::Builder {
+    let items = binding_items(bindings);
+    let std_video_names = items
+        .keys()
+        .filter_map(|key| key.split(' ').nth(1))
+        .filter(|name| name.starts_with("StdVideo") || name.starts_with("STD_VIDEO_"))
+        .collect::<Vec<_>>();
+
+    let mut builder = bindgen::Builder::default()
+        .prepend_enum_name(false)
+        .clang_arg(format!("-I{}", include_dir))
+        .layout_tests(false)
+        .allowlist_file(BETA_HEADERS)
+        .blocklist_file(r".*vulkan_core\.h")
+        .blocklist_function(".*");
+
+    if !std_video_names.is_empty() {
+        builder = builder.blocklist_item(format!("({})", std_video_names.join("|")));
+    }
+
+    for define in platform_defines {
+        builder = builder.clang_arg(format!("-D{}", define));
+    }
+
+    builder
+}
+
+// The values vulkan_core.h adds to its enums and flags for the provisional extensions: every item of
+// `beta_core_bindings`, generated with VK_ENABLE_BETA_EXTENSIONS, that `bindings`, generated without it, lack. bindgen
+// can't leave single enum values out of a set of bindings, so these are written from the items binding_items reads
+// instead.
+fn beta_core_values(bindings: &str, beta_core_bindings: &str) -> String {
+    let items = binding_items(bindings);
+    let mut values = String::new();
+    for (key, item) in binding_items(beta_core_bindings) {
+        if items.contains_key(&key) {
+            continue;
+        }
+        assert!(
+            key.starts_with("const "),
+            "vulkan_core.h declares {} for the provisional extensions, which the beta_extensions module can't hold.",
+            key
+        );
+        values.push_str(&item);
+        values.push('\n');
+    }
+    values
+}
+
+// Everything but the header, the target and the sysroot, which differ between the bindings the crate uses and the ones
+// it compares them with.
+fn bindings_builder(include_dir: &str, platform_defines: &[&str]) -> bindgen::Builder {
+    // Declaring a command libvulkan doesn't export would compile fine and then fail to link, so only the exported ones
+    // become extern functions. Every command, exported or not, gets its PFN_ type.
+    let mut builder = bindgen::Builder::default()
+        .prepend_enum_name(false)
+        .clang_arg(format!("-I{}", include_dir))
+        .layout_tests(false)
+        .allowlist_type("Vk.*")
+        .allowlist_type("PFN_vk.*")
+        .allowlist_var("VK_.*")
+        .blocklist_file(BETA_HEADERS);
+
+    // The video extensions only pull in the std types their structs point at. The video feature adds the rest of the
+    // codec std headers, such as the enums and the array sizes, for filling in those structs. bindgen generates
+    // accessors for their bitfields.
+    if env::var("CARGO_FEATURE_VIDEO").is_ok() {
+        builder = builder.allowlist_type("StdVideo.*").allowlist_var("STD_VIDEO_.*");
+    }
+
+    for command in exported_commands(platform_defines) {
+        builder = builder.allowlist_function(command);
+    }
+
+    for define in platform_defines {
+        builder = builder.clang_arg(format!("-D{}", define));
+    }
+
+    builder
+}
+
+struct AbiType {
+    name: String,
+    is_union: bool,
+    fields: Vec<AbiField>,
+}
+
+struct AbiField {
+    name: String,
+    // The field's Rust type with the whitespace removed, such as `[::std::os::raw::c_char;256usize]`.
+    type_name: String,
+}
+
+// Keywords that bindgen renames by appending an underscore when they are used as field names.
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut",
+    "override", "priv", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+fn c_field_name(rust_name: &str) -> &str {
+    match rust_name.strip_suffix('_') {
+        Some(name) if RUST_KEYWORDS.contains(&name) => name,
+        _ => rust_name,
+    }
+}
+
+// Splits Rust source into identifiers and single punctuation characters, dropping comments and string literals.
+fn tokenize(source: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            let mut token = c.to_string();
+            while let Some(&next) = chars.peek() {
+                if !(next.is_alphanumeric() || next == '_') {
+                    break;
+                }
+                token.push(next);
+                chars.next();
+            }
+            tokens.push(token);
+        } else if c == '"' {
+            while let Some(next) = chars.next() {
+                match next {
+                    '\\' => {
+                        chars.next();
+                    }
+                    '"' => break,
+                    _ => {}
+                }
+            }
+        } else if c == '/' && chars.peek() == Some(&'/') {
+            for next in chars.by_ref() {
+                if next == '\n' {
+                    break;
+                }
+            }
+        } else if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut previous = ' ';
+            for next in chars.by_ref() {
+                if previous == '*' && next == '/' {
+                    break;
+                }
+                previous = next;
+            }
+        } else if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    tokens
+}
+
+// Joins the tokens of a field's type, which ends at the first comma or closing brace outside of any brackets.
+fn field_type(tokens: &[String]) -> String {
+    let mut type_name = String::new();
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token.as_str() {
+            "," | "}" if depth == 0 => break,
+            "[" | "(" | "<" => depth += 1,
+            // The `>` of a `->` does not close anything.
+            ">" if index > 0 && tokens[index - 1] == "-" => {}
+            "]" | ")" | ">" => depth -= 1,
+            _ => {}
+        }
+        // Keeps `unsafe extern fn` and the like apart.
+        let is_word = |text: &str| text.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if is_word(token) && type_name.chars().last().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            type_name.push(' ');
+        }
+        type_name.push_str(token);
+    }
+    type_name
+}
+
+// Finds every Vk and StdVideo struct and union in the generated bindings, together with their named fields. Bindgen
+// only formats its output when rustfmt is around, so this goes by tokens rather than by lines.
+fn parse_abi_types(bindings: &str) -> Vec<AbiType> {
+    let tokens = tokenize(bindings);
+    let mut types = Vec::new();
+
+    let mut i = 0;
+    while i + 3 < tokens.len() {
+        let is_type = tokens[i] == "pub" && (tokens[i + 1] == "struct" || tokens[i + 1] == "union");
+        if !is_type || tokens[i + 3] != "{" {
+            i += 1;
+            continue;
+        }
+
+        let name = tokens[i + 2].clone();
+        let is_union = tokens[i + 1] == "union";
+
+        let mut fields = Vec::new();
+        let mut depth = 0;
+        let mut j = i + 3;
+        while j < tokens.len() {
+            match tokens[j].as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                // Function pointer parameters never start with `pub`, so this only ever matches fields.
+                "pub" if j + 3 < tokens.len() && tokens[j + 2] == ":" && tokens[j + 3] != ":" => {
+                    fields.push(AbiField {
+                        name: tokens[j + 1].clone(),
+                        type_name: field_type(&tokens[j + 3..]),
+                    });
+                }
+                _ => {}
+            }
+            j += 1;
+        }
+
+        // Handles such as VkInstance_T are never defined in C, and bitfield storage has no C counterpart.
+        if (name.starts_with("Vk") || name.starts_with("StdVideo")) && !name.ends_with("_T") {
+            fields.retain(|field| !field.name.starts_with('_'));
+            types.push(AbiType { name, is_union, fields });
+        }
+
+        i = j;
+    }
+
+    types
+}
+
+// Writes a C probe that records the size, alignment and field offsets of every Vk and StdVideo struct and union as the
+// C compiler sees them, and the matching list of what the bindings say, for tests/abi.rs to compare.
+fn generate_abi_tests(
+    bindings: &str,
+    out_dir: &str,
+    include_dir: &str,
+    platform_defines: &[&str],
+    sysroot: Option<&str>,
+) {
+    let types = parse_abi_types(bindings);
+    let field_count: usize = types.iter().map(|abi_type| abi_type.fields.len()).sum();
+
+    let mut probe = String::new();
+    probe.push_str("// Generated by build.rs, do not edit.\n\n");
+    probe.push_str("#include <stddef.h>\n#include <vulkan/vulkan.h>\n\n");
+    probe.push_str("#if defined(_MSC_VER)\n#define ABI_ALIGNOF(type) __alignof(type)\n#else\n#define ABI_ALIGNOF(type) _Alignof(type)\n#endif\n\n");
+    probe.push_str("typedef struct { const char *name; size_t size; size_t align; } AbiProbeType;\n");
+    probe.push_str("typedef struct { const char *type_name; const char *name; size_t offset; size_t size; } AbiProbeField;\n\n");
+    probe.push_str("const AbiProbeType vulkan_loader_sys_abi_types[] = {\n");
+    for abi_type in &types {
+        probe.push_str(&format!(
+            "    {{\"{0}\", sizeof({0}), ABI_ALIGNOF({0})}},\n",
+            abi_type.name
+        ));
+    }
+    probe.push_str("};\n\nconst AbiProbeField vulkan_loader_sys_abi_fields[] = {\n");
+    for abi_type in &types {
+        for field in &abi_type.fields {
+            probe.push_str(&format!(
+                "    {{\"{0}\", \"{1}\", offsetof({0}, {1}), sizeof((({0} *)0)->{1})}},\n",
+                abi_type.name,
+                c_field_name(&field.name)
+            ));
+        }
+    }
+    probe.push_str("};\n");
+
+    let probe_file = format!("{}/abi_probe.c", out_dir);
+    fs::write(&probe_file, probe).unwrap();
+
+    let mut probe_build = cc::Build::new();
+    probe_build.file(&probe_file).include(include_dir);
+    for define in platform_defines {
+        probe_build.define(define, None);
+    }
+    if let Some(sysroot) = sysroot {
+        probe_build.flag(format!("--sysroot={}", sysroot).as_str());
+    }
+    probe_build.compile("vulkan_loader_sys_abi_probe");
+
+    let mut tests = String::new();
+    tests.push_str("// Generated by build.rs, do not edit.\n\n");
+    tests.push_str(&format!("const ABI_TYPE_COUNT: usize = {};\n", types.len()));
+    tests.push_str(&format!("const ABI_FIELD_COUNT: usize = {};\n\n", field_count));
+    tests.push_str("fn rust_types() -> Vec<(&'static str, usize, usize)> {\n    vec![\n");
+    for abi_type in &types {
+        tests.push_str(&format!(
+            "        (\"{0}\", ::std::mem::size_of::<{0}>(), ::std::mem::align_of::<{0}>()),\n",
+            abi_type.name
+        ));
+    }
+    tests.push_str("    ]\n}\n\n");
+    tests.push_str("fn rust_fields() -> Vec<(&'static str, &'static str, usize, usize)> {\n    vec![\n");
+    for abi_type in &types {
+        for field in &abi_type.fields {
+            let access = if abi_type.is_union {
+                format!("unsafe {{ &value.{} }}", field.name)
+            } else {
+                format!("&value.{}", field.name)
+            };
+            tests.push_str(&format!(
+                "        (\"{0}\", \"{1}\", ::std::mem::offset_of!({0}, {2}), field_size(|value: &{0}| {3})),\n",
+                abi_type.name,
+                c_field_name(&field.name),
+                field.name,
+                access
+            ));
+        }
+    }
+    tests.push_str("    ]\n}\n");
+
+    fs::write(format!("{}/abi_tests.rs", out_dir), tests).unwrap();
+}
+
+// Every command and function pointer type in the Vulkan headers uses VKAPI_CALL or VKAPI_PTR, which are stdcall on
+// 32-bit Windows and the C calling convention everywhere else. bindgen writes out whichever one the target uses, and so
+// that a callback can be declared the same way for every target, the bindings use "system" instead, which Rust defines
+// the same way.
+fn with_vkapi_abi(bindings: &bindgen::Bindings) -> String {
+    bindings
+        .to_string()
+        .replace("extern \"C\"", "extern \"system\"")
+        .replace("extern \"stdcall\"", "extern \"system\"")
+}
+
+// Writes the bindings for vulkan_beta.h, followed by the values from beta_core_values, which the beta_extensions module
+// in src/lib.rs includes.
+fn write_beta_bindings(bindings: &bindgen::Bindings, core_values: &str, out_dir: &str) {
+    fs::write(
+        format!("{}/beta_extensions.rs", out_dir),
+        format!("{}\n{}", with_vkapi_abi(bindings), core_values),
+    )
+    .expect("Failed to write bindings to a disk.");
+}
+
+// Writes the bindings together with the list of commands they declare as extern functions.
+fn write_bindings(bindings: &bindgen::Bindings, out_dir: &str) {
+    let mut bindgen_out_file = std::path::PathBuf::from_str(out_dir).unwrap();
+    bindgen_out_file.push("vulkan.rs");
+    fs::write(bindgen_out_file, with_vkapi_abi(bindings)).expect("Failed to write bindings to a disk.");
+
+    let externs = extern_functions(&bindings.to_string())
+        .iter()
+        .map(|name| format!("    \"{}\",\n", name))
+        .collect::<String>();
+    fs::write(format!("{}/exported_commands.rs", out_dir), format!("[\n{}]\n", externs)).unwrap();
+}
+
+// The headers documentation builds generate bindings from, with the macro that enables each of them in vulkan.h and
+// stand-ins for the window system types they refer to. Only pointers to these types are ever passed around, apart from
+// the handles and ids that are plain integers.
+const DOCS_HEADERS: &[(&str, &str, &str)] = &[
+    ("VK_USE_PLATFORM_ANDROID_KHR", "vulkan_android.h", ""),
+    ("VK_USE_PLATFORM_FUCHSIA", "vulkan_fuchsia.h", "typedef uint32_t zx_handle_t;"),
+    ("VK_USE_PLATFORM_IOS_MVK", "vulkan_ios.h", ""),
+    ("VK_USE_PLATFORM_MACOS_MVK", "vulkan_macos.h", ""),
+    ("VK_USE_PLATFORM_METAL_EXT", "vulkan_metal.h", ""),
+    ("VK_USE_PLATFORM_VI_NN", "vulkan_vi.h", ""),
+    ("VK_USE_PLATFORM_WAYLAND_KHR", "vulkan_wayland.h", ""),
+    (
+        "VK_USE_PLATFORM_WIN32_KHR",
+        "vulkan_win32.h",
+        "typedef void *HINSTANCE; typedef void *HWND; typedef void *HMONITOR; typedef void *HANDLE; \
+         typedef uint32_t DWORD; typedef const uint16_t *LPCWSTR; \
+         typedef struct _SECURITY_ATTRIBUTES SECURITY_ATTRIBUTES;",
+    ),
+    (
+        "VK_USE_PLATFORM_XCB_KHR",
+        "vulkan_xcb.h",
+        "typedef struct xcb_connection_t xcb_connection_t; typedef uint32_t xcb_window_t; \
+         typedef uint32_t xcb_visualid_t;",
+    ),
+    (
+        "VK_USE_PLATFORM_XLIB_KHR",
+        "vulkan_xlib.h",
+        "typedef struct _XDisplay Display; typedef unsigned long Window; typedef unsigned long VisualID;",
+    ),
+    ("VK_USE_PLATFORM_XLIB_XRANDR_EXT", "vulkan_xlib_xrandr.h", "typedef unsigned long RROutput;"),
+    (
+        "VK_USE_PLATFORM_DIRECTFB_EXT",
+        "vulkan_directfb.h",
+        "typedef struct _IDirectFB IDirectFB; typedef struct _IDirectFBSurface IDirectFBSurface;",
+    ),
+    (
+        "VK_USE_PLATFORM_GGP",
+        "vulkan_ggp.h",
+        "typedef uint32_t GgpStreamDescriptor; typedef uint64_t GgpFrameToken;",
+    ),
+    ("VK_USE_PLATFORM_SCREEN_QNX", "vulkan_screen.h", ""),
+];
+
+const DOCS_INCLUDE_DIR: &str = "vendor/Vulkan-Headers/include";
+
+// docs.rs has neither network access nor CMake, so documentation builds skip the loader and only generate the bindings,
+// from the headers in vendor/, with every window system so that all of it gets documented. The provisional extensions
+// are documented along with the beta-extensions feature.
+fn generate_docs_bindings(out_dir: &str) {
+    let include_dir = DOCS_INCLUDE_DIR;
+    if !Path::new(include_dir).join("vulkan/vulkan_core.h").exists() {
+        panic!("Documentation builds need the headers in {}, run scripts/vendor_headers.py first.", include_dir);
+    }
+
+    // vulkan.h would include the window systems' own headers, so the platform headers are included one by one instead.
+    let mut header = String::from("#include <vulkan/vulkan_core.h>\n");
+    for (_, platform_header, stand_ins) in DOCS_HEADERS {
+        header.push_str(&format!("{}\n#include <vulkan/{}>\n", stand_ins, platform_header));
+    }
+    let defines: Vec<&str> = DOCS_HEADERS.iter().map(|(define, _, _)| *define).collect();
+
+    let bindings = bindings_builder(include_dir, &defines)
+        .header_contents("vulkan_docs.h", &header)
+        .generate()
+        .expect("Failed to generate bindings for the vendored headers!");
+    write_bindings(&bindings, out_dir);
+
+    if env::var("CARGO_FEATURE_BETA_EXTENSIONS").is_ok() {
+        let beta_defines = [defines.as_slice(), &["VK_ENABLE_BETA_EXTENSIONS"]].concat();
+        let beta_core_bindings = bindings_builder(include_dir, &beta_defines)
+            .header_contents("vulkan_docs.h", &header)
+            .generate()
+            .expect("Failed to generate bindings for the vendored headers!");
+
+        header.push_str("#include <vulkan/vulkan_beta.h>\n");
+        let beta_bindings = beta_bindings_builder(include_dir, &beta_defines, &bindings.to_string())
+            .header_contents("vulkan_docs.h", &header)
+            .generate()
+            .expect("Failed to generate bindings for the vendored vulkan_beta.h!");
+        let core_values = beta_core_values(&bindings.to_string(), &beta_core_bindings.to_string());
+        write_beta_bindings(&beta_bindings, &core_values, out_dir);
+    }
+
+    if env::var("CARGO_FEATURE_TOOLS").is_ok() {
+        generate_vkinfo_support(&bindings.to_string(), out_dir);
+    }
+}
+
+// Enables the parts of src/loader_log.rs that call LOG_HOOK_SYMBOLS. The `log_hook` metadata does the same for the
+// test-support crate, through DEP_VULKAN_LOG_HOOK in its build script.
+fn emit_log_hook(has_log_hook: bool) {
+    println!("cargo:rustc-check-cfg=cfg(loader_log_hook)");
+    if has_log_hook {
+        println!("cargo:rustc-cfg=loader_log_hook");
+        println!("cargo:log_hook=1");
     }
 }
 
 fn main() {
-    // Files that may affect the build of the project.
-    println!("cargo:rerun-if-changed=CMakeLists.txt");
-    println!("cargo:rerun-if-changed=.gn");
-    println!("cargo:rerun-if-changed=BUILD.gn");
-    println!("cargo:rerun-if-changed=vulkan.symbols.api");
-    
-    // Directories that may affect the build of the project.
-    println!("cargo:rerun-if-changed=scripts");
-    println!("cargo:rerun-if-changed=fuchsia");
-    println!("cargo:rerun-if-changed=cmake");
-    println!("cargo:rerun-if-changed=build-gn");
-    println!("cargo:rerun-if-changed=build-qnx");
-    
-    // The loader folder timestamp changes because of CMake configuration files
-    // being written and deleted to that directory, so we have to use this wei-
-    // rd hack.
-    rerun_if_dir_changed("loader", false);
-    
     let out_dir = env::var("OUT_DIR").unwrap();
-    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
-    
-    fs::create_dir_all(format!("{}/deps", out_dir)).unwrap();
-    run_python("./scripts/update_deps.py", format!("{}/deps", out_dir).as_str());
+    let mut build_inputs = BuildInputs::new(&env::var("TARGET").unwrap());
 
-    cmake::Config::new(".")
-        .configure_arg(format!("-C{}/deps/helper.cmake", out_dir))
-        .build();
+    if env::var_os("DOCS_RS").is_some() || env::var("CARGO_FEATURE_DOCS_ONLY").is_ok() {
+        build_inputs.add(Path::new(DOCS_INCLUDE_DIR), &[]);
+        build_inputs.emit(&out_dir);
+        SearchPaths::from_env().write(&out_dir, None);
+        emit_log_hook(true);
+        generate_docs_bindings(&out_dir);
+        return;
+    }
 
-    println!("cargo:rustc-link-search={}/lib", out_dir);
+    let target = Target::from_env();
+    let target_os = target.os.as_str();
+
+    // The loader's own googletest suite, together with the test driver, test layers and the shim library it needs, is
+    // only built on request as it adds considerably to the build time. The shim intercepts the shared loader's calls,
+    // which doesn't work with the static loader built for musl.
+    let build_loader_tests =
+        env::var("CARGO_FEATURE_LOADER_TESTS").is_ok() && target_os == "linux" && !target.is_musl();
+
+    let prebuilt_loader = prebuilt_loader(&target);
+    match &prebuilt_loader {
+        Some(loader) => {
+            for header_dir in ["vulkan", "vk_video"] {
+                build_inputs.add(&Path::new(&loader.include_dir).join(header_dir), &[]);
+            }
+            // A replaced library may add or lose the log hook.
+            build_inputs.add(&Path::new(&loader.lib_dir).join(library_file_name(&target)), &[]);
+        }
+        None => {
+            for source in LOADER_SOURCES {
+                build_inputs.add(Path::new(source), LOADER_SOURCES_SKIPPED_EXTENSIONS);
+            }
+            // The loader's own tests, without the Rust ones, which cargo tracks itself.
+            if build_loader_tests {
+                build_inputs.add(Path::new("tests"), &["rs", "md"]);
+            }
+        }
+    }
+    build_inputs.emit(&out_dir);
+
+    // For a prebuilt loader, the variables describe how it was built.
+    let mut search_paths = SearchPaths::from_env();
+    // The test-support crate's FrameworkEnvironment links this directory to one of its own, which keeps the implicit
+    // layers installed in /etc/vulkan out of the tests.
+    if build_loader_tests && prebuilt_loader.is_none() && search_paths.sysconfdir.is_none() {
+        let test_sysconfdir = format!("{}/test_sysconfdir", out_dir);
+        println!("cargo:rustc-env=VULKAN_LOADER_SYS_TEST_SYSCONFDIR={}", test_sysconfdir);
+        search_paths.sysconfdir = Some(test_sysconfdir);
+    }
+    let loader = match prebuilt_loader {
+        Some(loader) => loader,
+        None => build_loader(&target, &out_dir, build_loader_tests, &search_paths),
+    };
+    search_paths.write(&out_dir, loader.sysconfdir.as_deref());
+    emit_log_hook(loader.has_log_hook);
+
+    if let Some(test_binary_dir) = &loader.test_binary_dir {
+        // Lets tests/loader_regression.rs find the test executables.
+        println!("cargo:rustc-env=VULKAN_LOADER_SYS_TEST_BINARY_DIR={}", test_binary_dir);
+    } else if env::var("CARGO_FEATURE_LOADER_TESTS").is_ok() {
+        println!(
+            "cargo:warning=The loader-tests feature is only supported on Linux with glibc, when the crate builds the \
+             loader itself, and has been ignored."
+        );
+    }
+
+    println!("cargo:rustc-link-search={}", loader.lib_dir);
+    // Lets tests/exported_commands.rs look at the library's dynamic symbol table.
+    println!("cargo:rustc-env=VULKAN_LOADER_SYS_LINKED_LIB_DIR={}", loader.lib_dir);
 
     if target_os == "windows" {
         println!("cargo:rustc-link-lib=vulkan-1");
+    } else if target.is_musl() {
+        // musl's libc also provides dlopen, pthreads and libm, so the archive needs nothing else.
+        println!("cargo:rustc-link-lib=static=vulkan");
     } else if target_os == "linux" {
         println!("cargo:rustc-link-lib=vulkan");
     }
 
-    let mut bindgen_builder = bindgen::Builder::default()
-        .header(format!("{}/deps/Vulkan-Headers/build/install/include/vulkan/vulkan.h", out_dir.as_str()))
-        .prepend_enum_name(false)
-        .clang_arg(format!("-I{}/deps/Vulkan-Headers/build/install/include", out_dir))
-        .layout_tests(false)
-        .allowlist_type("Vk.*")
-        .allowlist_function("vk.*")
-        .allowlist_var("VK_.*");
+    let include_dir = loader.include_dir;
+
+    // The platform defines decide which parts of vulkan.h end up in the bindings, and the ABI probe has to see the
+    // same header as bindgen does.
+    let mut platform_defines = Vec::new();
 
     if target_os == "windows" {
-        bindgen_builder = bindgen_builder.clang_arg("-DVK_USE_PLATFORM_WIN32_KHR");
+        platform_defines.push("VK_USE_PLATFORM_WIN32_KHR");
     }
 
     if env::var("CARGO_FEATURE_XCB_EXTENSIONS").is_ok() {
-        bindgen_builder = bindgen_builder.clang_arg("-DVK_USE_PLATFORM_XCB_KHR");
+        platform_defines.push("VK_USE_PLATFORM_XCB_KHR");
     }
 
     if env::var("CARGO_FEATURE_WAYLAND_EXTENSIONS").is_ok() {
-        bindgen_builder = bindgen_builder.clang_arg("-DVK_USE_PLATFORM_WAYLAND_KHR");
+        platform_defines.push("VK_USE_PLATFORM_WAYLAND_KHR");
     }
 
-    let mut bindgen_out_file = std::path::PathBuf::from_str(out_dir.as_str()).unwrap();
-    bindgen_out_file.push("vulkan.rs");
-    bindgen_builder
+    // The loader itself is always built with the provisional extensions, see CMakeLists.txt. Only their bindings and
+    // the ABI probe see VK_ENABLE_BETA_EXTENSIONS.
+    let beta_extensions = env::var("CARGO_FEATURE_BETA_EXTENSIONS").is_ok();
+    let beta_defines = [platform_defines.as_slice(), &["VK_ENABLE_BETA_EXTENSIONS"]].concat();
+
+    // Lets tests/exported_commands.rs tell the window system commands the loader exports but the bindings leave out
+    // from commands that are missing by mistake.
+    let undeclared_platform_commands = PLATFORM_EXPORTS
+        .iter()
+        .filter(|(define, _)| !platform_defines.contains(define))
+        .flat_map(|(_, commands)| commands.iter().copied())
+        .collect::<Vec<_>>();
+    println!(
+        "cargo:rustc-env=VULKAN_LOADER_SYS_UNDECLARED_PLATFORM_COMMANDS={}",
+        undeclared_platform_commands.join(",")
+    );
+
+    // Without an explicit target bindgen only passes one to clang when it differs from the host, and the sysroot is
+    // needed for the target's C library headers.
+    let for_target = |mut builder: bindgen::Builder| {
+        builder = builder
+            .header(format!("{}/vulkan/vulkan.h", include_dir))
+            .clang_arg(format!("--target={}", clang_target(&target.triple)));
+        if let Some(sysroot) = &target.sysroot {
+            builder = builder.clang_arg(format!("--sysroot={}", sysroot));
+        }
+        builder
+    };
+
+    let bindings = for_target(bindings_builder(&include_dir, &platform_defines))
         .generate()
-        .expect("Failed to generate bindings for vulkan/vulkan.h!")
-        .write_to_file(bindgen_out_file.to_str().unwrap())
-        .expect("Failed to write bindings to a disk.");
+        .expect("Failed to generate bindings for vulkan/vulkan.h!");
+    write_bindings(&bindings, &out_dir);
+
+    // The ABI probe sees the provisional structs as well.
+    let mut all_bindings = bindings.to_string();
+    if beta_extensions {
+        let beta_core_bindings = for_target(bindings_builder(&include_dir, &beta_defines))
+            .generate()
+            .expect("Failed to generate bindings for vulkan/vulkan.h!");
+        let beta_bindings = for_target(beta_bindings_builder(&include_dir, &beta_defines, &all_bindings))
+            .generate()
+            .expect("Failed to generate bindings for vulkan/vulkan_beta.h!");
+        let core_values = beta_core_values(&all_bindings, &beta_core_bindings.to_string());
+        write_beta_bindings(&beta_bindings, &core_values, &out_dir);
+        all_bindings.push_str(&beta_bindings.to_string());
+    }
+
+    if env::var("CARGO_FEATURE_ABI_TESTS").is_ok() {
+        generate_abi_tests(
+            &all_bindings,
+            &out_dir,
+            &include_dir,
+            if beta_extensions { &beta_defines } else { &platform_defines },
+            target.sysroot.as_deref(),
+        );
+        generate_bindings_comparison(&target, &out_dir, &include_dir, &platform_defines);
+    }
+
+    if env::var("CARGO_FEATURE_TOOLS").is_ok() {
+        generate_vkinfo_support(&bindings.to_string(), &out_dir);
+    }
+}
+
+// Targets whose bindings tests/abi.rs compares with the ones for the target being built, unless
+// VULKAN_LOADER_SYS_COMPARE_TARGETS lists others.
+const COMPARED_TARGETS: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "aarch64-unknown-linux-gnu",
+    "i686-unknown-linux-gnu",
+    "armv7-unknown-linux-gnueabihf",
+];
+
+// Every top level item in the bindings, keyed by its kind and name, with its tokens joined by spaces. Functions are
+// taken out of their extern blocks, and impl blocks are left out as they only follow from the items.
+fn binding_items(bindings: &str) -> BTreeMap<String, String> {
+    let tokens = tokenize(bindings);
+    let mut items = BTreeMap::new();
+
+    let mut i = 0;
+    while i + 2 < tokens.len() {
+        match tokens[i].as_str() {
+            "extern" if tokens[i + 1] == "{" => i += 2,
+            "impl" => {
+                let mut depth = 0;
+                while i < tokens.len() {
+                    match tokens[i].as_str() {
+                        "{" => depth += 1,
+                        "}" => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            "pub" => {
+                let key = format!("{} {}", tokens[i + 1], tokens[i + 2]);
+                let braced = tokens[i + 1] == "struct" || tokens[i + 1] == "union";
+                let mut depth = 0;
+                let mut end = i;
+                while end < tokens.len() {
+                    match tokens[end].as_str() {
+                        "{" | "(" | "[" => depth += 1,
+                        "}" | ")" | "]" => {
+                            depth -= 1;
+                            if depth == 0 && braced && tokens[end] == "}" {
+                                break;
+                            }
+                        }
+                        ";" if depth == 0 => break,
+                        _ => {}
+                    }
+                    end += 1;
+                }
+                items.insert(key, tokens[i..=end.min(tokens.len() - 1)].join(" "));
+                i = end + 1;
+            }
+            _ => i += 1,
+        }
+    }
+
+    items
+}
+
+// Generates the bindings for other targets and writes down every item that differs from the bindings for the target
+// being built, for tests/abi.rs to judge.
+fn generate_bindings_comparison(target: &Target, out_dir: &str, include_dir: &str, platform_defines: &[&str]) {
+    let compared: Vec<String> = match env::var("VULKAN_LOADER_SYS_COMPARE_TARGETS") {
+        Ok(targets) => targets
+            .split(',')
+            .map(str::trim)
+            .filter(|triple| !triple.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(_) => COMPARED_TARGETS.iter().map(|triple| triple.to_string()).collect(),
+    };
+
+    // Freestanding, so that none of the targets needs a C library installed: vulkan.h only wants stdint.h and stddef.h,
+    // which come with clang.
+    let items_for = |triple: &str| {
+        let bindings = bindings_builder(include_dir, platform_defines)
+            .header(format!("{}/vulkan/vulkan.h", include_dir))
+            .clang_arg(format!("--target={}", clang_target(triple)))
+            .clang_arg("-ffreestanding")
+            .generate()
+            .unwrap_or_else(|_| panic!("Failed to generate bindings for {}!", triple));
+        binding_items(&bindings.to_string())
+    };
+    let ours = items_for(&target.triple);
+
+    let mut comparison = String::new();
+    comparison.push_str("// Generated by build.rs, do not edit.\n\n");
+    comparison.push_str("const COMPARED_TARGETS: &[&str] = &[\n");
+    for triple in &compared {
+        comparison.push_str(&format!("    {:?},\n", triple));
+    }
+    comparison.push_str("];\n\n");
+
+    // The target, the item, and the item in our bindings and in theirs, empty when it is missing.
+    comparison.push_str("const BINDING_DIFFERENCES: &[(&str, &str, &str, &str)] = &[\n");
+    for triple in compared.iter().filter(|triple| **triple != target.triple) {
+        let theirs = items_for(triple);
+        let keys: BTreeSet<&String> = ours.keys().chain(theirs.keys()).collect();
+        for key in keys {
+            let (our_item, their_item) = (ours.get(key), theirs.get(key));
+            if our_item != their_item {
+                comparison.push_str(&format!(
+                    "    ({:?}, {:?}, {:?}, {:?}),\n",
+                    triple,
+                    key,
+                    our_item.map_or("", String::as_str),
+                    their_item.map_or("", String::as_str)
+                ));
+            }
+        }
+    }
+    comparison.push_str("];\n");
+
+    fs::write(format!("{}/bindings_comparison.rs", out_dir), comparison).unwrap();
+}
+
+// The structs vkinfo prints. Every Vk struct reachable through their fields is printed as well.
+const VKINFO_ROOT_TYPES: &[&str] = &[
+    "VkPhysicalDeviceProperties",
+    "VkPhysicalDeviceFeatures",
+    "VkPhysicalDeviceVulkan11Features",
+    "VkPhysicalDeviceVulkan11Properties",
+    "VkPhysicalDeviceVulkan12Features",
+    "VkPhysicalDeviceVulkan12Properties",
+    "VkPhysicalDeviceVulkan13Features",
+    "VkPhysicalDeviceVulkan13Properties",
+    "VkQueueFamilyProperties",
+    "VkMemoryType",
+    "VkMemoryHeap",
+    "VkFormatProperties",
+    "VkDisplayPropertiesKHR",
+    "VkDisplayPlanePropertiesKHR",
+];
+
+// Enums that vkinfo needs by name on top of the ones its structs use.
+const VKINFO_EXTRA_ENUMS: &[&str] = &["VkFormat", "VkResult"];
+
+// `VkPhysicalDeviceType` becomes `VK_PHYSICAL_DEVICE_TYPE_NAMES`.
+fn names_table(type_name: &str) -> String {
+    let mut table = String::new();
+    let mut previous = ' ';
+    for c in type_name.chars() {
+        if c.is_ascii_uppercase() && (previous.is_ascii_lowercase() || previous.is_ascii_digit()) {
+            table.push('_');
+        }
+        table.push(c.to_ascii_uppercase());
+        previous = c;
+    }
+    table + "_NAMES"
+}
+
+// Writes the `Dump` impls and the enum name tables that src/bin/vkinfo.rs includes. Bitmask types are printed by the
+// names of their bits when a matching FlagBits enum exists, `VkBool32` as a bool and the rest as plain values.
+fn generate_vkinfo_support(bindings: &str, out_dir: &str) {
+    let tokens = tokenize(bindings);
+    let types = parse_abi_types(bindings);
+
+    // Constants typed with an enum, in declaration order, and aliases that are really pointers, such as the handles.
+    let mut enum_values: Vec<(String, String, i64)> = Vec::new();
+    let mut pointer_aliases = Vec::new();
+    for i in 0..tokens.len().saturating_sub(6) {
+        if tokens[i] != "pub" {
+            continue;
+        }
+        if tokens[i + 1] == "const" && tokens[i + 3] == ":" && tokens[i + 4].starts_with("Vk") && tokens[i + 5] == "=" {
+            let (negative, literal) = match tokens[i + 6].as_str() {
+                "-" => (true, tokens.get(i + 7).map_or("", String::as_str)),
+                literal => (false, literal),
+            };
+            let digits: String = literal.chars().take_while(char::is_ascii_digit).collect();
+            if let Ok(value) = digits.parse::<u64>() {
+                let value = if negative { -(value as i64) } else { value as i64 };
+                enum_values.push((tokens[i + 4].clone(), tokens[i + 2].clone(), value));
+            }
+        } else if tokens[i + 1] == "type" && tokens[i + 3] == "=" && tokens[i + 4] == "*" {
+            pointer_aliases.push(tokens[i + 2].clone());
+        }
+    }
+    let has_names = |type_name: &str| enum_values.iter().any(|(enum_type, _, _)| enum_type == type_name);
+
+    let find_type = |name: &str| types.iter().find(|abi_type| abi_type.name == name && !abi_type.is_union);
+    let is_skipped = |type_name: &str| {
+        type_name.contains('*')
+            || tokenize(type_name).iter().any(|token| {
+                token == "Option"
+                    || token.starts_with("StdVideo")
+                    || token.starts_with("__Bindgen")
+                    || pointer_aliases.contains(token)
+                    || types.iter().any(|abi_type| abi_type.is_union && &abi_type.name == token)
+            })
+    };
+
+    let mut dumped: Vec<&AbiType> = Vec::new();
+    let mut pending: Vec<&str> = VKINFO_ROOT_TYPES.to_vec();
+    while let Some(name) = pending.pop() {
+        if dumped.iter().any(|abi_type| abi_type.name == name) {
+            continue;
+        }
+        let Some(abi_type) = find_type(name) else {
+            continue;
+        };
+        for field in &abi_type.fields {
+            if is_skipped(&field.type_name) {
+                continue;
+            }
+            for token in tokenize(&field.type_name) {
+                if let Some(nested) = find_type(&token) {
+                    pending.push(&nested.name);
+                }
+            }
+        }
+        dumped.push(abi_type);
+    }
+    dumped.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut tables: Vec<String> = VKINFO_EXTRA_ENUMS.iter().map(|name| name.to_string()).collect();
+    let mut impls = String::new();
+    for abi_type in &dumped {
+        impls.push_str(&format!("impl Dump for {} {{\n", abi_type.name));
+        impls.push_str("    fn dump(&self) -> Value {\n        let mut object = Map::new();\n");
+        for field in &abi_type.fields {
+            let name = &field.name;
+            let type_name = field.type_name.as_str();
+            if name == "sType" || name == "pNext" || is_skipped(type_name) {
+                continue;
+            }
+            let flag_bits = type_name.rfind("Flags").map(|at| format!("{}FlagBits{}", &type_name[..at], &type_name[at + 5..]));
+            let value = if type_name == "VkBool32" {
+                format!("Value::Bool(self.{} != 0)", name)
+            } else if name == "apiVersion" {
+                format!("api_version(self.{})", name)
+            } else if type_name.starts_with('[') && type_name.contains("c_char") {
+                format!("c_chars(&self.{})", name)
+            } else if type_name == "[u8;8usize]" || type_name == "[u8;16usize]" {
+                format!("hex(&self.{})", name)
+            } else if has_names(type_name) {
+                tables.push(type_name.to_string());
+                format!("enum_name({}, self.{} as i64)", names_table(type_name), name)
+            } else if let Some(flag_bits) = flag_bits.filter(|flag_bits| has_names(flag_bits)) {
+                let table = names_table(&flag_bits);
+                tables.push(flag_bits);
+                format!("flag_names({}, self.{} as u64)", table, name)
+            } else {
+                format!("Dump::dump(&self.{})", name)
+            };
+            impls.push_str(&format!("        object.insert(\"{}\".to_string(), {});\n", name, value));
+        }
+        impls.push_str("        Value::Object(object)\n    }\n}\n\n");
+    }
+    tables.sort();
+    tables.dedup();
+
+    let mut support = String::new();
+    support.push_str("// Generated by build.rs, do not edit.\n\n");
+    for table in &tables {
+        support.push_str(&format!("const {}: &[(i64, &str)] = &[\n", names_table(table)));
+        let mut seen = Vec::new();
+        for (_, name, value) in enum_values.iter().filter(|(enum_type, _, _)| enum_type == table) {
+            // Aliases come after the name they alias, so the first name is the one to print.
+            if name.ends_with("_MAX_ENUM") || seen.contains(value) {
+                continue;
+            }
+            seen.push(*value);
+            support.push_str(&format!("    ({}, \"{}\"),\n", value, name));
+        }
+        support.push_str("];\n\n");
+    }
+    support.push_str(&impls);
+
+    fs::write(format!("{}/vkinfo.rs", out_dir), support).unwrap();
 }
@@ -0,0 +1,590 @@
+//! A headless compute example: it fills a buffer with numbers, squares every one of them in a compute shader and reads
+//! the results back. There is no window and no surface, so it runs anywhere a Vulkan driver with a compute queue is
+//! installed, including software drivers such as lavapipe or SwiftShader (point `VK_DRIVER_FILES` at their manifest).
+//!
+//! Like the triangle example, everything happens in one place, in the order Vulkan wants it: instance, physical device,
+//! logical device and queue, buffer and memory, descriptor set, pipeline, command buffer, submission and readback. The
+//! shader is `examples/compute/shaders/square.comp`, compiled to SPIR-V next to it.
+
+use std::{
+    ffi::CStr,
+    fmt,
+    mem::{size_of, MaybeUninit},
+    ptr::{null, null_mut},
+};
+
+use vulkan_loader_sys::{instance::Instance, *};
+
+const SHADER: &[u8] = include_bytes!("compute/shaders/square.comp.spv");
+
+// Has to match `local_size_x` in the shader.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Why the values could not be squared.
+#[derive(Debug)]
+pub enum Error {
+    /// There is no driver, or no device with a compute queue.
+    NoDevice(String),
+    /// A Vulkan call failed.
+    Vulkan(&'static str, VkResult),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NoDevice(reason) => write!(f, "no usable device: {}", reason),
+            Error::Vulkan(function, result) => write!(f, "{} failed with {}", function, result),
+        }
+    }
+}
+
+fn check(function: &'static str, result: VkResult) -> Result<(), Error> {
+    if result == VK_SUCCESS {
+        Ok(())
+    } else {
+        Err(Error::Vulkan(function, result))
+    }
+}
+
+/// Everything created from the logical device, destroyed in reverse order when it goes out of scope. Destroying a null
+/// handle does nothing, so whatever was created before an error is cleaned up as well.
+struct Device {
+    handle: VkDevice,
+    buffer: VkBuffer,
+    memory: VkDeviceMemory,
+    descriptor_set_layout: VkDescriptorSetLayout,
+    descriptor_pool: VkDescriptorPool,
+    shader_module: VkShaderModule,
+    pipeline_layout: VkPipelineLayout,
+    pipeline: VkPipeline,
+    command_pool: VkCommandPool,
+    fence: VkFence,
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        unsafe {
+            vkDeviceWaitIdle(self.handle);
+            vkDestroyFence(self.handle, self.fence, null());
+            vkDestroyCommandPool(self.handle, self.command_pool, null());
+            vkDestroyPipeline(self.handle, self.pipeline, null());
+            vkDestroyPipelineLayout(self.handle, self.pipeline_layout, null());
+            vkDestroyShaderModule(self.handle, self.shader_module, null());
+            vkDestroyDescriptorPool(self.handle, self.descriptor_pool, null());
+            vkDestroyDescriptorSetLayout(self.handle, self.descriptor_set_layout, null());
+            vkDestroyBuffer(self.handle, self.buffer, null());
+            vkFreeMemory(self.handle, self.memory, null());
+            vkDestroyDevice(self.handle, null());
+        }
+    }
+}
+
+/// Squares every value on the first device that has a compute queue.
+pub fn square(values: &[u32]) -> Result<Vec<u32>, Error> {
+    unsafe {
+        let instance = {
+            let application_info = VkApplicationInfo {
+                sType: VK_STRUCTURE_TYPE_APPLICATION_INFO,
+                pNext: null(),
+                pApplicationName: c"Compute Example".as_ptr(),
+                applicationVersion: 1,
+                pEngineName: null(),
+                engineVersion: 0,
+                apiVersion: VK_MAKE_API_VERSION(0, 1, 0, 0),
+            };
+
+            // No extensions at all: without presentation there is nothing to ask for.
+            let create_info = VkInstanceCreateInfo {
+                sType: VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                pApplicationInfo: &application_info,
+                enabledLayerCount: 0,
+                ppEnabledLayerNames: null(),
+                enabledExtensionCount: 0,
+                ppEnabledExtensionNames: null(),
+            };
+
+            match Instance::new(&create_info) {
+                Ok(instance) => instance,
+                Err(VK_ERROR_INCOMPATIBLE_DRIVER) => {
+                    return Err(Error::NoDevice(String::from(
+                        "no Vulkan driver is installed",
+                    )))
+                }
+                Err(result) => return Err(Error::Vulkan("vkCreateInstance", result)),
+            }
+        };
+
+        let (physical_device, queue_family) = {
+            let mut physical_device_count = 0;
+            check(
+                "vkEnumeratePhysicalDevices",
+                vkEnumeratePhysicalDevices(
+                    instance.handle(),
+                    &mut physical_device_count,
+                    null_mut(),
+                ),
+            )?;
+            let mut physical_devices = Vec::with_capacity(physical_device_count as usize);
+            check(
+                "vkEnumeratePhysicalDevices",
+                vkEnumeratePhysicalDevices(
+                    instance.handle(),
+                    &mut physical_device_count,
+                    physical_devices.as_mut_ptr(),
+                ),
+            )?;
+            physical_devices.set_len(physical_device_count as usize);
+
+            physical_devices
+                .iter()
+                .find_map(|&physical_device| {
+                    let mut queue_family_count = 0;
+                    vkGetPhysicalDeviceQueueFamilyProperties(
+                        physical_device,
+                        &mut queue_family_count,
+                        null_mut(),
+                    );
+                    let mut queue_families = Vec::with_capacity(queue_family_count as usize);
+                    vkGetPhysicalDeviceQueueFamilyProperties(
+                        physical_device,
+                        &mut queue_family_count,
+                        queue_families.as_mut_ptr(),
+                    );
+                    queue_families.set_len(queue_family_count as usize);
+
+                    queue_families
+                        .iter()
+                        .position(|family| family.queueFlags & VK_QUEUE_COMPUTE_BIT != 0)
+                        .map(|index| (physical_device, index as u32))
+                })
+                .ok_or_else(|| {
+                    Error::NoDevice(format!(
+                        "none of the {} physical devices has a compute queue",
+                        physical_device_count
+                    ))
+                })?
+        };
+
+        let mut properties = MaybeUninit::uninit();
+        vkGetPhysicalDeviceProperties(physical_device, properties.as_mut_ptr());
+        let properties = properties.assume_init();
+        println!(
+            "[INFO]: Using {}.",
+            CStr::from_ptr(properties.deviceName.as_ptr()).to_string_lossy()
+        );
+
+        let mut device = {
+            let queue_priority = 1.0;
+            let queue_create_info = VkDeviceQueueCreateInfo {
+                sType: VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                queueFamilyIndex: queue_family,
+                queueCount: 1,
+                pQueuePriorities: &queue_priority,
+            };
+
+            let create_info = VkDeviceCreateInfo {
+                sType: VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                queueCreateInfoCount: 1,
+                pQueueCreateInfos: &queue_create_info,
+                enabledLayerCount: 0,
+                ppEnabledLayerNames: null(),
+                enabledExtensionCount: 0,
+                ppEnabledExtensionNames: null(),
+                pEnabledFeatures: null(),
+            };
+
+            let mut handle = null_mut();
+            check(
+                "vkCreateDevice",
+                vkCreateDevice(physical_device, &create_info, null(), &mut handle),
+            )?;
+
+            Device {
+                handle,
+                buffer: null_mut(),
+                memory: null_mut(),
+                descriptor_set_layout: null_mut(),
+                descriptor_pool: null_mut(),
+                shader_module: null_mut(),
+                pipeline_layout: null_mut(),
+                pipeline: null_mut(),
+                command_pool: null_mut(),
+                fence: null_mut(),
+            }
+        };
+
+        let mut queue = null_mut();
+        vkGetDeviceQueue(device.handle, queue_family, 0, &mut queue);
+
+        // Every invocation of the last workgroup writes its element, so the buffer is rounded up to whole workgroups.
+        let workgroup_count = (values.len() as u32).div_ceil(WORKGROUP_SIZE).max(1);
+        let buffer_size =
+            (workgroup_count * WORKGROUP_SIZE) as VkDeviceSize * size_of::<u32>() as VkDeviceSize;
+
+        // The buffer lives in host visible, coherent memory, which saves a staging buffer and explicit flushes.
+        let mapped = {
+            let create_info = VkBufferCreateInfo {
+                sType: VK_STRUCTURE_TYPE_BUFFER_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                size: buffer_size,
+                usage: VK_BUFFER_USAGE_STORAGE_BUFFER_BIT,
+                sharingMode: VK_SHARING_MODE_EXCLUSIVE,
+                queueFamilyIndexCount: 0,
+                pQueueFamilyIndices: null(),
+            };
+            check(
+                "vkCreateBuffer",
+                vkCreateBuffer(device.handle, &create_info, null(), &mut device.buffer),
+            )?;
+
+            let mut requirements = MaybeUninit::uninit();
+            vkGetBufferMemoryRequirements(device.handle, device.buffer, requirements.as_mut_ptr());
+            let requirements = requirements.assume_init();
+
+            let mut memory_properties = MaybeUninit::uninit();
+            vkGetPhysicalDeviceMemoryProperties(physical_device, memory_properties.as_mut_ptr());
+            let memory_properties = memory_properties.assume_init();
+
+            let wanted = VK_MEMORY_PROPERTY_HOST_VISIBLE_BIT | VK_MEMORY_PROPERTY_HOST_COHERENT_BIT;
+            let memory_type = (0..memory_properties.memoryTypeCount)
+                .find(|&index| {
+                    requirements.memoryTypeBits & (1 << index) != 0
+                        && memory_properties.memoryTypes[index as usize].propertyFlags & wanted
+                            == wanted
+                })
+                .ok_or_else(|| {
+                    Error::NoDevice(String::from(
+                        "no host visible, coherent memory for the buffer",
+                    ))
+                })?;
+
+            let allocate_info = VkMemoryAllocateInfo {
+                sType: VK_STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO,
+                pNext: null(),
+                allocationSize: requirements.size,
+                memoryTypeIndex: memory_type,
+            };
+            check(
+                "vkAllocateMemory",
+                vkAllocateMemory(device.handle, &allocate_info, null(), &mut device.memory),
+            )?;
+            check(
+                "vkBindBufferMemory",
+                vkBindBufferMemory(device.handle, device.buffer, device.memory, 0),
+            )?;
+
+            let mut mapped = null_mut();
+            check(
+                "vkMapMemory",
+                vkMapMemory(device.handle, device.memory, 0, buffer_size, 0, &mut mapped),
+            )?;
+            let mapped = std::slice::from_raw_parts_mut(
+                mapped as *mut u32,
+                (workgroup_count * WORKGROUP_SIZE) as usize,
+            );
+            mapped[..values.len()].copy_from_slice(values);
+            mapped[values.len()..].fill(0);
+            mapped
+        };
+
+        let descriptor_set = {
+            let binding = VkDescriptorSetLayoutBinding {
+                binding: 0,
+                descriptorType: VK_DESCRIPTOR_TYPE_STORAGE_BUFFER,
+                descriptorCount: 1,
+                stageFlags: VK_SHADER_STAGE_COMPUTE_BIT,
+                pImmutableSamplers: null(),
+            };
+            let layout_info = VkDescriptorSetLayoutCreateInfo {
+                sType: VK_STRUCTURE_TYPE_DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                bindingCount: 1,
+                pBindings: &binding,
+            };
+            check(
+                "vkCreateDescriptorSetLayout",
+                vkCreateDescriptorSetLayout(
+                    device.handle,
+                    &layout_info,
+                    null(),
+                    &mut device.descriptor_set_layout,
+                ),
+            )?;
+
+            let pool_size = VkDescriptorPoolSize {
+                type_: VK_DESCRIPTOR_TYPE_STORAGE_BUFFER,
+                descriptorCount: 1,
+            };
+            let pool_info = VkDescriptorPoolCreateInfo {
+                sType: VK_STRUCTURE_TYPE_DESCRIPTOR_POOL_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                maxSets: 1,
+                poolSizeCount: 1,
+                pPoolSizes: &pool_size,
+            };
+            check(
+                "vkCreateDescriptorPool",
+                vkCreateDescriptorPool(
+                    device.handle,
+                    &pool_info,
+                    null(),
+                    &mut device.descriptor_pool,
+                ),
+            )?;
+
+            let allocate_info = VkDescriptorSetAllocateInfo {
+                sType: VK_STRUCTURE_TYPE_DESCRIPTOR_SET_ALLOCATE_INFO,
+                pNext: null(),
+                descriptorPool: device.descriptor_pool,
+                descriptorSetCount: 1,
+                pSetLayouts: &device.descriptor_set_layout,
+            };
+            let mut descriptor_set = null_mut();
+            check(
+                "vkAllocateDescriptorSets",
+                vkAllocateDescriptorSets(device.handle, &allocate_info, &mut descriptor_set),
+            )?;
+
+            let buffer_info = VkDescriptorBufferInfo {
+                buffer: device.buffer,
+                offset: 0,
+                range: buffer_size,
+            };
+            let write = VkWriteDescriptorSet {
+                sType: VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
+                pNext: null(),
+                dstSet: descriptor_set,
+                dstBinding: 0,
+                dstArrayElement: 0,
+                descriptorCount: 1,
+                descriptorType: VK_DESCRIPTOR_TYPE_STORAGE_BUFFER,
+                pImageInfo: null(),
+                pBufferInfo: &buffer_info,
+                pTexelBufferView: null(),
+            };
+            vkUpdateDescriptorSets(device.handle, 1, &write, 0, null());
+
+            descriptor_set
+        };
+
+        {
+            // SPIR-V is made of 32 bit words, which the bytes from include_bytes! aren't guaranteed to be aligned to.
+            let code = SHADER
+                .chunks_exact(4)
+                .map(|word| u32::from_ne_bytes(word.try_into().unwrap()))
+                .collect::<Vec<_>>();
+            let shader_info = VkShaderModuleCreateInfo {
+                sType: VK_STRUCTURE_TYPE_SHADER_MODULE_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                codeSize: SHADER.len(),
+                pCode: code.as_ptr(),
+            };
+            check(
+                "vkCreateShaderModule",
+                vkCreateShaderModule(
+                    device.handle,
+                    &shader_info,
+                    null(),
+                    &mut device.shader_module,
+                ),
+            )?;
+
+            let layout_info = VkPipelineLayoutCreateInfo {
+                sType: VK_STRUCTURE_TYPE_PIPELINE_LAYOUT_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                setLayoutCount: 1,
+                pSetLayouts: &device.descriptor_set_layout,
+                pushConstantRangeCount: 0,
+                pPushConstantRanges: null(),
+            };
+            check(
+                "vkCreatePipelineLayout",
+                vkCreatePipelineLayout(
+                    device.handle,
+                    &layout_info,
+                    null(),
+                    &mut device.pipeline_layout,
+                ),
+            )?;
+
+            let pipeline_info = VkComputePipelineCreateInfo {
+                sType: VK_STRUCTURE_TYPE_COMPUTE_PIPELINE_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                stage: VkPipelineShaderStageCreateInfo {
+                    sType: VK_STRUCTURE_TYPE_PIPELINE_SHADER_STAGE_CREATE_INFO,
+                    pNext: null(),
+                    flags: 0,
+                    stage: VK_SHADER_STAGE_COMPUTE_BIT,
+                    module: device.shader_module,
+                    pName: c"main".as_ptr(),
+                    pSpecializationInfo: null(),
+                },
+                layout: device.pipeline_layout,
+                basePipelineHandle: null_mut(),
+                basePipelineIndex: -1,
+            };
+            check(
+                "vkCreateComputePipelines",
+                vkCreateComputePipelines(
+                    device.handle,
+                    null_mut(),
+                    1,
+                    &pipeline_info,
+                    null(),
+                    &mut device.pipeline,
+                ),
+            )?;
+        }
+
+        let command_buffer = {
+            let pool_info = VkCommandPoolCreateInfo {
+                sType: VK_STRUCTURE_TYPE_COMMAND_POOL_CREATE_INFO,
+                pNext: null(),
+                flags: 0,
+                queueFamilyIndex: queue_family,
+            };
+            check(
+                "vkCreateCommandPool",
+                vkCreateCommandPool(device.handle, &pool_info, null(), &mut device.command_pool),
+            )?;
+
+            let allocate_info = VkCommandBufferAllocateInfo {
+                sType: VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
+                pNext: null(),
+                commandPool: device.command_pool,
+                level: VK_COMMAND_BUFFER_LEVEL_PRIMARY,
+                commandBufferCount: 1,
+            };
+            let mut command_buffer = null_mut();
+            check(
+                "vkAllocateCommandBuffers",
+                vkAllocateCommandBuffers(device.handle, &allocate_info, &mut command_buffer),
+            )?;
+
+            let begin_info = VkCommandBufferBeginInfo {
+                sType: VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO,
+                pNext: null(),
+                flags: VK_COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT,
+                pInheritanceInfo: null(),
+            };
+            check(
+                "vkBeginCommandBuffer",
+                vkBeginCommandBuffer(command_buffer, &begin_info),
+            )?;
+
+            vkCmdBindPipeline(
+                command_buffer,
+                VK_PIPELINE_BIND_POINT_COMPUTE,
+                device.pipeline,
+            );
+            vkCmdBindDescriptorSets(
+                command_buffer,
+                VK_PIPELINE_BIND_POINT_COMPUTE,
+                device.pipeline_layout,
+                0,
+                1,
+                &descriptor_set,
+                0,
+                null(),
+            );
+            vkCmdDispatch(command_buffer, workgroup_count, 1, 1);
+
+            // Makes the shader's writes visible to the host once the fence is signalled.
+            let barrier = VkMemoryBarrier {
+                sType: VK_STRUCTURE_TYPE_MEMORY_BARRIER,
+                pNext: null(),
+                srcAccessMask: VK_ACCESS_SHADER_WRITE_BIT,
+                dstAccessMask: VK_ACCESS_HOST_READ_BIT,
+            };
+            vkCmdPipelineBarrier(
+                command_buffer,
+                VK_PIPELINE_STAGE_COMPUTE_SHADER_BIT,
+                VK_PIPELINE_STAGE_HOST_BIT,
+                0,
+                1,
+                &barrier,
+                0,
+                null(),
+                0,
+                null(),
+            );
+
+            check("vkEndCommandBuffer", vkEndCommandBuffer(command_buffer))?;
+            command_buffer
+        };
+
+        let fence_info = VkFenceCreateInfo {
+            sType: VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: null(),
+            flags: 0,
+        };
+        check(
+            "vkCreateFence",
+            vkCreateFence(device.handle, &fence_info, null(), &mut device.fence),
+        )?;
+
+        let submit_info = VkSubmitInfo {
+            sType: VK_STRUCTURE_TYPE_SUBMIT_INFO,
+            pNext: null(),
+            waitSemaphoreCount: 0,
+            pWaitSemaphores: null(),
+            pWaitDstStageMask: null(),
+            commandBufferCount: 1,
+            pCommandBuffers: &command_buffer,
+            signalSemaphoreCount: 0,
+            pSignalSemaphores: null(),
+        };
+        check(
+            "vkQueueSubmit",
+            vkQueueSubmit(queue, 1, &submit_info, device.fence),
+        )?;
+        check(
+            "vkWaitForFences",
+            vkWaitForFences(device.handle, 1, &device.fence, VK_TRUE, u64::MAX),
+        )?;
+
+        let results = mapped[..values.len()].to_vec();
+        vkUnmapMemory(device.handle, device.memory);
+
+        // The device has to go before the instance it was created from.
+        drop(device);
+        drop(instance);
+
+        Ok(results)
+    }
+}
+
+fn main() {
+    let values = (0..1000).collect::<Vec<u32>>();
+    match square(&values) {
+        Ok(squares) => {
+            for (value, square) in values.iter().zip(&squares).step_by(100) {
+                println!("[INFO]: {}² = {}", value, square);
+            }
+            assert!(values
+                .iter()
+                .zip(&squares)
+                .all(|(value, square)| value * value == *square));
+            println!(
+                "[INFO]: All {} values were squared on the device.",
+                values.len()
+            );
+        }
+        Err(error) => {
+            eprintln!("[ERROR]: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
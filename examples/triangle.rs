@@ -57,11 +57,16 @@ fn main() {
                 .iter()
                 .map(|s| CString::new(s.as_str()).unwrap())
                 .collect::<Vec<CString>>();
-            let required_extensions = required_extensions
+            let mut required_extensions = required_extensions
                 .iter()
                 .map(|s| s.as_ptr())
                 .collect::<Vec<*const i8>>();
 
+            // The debug utils extension is what lets the validation layers report back to us.
+            if ENABLE_VALIDATION {
+                required_extensions.push(VK_EXT_DEBUG_UTILS_EXTENSION_NAME.as_ptr() as *const i8);
+            }
+
             let validation_layers = [b"VK_LAYER_KHRONOS_validation\0".as_ptr() as *const i8];
 
             let create_info = VkInstanceCreateInfo {
@@ -92,6 +97,25 @@ fn main() {
             instance
         };
 
+        // Print whatever the validation layers have to say. The messenger has to be gone before the instance is
+        // destroyed, which is why it is dropped explicitly at the end of this function.
+        let debug_messenger = if ENABLE_VALIDATION {
+            Some(
+                debug_utils::DebugUtilsMessenger::new(
+                    instance,
+                    VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT
+                        | VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT,
+                    VK_DEBUG_UTILS_MESSAGE_TYPE_GENERAL_BIT_EXT
+                        | VK_DEBUG_UTILS_MESSAGE_TYPE_VALIDATION_BIT_EXT
+                        | VK_DEBUG_UTILS_MESSAGE_TYPE_PERFORMANCE_BIT_EXT,
+                    |message| eprintln!("[VALIDATION]: {}", message.message),
+                )
+                .expect("Failed to create the debug messenger."),
+            )
+        } else {
+            None
+        };
+
         let (window, _) = glfw
             .create_window(
                 WINDOW_WIDTH,
@@ -1335,6 +1359,7 @@ fn main() {
         vkDestroySwapchainKHR(device, swap_chain, null());
         vkDestroyDevice(device, null());
         vkDestroySurfaceKHR(instance, surface, null());
+        drop(debug_messenger);
         vkDestroyInstance(instance, null());
     }
 }
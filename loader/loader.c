@@ -1830,6 +1830,9 @@ void loader_initialize(void) {
     loader_platform_thread_create_mutex(&loader_lock);
     loader_platform_thread_create_mutex(&loader_preload_icd_lock);
     loader_platform_thread_create_mutex(&loader_global_instance_list_lock);
+#if defined(LOADER_SYS_ENTRY_POINTS)
+    loader_sys_init_log_callback();
+#endif
     init_global_loader_settings();
 
     // initialize logging
@@ -1868,6 +1871,9 @@ void loader_release() {
     loader_platform_thread_delete_mutex(&loader_lock);
     loader_platform_thread_delete_mutex(&loader_preload_icd_lock);
     loader_platform_thread_delete_mutex(&loader_global_instance_list_lock);
+#if defined(LOADER_SYS_ENTRY_POINTS)
+    loader_sys_teardown_log_callback();
+#endif
 }
 
 // Preload the ICD libraries that are likely to be needed so we don't repeatedly load/unload them later
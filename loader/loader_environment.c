@@ -62,17 +62,18 @@ char *loader_secure_getenv(const char *name, const struct loader_instance *inst)
 #else
     // Linux
     char *out;
-#if defined(HAVE_SECURE_GETENV) && !defined(LOADER_USE_UNSAFE_FILE_SEARCH)
+#if defined(LOADER_USE_UNSAFE_FILE_SEARCH)
+    out = loader_getenv(name, inst);
+#elif defined(HAVE_SECURE_GETENV)
     (void)inst;
     out = secure_getenv(name);
-#elif defined(HAVE___SECURE_GETENV) && !defined(LOADER_USE_UNSAFE_FILE_SEARCH)
+#elif defined(HAVE___SECURE_GETENV)
     (void)inst;
     out = __secure_getenv(name);
 #else
-    out = loader_getenv(name, inst);
-#if !defined(LOADER_USE_UNSAFE_FILE_SEARCH)
-    loader_log(inst, VULKAN_LOADER_INFO_BIT, 0, "Loader is using non-secure environment variable lookup for %s", name);
-#endif
+    // C libraries other than glibc, such as musl, may not provide secure_getenv, or the check for it may fail to link
+    // when building a static loader. Do what secure_getenv does, like on Apple platforms.
+    out = is_high_integrity() ? NULL : loader_getenv(name, inst);
 #endif
     return out;
 #endif
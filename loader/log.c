@@ -39,6 +39,42 @@
 
 uint32_t g_loader_debug = 0;
 
+#if defined(LOADER_SYS_ENTRY_POINTS)
+// Entry points used by the vulkan-loader-sys crate, exported in addition to the ones listed in vulkan-1.def
+#if defined(_WIN32)
+#define LOADER_SYS_EXPORT __declspec(dllexport)
+#else
+#define LOADER_SYS_EXPORT LOADER_EXPORT
+#endif
+
+// Guards the callback, its user data and its mask, which are only ever read or written together
+static loader_platform_thread_mutex g_loader_sys_log_lock;
+static PFN_vulkan_loader_sys_log_callback g_loader_sys_log_callback = NULL;
+static void *g_loader_sys_log_user_data = NULL;
+static uint32_t g_loader_sys_log_mask = 0;
+
+void loader_sys_init_log_callback(void) { loader_platform_thread_create_mutex(&g_loader_sys_log_lock); }
+
+void loader_sys_teardown_log_callback(void) { loader_platform_thread_delete_mutex(&g_loader_sys_log_lock); }
+
+// The callback is invoked with the lock held, so once this returns the previous callback is no longer running and its
+// user data can be freed. The callback must not call this function itself.
+LOADER_SYS_EXPORT void vulkan_loader_sys_set_log_callback(uint32_t mask, PFN_vulkan_loader_sys_log_callback callback,
+                                                          void *user_data) {
+    loader_platform_thread_lock_mutex(&g_loader_sys_log_lock);
+    g_loader_sys_log_callback = callback;
+    g_loader_sys_log_user_data = user_data;
+    g_loader_sys_log_mask = callback ? mask : 0;
+    loader_platform_thread_unlock_mutex(&g_loader_sys_log_lock);
+}
+
+// Runtime equivalent of setting VK_LOADER_DEBUG. Note that an active loader settings file overrides this value whenever it
+// is (re)read.
+LOADER_SYS_EXPORT void vulkan_loader_sys_set_debug_level(uint32_t debug_level) { loader_set_global_debug_level(debug_level); }
+
+LOADER_SYS_EXPORT uint32_t vulkan_loader_sys_get_debug_level(void) { return loader_get_global_debug_level(); }
+#endif
+
 void loader_init_global_debug_level(void) {
     char *env, *orig;
 
@@ -102,6 +138,14 @@ void loader_log(const struct loader_instance *inst, VkFlags msg_type, int32_t ms
     }
     va_end(ap);
 
+#if defined(LOADER_SYS_ENTRY_POINTS)
+    loader_platform_thread_lock_mutex(&g_loader_sys_log_lock);
+    if (0 != (msg_type & g_loader_sys_log_mask) && g_loader_sys_log_callback) {
+        g_loader_sys_log_callback(msg_type, msg_code, msg, g_loader_sys_log_user_data);
+    }
+    loader_platform_thread_unlock_mutex(&g_loader_sys_log_lock);
+#endif
+
     if (inst) {
         VkDebugUtilsMessageSeverityFlagBitsEXT severity = 0;
         VkDebugUtilsMessageTypeFlagsEXT type;
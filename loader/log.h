@@ -57,6 +57,16 @@ void loader_set_global_debug_level(uint32_t new_loader_debug);
 // Returns a bitmask that indicates the current flags that should be output
 uint32_t loader_get_global_debug_level(void);
 
+#if defined(LOADER_SYS_ENTRY_POINTS)
+// Callback invoked by loader_log for every message whose type intersects the mask it was registered with, regardless of
+// whether an instance exists yet or the message would be filtered from stderr
+typedef void (*PFN_vulkan_loader_sys_log_callback)(uint32_t msg_type, int32_t msg_code, const char *message, void *user_data);
+
+// Creates and destroys the lock guarding the callback, called from loader_initialize and loader_release
+void loader_sys_init_log_callback(void);
+void loader_sys_teardown_log_callback(void);
+#endif
+
 // The asm declaration prevents name mangling which is necessary for macOS
 #if defined(MODIFY_UNKNOWN_FUNCTION_DECLS)
 #define ASM_NAME(name) __asm(name)
@@ -0,0 +1,41 @@
+#!/usr/bin/env python3
+
+# Copies the Vulkan headers the loader is pinned to into vendor/Vulkan-Headers, for the documentation builds on docs.rs,
+# which can't clone them at build time. Run it again whenever the Vulkan-Headers commit in known_good.json changes.
+
+import json
+import os
+import shutil
+import subprocess
+import tempfile
+
+SCRIPT_DIR = os.path.dirname(os.path.abspath(__file__))
+VENDOR_DIR = os.path.join(SCRIPT_DIR, '..', 'vendor', 'Vulkan-Headers')
+
+# Everything vulkan.h includes, directly or through the video headers.
+INCLUDE_DIRS = ['vulkan', 'vk_video']
+
+
+def main():
+    with open(os.path.join(SCRIPT_DIR, 'known_good.json')) as known_good:
+        repo = next(repo for repo in json.load(known_good)['repos'] if repo['name'] == 'Vulkan-Headers')
+
+    with tempfile.TemporaryDirectory() as checkout:
+        subprocess.run(['git', 'clone', '--quiet', '--depth', '1', '--branch', repo['commit'], repo['url'], checkout],
+                       check=True)
+
+        shutil.rmtree(VENDOR_DIR, ignore_errors=True)
+        for include_dir in INCLUDE_DIRS:
+            shutil.copytree(os.path.join(checkout, 'include', include_dir),
+                            os.path.join(VENDOR_DIR, 'include', include_dir),
+                            ignore=shutil.ignore_patterns('*.hpp', '*.cppm'))
+        shutil.copy(os.path.join(checkout, 'LICENSE.txt'), VENDOR_DIR)
+
+    with open(os.path.join(VENDOR_DIR, 'VERSION'), 'w') as version:
+        version.write(repo['commit'] + '\n')
+
+    print('Copied Vulkan-Headers {} to {}'.format(repo['commit'], os.path.normpath(VENDOR_DIR)))
+
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,542 @@
+//! Rust implementations of `VkAllocationCallbacks`.
+//!
+//! Everything that ends up in a `VkAllocationCallbacks` implements [`VulkanAllocator`]. [`GlobalAllocator`] adapts any
+//! [`GlobalAlloc`], taking care of remembering the size and alignment of every allocation, since Vulkan only hands the
+//! pointer back when freeing. [`TrackingAllocator`] wraps another allocator and keeps statistics per
+//! `VkSystemAllocationScope`, which makes it possible to check that everything the loader, layers and drivers allocated
+//! has been freed again. [`AllocationCallbacks`] finally turns an allocator into the structure Vulkan expects.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashMap,
+    ffi::c_void,
+    mem::{align_of, size_of},
+    ptr::null_mut,
+    sync::{Arc, Mutex},
+};
+
+use crate::*;
+
+/// The operations behind `VkAllocationCallbacks`. Implementations are called from whatever thread the Vulkan call
+/// happens on, and must follow the rules the specification sets for the corresponding callbacks.
+pub trait VulkanAllocator: Send + Sync {
+    /// `pfnAllocation`. Returns null on failure.
+    fn allocate(
+        &self,
+        size: usize,
+        alignment: usize,
+        scope: VkSystemAllocationScope,
+    ) -> *mut c_void;
+
+    /// `pfnReallocation`. A null `original` behaves like [`VulkanAllocator::allocate`], a `size` of zero like
+    /// [`VulkanAllocator::free`].
+    ///
+    /// # Safety
+    ///
+    /// `original` must be null or a live allocation returned by this allocator.
+    unsafe fn reallocate(
+        &self,
+        original: *mut c_void,
+        size: usize,
+        alignment: usize,
+        scope: VkSystemAllocationScope,
+    ) -> *mut c_void;
+
+    /// `pfnFree`.
+    ///
+    /// # Safety
+    ///
+    /// `memory` must be null or a live allocation returned by this allocator.
+    unsafe fn free(&self, memory: *mut c_void);
+
+    /// `pfnInternalAllocation`, called when the implementation allocates memory on its own.
+    fn internal_allocation(
+        &self,
+        _size: usize,
+        _allocation_type: VkInternalAllocationType,
+        _scope: VkSystemAllocationScope,
+    ) {
+    }
+
+    /// `pfnInternalFree`, called when the implementation frees memory it allocated on its own.
+    fn internal_free(
+        &self,
+        _size: usize,
+        _allocation_type: VkInternalAllocationType,
+        _scope: VkSystemAllocationScope,
+    ) {
+    }
+}
+
+impl<T: VulkanAllocator + ?Sized> VulkanAllocator for Arc<T> {
+    fn allocate(
+        &self,
+        size: usize,
+        alignment: usize,
+        scope: VkSystemAllocationScope,
+    ) -> *mut c_void {
+        (**self).allocate(size, alignment, scope)
+    }
+
+    unsafe fn reallocate(
+        &self,
+        original: *mut c_void,
+        size: usize,
+        alignment: usize,
+        scope: VkSystemAllocationScope,
+    ) -> *mut c_void {
+        (**self).reallocate(original, size, alignment, scope)
+    }
+
+    unsafe fn free(&self, memory: *mut c_void) {
+        (**self).free(memory)
+    }
+
+    fn internal_allocation(
+        &self,
+        size: usize,
+        allocation_type: VkInternalAllocationType,
+        scope: VkSystemAllocationScope,
+    ) {
+        (**self).internal_allocation(size, allocation_type, scope)
+    }
+
+    fn internal_free(
+        &self,
+        size: usize,
+        allocation_type: VkInternalAllocationType,
+        scope: VkSystemAllocationScope,
+    ) {
+        (**self).internal_free(size, allocation_type, scope)
+    }
+}
+
+/// Stored right in front of every allocation handed out by [`GlobalAllocator`].
+#[derive(Clone, Copy)]
+struct Header {
+    size: usize,
+    alignment: usize,
+}
+
+/// Adapts a [`GlobalAlloc`] to [`VulkanAllocator`]. Defaults to the system allocator.
+///
+/// Only [`GlobalAlloc`] is supported, not the unstable `Allocator` trait. An allocator that only implements the latter
+/// can implement [`VulkanAllocator`] directly instead.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GlobalAllocator<A: GlobalAlloc = System>(pub A);
+
+impl<A: GlobalAlloc> GlobalAllocator<A> {
+    /// The offset of the user visible pointer from the start of the underlying allocation. It is a multiple of the
+    /// requested alignment that leaves enough room for the header.
+    fn header_offset(alignment: usize) -> usize {
+        let alignment = alignment.max(align_of::<Header>());
+        size_of::<Header>().div_ceil(alignment) * alignment
+    }
+
+    fn layout(size: usize, alignment: usize) -> Option<Layout> {
+        let alignment = alignment.max(align_of::<Header>());
+        let total = size.checked_add(Self::header_offset(alignment))?;
+        Layout::from_size_align(total, alignment).ok()
+    }
+
+    unsafe fn header(memory: *mut c_void) -> *mut Header {
+        (memory as *mut u8).sub(size_of::<Header>()) as *mut Header
+    }
+}
+
+impl<A: GlobalAlloc + Send + Sync> VulkanAllocator for GlobalAllocator<A> {
+    fn allocate(
+        &self,
+        size: usize,
+        alignment: usize,
+        _scope: VkSystemAllocationScope,
+    ) -> *mut c_void {
+        if size == 0 {
+            return null_mut();
+        }
+
+        let layout = match Self::layout(size, alignment) {
+            Some(layout) => layout,
+            None => return null_mut(),
+        };
+
+        unsafe {
+            let base = self.0.alloc(layout);
+            if base.is_null() {
+                return null_mut();
+            }
+
+            let memory = base.add(Self::header_offset(alignment)) as *mut c_void;
+            Self::header(memory).write_unaligned(Header { size, alignment });
+            memory
+        }
+    }
+
+    unsafe fn reallocate(
+        &self,
+        original: *mut c_void,
+        size: usize,
+        alignment: usize,
+        scope: VkSystemAllocationScope,
+    ) -> *mut c_void {
+        if original.is_null() {
+            return self.allocate(size, alignment, scope);
+        }
+        if size == 0 {
+            self.free(original);
+            return null_mut();
+        }
+
+        unsafe {
+            let header = Self::header(original).read_unaligned();
+            let (old_layout, new_layout) = match (
+                Self::layout(header.size, header.alignment),
+                Self::layout(size, header.alignment),
+            ) {
+                (Some(old_layout), Some(new_layout)) => (old_layout, new_layout),
+                _ => return null_mut(),
+            };
+
+            // The specification requires the alignment to stay the same across reallocations, which is what allows the
+            // underlying allocator to grow the block in place.
+            let offset = Self::header_offset(header.alignment);
+            let base = (original as *mut u8).sub(offset);
+            let base = self.0.realloc(base, old_layout, new_layout.size());
+            if base.is_null() {
+                return null_mut();
+            }
+
+            let memory = base.add(offset) as *mut c_void;
+            Self::header(memory).write_unaligned(Header {
+                size,
+                alignment: header.alignment,
+            });
+            memory
+        }
+    }
+
+    unsafe fn free(&self, memory: *mut c_void) {
+        if memory.is_null() {
+            return;
+        }
+
+        unsafe {
+            let header = Self::header(memory).read_unaligned();
+            if let Some(layout) = Self::layout(header.size, header.alignment) {
+                let base = (memory as *mut u8).sub(Self::header_offset(header.alignment));
+                self.0.dealloc(base, layout);
+            }
+        }
+    }
+}
+
+/// All five `VkSystemAllocationScope` values, in order. Indexes into [`TrackingStatistics::scopes`] follow this order.
+pub const SYSTEM_ALLOCATION_SCOPES: [VkSystemAllocationScope; 5] = [
+    VK_SYSTEM_ALLOCATION_SCOPE_COMMAND,
+    VK_SYSTEM_ALLOCATION_SCOPE_OBJECT,
+    VK_SYSTEM_ALLOCATION_SCOPE_CACHE,
+    VK_SYSTEM_ALLOCATION_SCOPE_DEVICE,
+    VK_SYSTEM_ALLOCATION_SCOPE_INSTANCE,
+];
+
+/// Counters for a single `VkSystemAllocationScope`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeStatistics {
+    pub allocations: u64,
+    pub reallocations: u64,
+    pub frees: u64,
+    /// Allocations made in this scope that have not been freed yet.
+    pub live_allocations: u64,
+    pub live_bytes: usize,
+    pub peak_bytes: usize,
+    pub internal_allocations: u64,
+    pub internal_frees: u64,
+}
+
+/// A snapshot of everything a [`TrackingAllocator`] has seen.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrackingStatistics {
+    pub scopes: [ScopeStatistics; 5],
+    /// Calls made with a scope that isn't one of [`SYSTEM_ALLOCATION_SCOPES`].
+    pub unknown_scope: ScopeStatistics,
+    /// Calls that tried to free or reallocate a pointer this allocator never handed out.
+    pub unknown_frees: u64,
+}
+
+impl TrackingStatistics {
+    /// The counters for `scope`, or [`TrackingStatistics::unknown_scope`] if it isn't a valid scope.
+    pub fn scope(&self, scope: VkSystemAllocationScope) -> &ScopeStatistics {
+        self.scopes
+            .get(scope as usize)
+            .unwrap_or(&self.unknown_scope)
+    }
+
+    fn scope_mut(&mut self, scope: VkSystemAllocationScope) -> &mut ScopeStatistics {
+        match self.scopes.get_mut(scope as usize) {
+            Some(statistics) => statistics,
+            None => &mut self.unknown_scope,
+        }
+    }
+}
+
+/// An allocation that was still live when [`TrackingAllocator::leaks`] was called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiveAllocation {
+    pub address: usize,
+    pub size: usize,
+    pub alignment: usize,
+    pub scope: VkSystemAllocationScope,
+}
+
+#[derive(Default)]
+struct TrackingState {
+    statistics: TrackingStatistics,
+    live: HashMap<usize, LiveAllocation>,
+}
+
+/// Wraps another [`VulkanAllocator`] and records what goes through it.
+///
+/// Share it through an [`Arc`] so the statistics can still be read after the callbacks have been handed to Vulkan.
+pub struct TrackingAllocator<A: VulkanAllocator = GlobalAllocator> {
+    inner: A,
+    state: Mutex<TrackingState>,
+}
+
+impl Default for TrackingAllocator {
+    fn default() -> Self {
+        TrackingAllocator::new(GlobalAllocator::default())
+    }
+}
+
+impl<A: VulkanAllocator> TrackingAllocator<A> {
+    pub fn new(inner: A) -> TrackingAllocator<A> {
+        TrackingAllocator {
+            inner,
+            state: Mutex::new(TrackingState::default()),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, TrackingState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn statistics(&self) -> TrackingStatistics {
+        self.state().statistics.clone()
+    }
+
+    /// Every allocation that has not been freed yet, sorted by address.
+    pub fn leaks(&self) -> Vec<LiveAllocation> {
+        let mut leaks = self.state().live.values().copied().collect::<Vec<_>>();
+        leaks.sort_by_key(|allocation| allocation.address);
+        leaks
+    }
+
+    fn record_allocation(state: &mut TrackingState, allocation: LiveAllocation) {
+        let scope = state.statistics.scope_mut(allocation.scope);
+        scope.live_allocations += 1;
+        scope.live_bytes += allocation.size;
+        scope.peak_bytes = scope.peak_bytes.max(scope.live_bytes);
+        state.live.insert(allocation.address, allocation);
+    }
+
+    /// Removes a live allocation. Frees are attributed to the scope the memory was allocated in, since `pfnFree` does
+    /// not carry one.
+    fn record_free(state: &mut TrackingState, address: usize, is_free: bool) -> bool {
+        match state.live.remove(&address) {
+            Some(allocation) => {
+                let scope = state.statistics.scope_mut(allocation.scope);
+                if is_free {
+                    scope.frees += 1;
+                }
+                scope.live_allocations -= 1;
+                scope.live_bytes -= allocation.size;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<A: VulkanAllocator> VulkanAllocator for TrackingAllocator<A> {
+    fn allocate(
+        &self,
+        size: usize,
+        alignment: usize,
+        scope: VkSystemAllocationScope,
+    ) -> *mut c_void {
+        let memory = self.inner.allocate(size, alignment, scope);
+
+        let mut state = self.state();
+        state.statistics.scope_mut(scope).allocations += 1;
+        if !memory.is_null() {
+            Self::record_allocation(
+                &mut state,
+                LiveAllocation {
+                    address: memory as usize,
+                    size,
+                    alignment,
+                    scope,
+                },
+            );
+        }
+
+        memory
+    }
+
+    unsafe fn reallocate(
+        &self,
+        original: *mut c_void,
+        size: usize,
+        alignment: usize,
+        scope: VkSystemAllocationScope,
+    ) -> *mut c_void {
+        let memory = self.inner.reallocate(original, size, alignment, scope);
+
+        let mut state = self.state();
+        state.statistics.scope_mut(scope).reallocations += 1;
+        // A failed reallocation leaves the original allocation untouched.
+        if !original.is_null()
+            && (size == 0 || !memory.is_null())
+            && !Self::record_free(&mut state, original as usize, size == 0)
+        {
+            state.statistics.unknown_frees += 1;
+        }
+        if !memory.is_null() {
+            Self::record_allocation(
+                &mut state,
+                LiveAllocation {
+                    address: memory as usize,
+                    size,
+                    alignment,
+                    scope,
+                },
+            );
+        }
+
+        memory
+    }
+
+    unsafe fn free(&self, memory: *mut c_void) {
+        if !memory.is_null() {
+            let mut state = self.state();
+            if !Self::record_free(&mut state, memory as usize, true) {
+                state.statistics.unknown_frees += 1;
+            }
+        }
+
+        self.inner.free(memory);
+    }
+
+    fn internal_allocation(
+        &self,
+        size: usize,
+        allocation_type: VkInternalAllocationType,
+        scope: VkSystemAllocationScope,
+    ) {
+        self.state()
+            .statistics
+            .scope_mut(scope)
+            .internal_allocations += 1;
+        self.inner.internal_allocation(size, allocation_type, scope);
+    }
+
+    fn internal_free(
+        &self,
+        size: usize,
+        allocation_type: VkInternalAllocationType,
+        scope: VkSystemAllocationScope,
+    ) {
+        self.state().statistics.scope_mut(scope).internal_frees += 1;
+        self.inner.internal_free(size, allocation_type, scope);
+    }
+}
+
+/// Owns a [`VulkanAllocator`] together with the `VkAllocationCallbacks` that point at it.
+///
+/// The allocator is kept behind its own box, whose address is what Vulkan receives as `pUserData`, so the
+/// `AllocationCallbacks` itself can be moved around freely. It has to stay alive for as long as any object allocated
+/// through it exists.
+pub struct AllocationCallbacks {
+    raw: VkAllocationCallbacks,
+    // Double boxed so that the pointer handed to Vulkan is thin and stays put.
+    _allocator: Box<Box<dyn VulkanAllocator>>,
+}
+
+unsafe impl Send for AllocationCallbacks {}
+unsafe impl Sync for AllocationCallbacks {}
+
+impl AllocationCallbacks {
+    pub fn new(allocator: impl VulkanAllocator + 'static) -> AllocationCallbacks {
+        let allocator: Box<Box<dyn VulkanAllocator>> = Box::new(Box::new(allocator));
+        AllocationCallbacks {
+            raw: VkAllocationCallbacks {
+                pUserData: &*allocator as *const Box<dyn VulkanAllocator> as *mut c_void,
+                pfnAllocation: Some(allocation_function),
+                pfnReallocation: Some(reallocation_function),
+                pfnFree: Some(free_function),
+                pfnInternalAllocation: Some(internal_allocation_notification),
+                pfnInternalFree: Some(internal_free_notification),
+            },
+            _allocator: allocator,
+        }
+    }
+
+    /// The structure to pass as `pAllocator`. Vulkan copies it, so the pointer only needs to be valid for the duration
+    /// of the call it is passed to.
+    pub fn as_raw(&self) -> *const VkAllocationCallbacks {
+        &self.raw
+    }
+}
+
+unsafe fn allocator<'a>(user_data: *mut c_void) -> &'a dyn VulkanAllocator {
+    &**(user_data as *const Box<dyn VulkanAllocator>)
+}
+
+// None of the callbacks below may unwind into C, so a panicking allocator is treated as an allocation failure.
+
+unsafe extern "system" fn allocation_function(
+    user_data: *mut c_void,
+    size: usize,
+    alignment: usize,
+    scope: VkSystemAllocationScope,
+) -> *mut c_void {
+    std::panic::catch_unwind(|| allocator(user_data).allocate(size, alignment, scope))
+        .unwrap_or(null_mut())
+}
+
+unsafe extern "system" fn reallocation_function(
+    user_data: *mut c_void,
+    original: *mut c_void,
+    size: usize,
+    alignment: usize,
+    scope: VkSystemAllocationScope,
+) -> *mut c_void {
+    std::panic::catch_unwind(|| allocator(user_data).reallocate(original, size, alignment, scope))
+        .unwrap_or(null_mut())
+}
+
+unsafe extern "system" fn free_function(user_data: *mut c_void, memory: *mut c_void) {
+    let _ = std::panic::catch_unwind(|| allocator(user_data).free(memory));
+}
+
+unsafe extern "system" fn internal_allocation_notification(
+    user_data: *mut c_void,
+    size: usize,
+    allocation_type: VkInternalAllocationType,
+    scope: VkSystemAllocationScope,
+) {
+    let _ = std::panic::catch_unwind(|| {
+        allocator(user_data).internal_allocation(size, allocation_type, scope)
+    });
+}
+
+unsafe extern "system" fn internal_free_notification(
+    user_data: *mut c_void,
+    size: usize,
+    allocation_type: VkInternalAllocationType,
+    scope: VkSystemAllocationScope,
+) {
+    let _ = std::panic::catch_unwind(|| {
+        allocator(user_data).internal_free(size, allocation_type, scope)
+    });
+}
@@ -0,0 +1,107 @@
+//! Shows the instance and device call chains the loader would build for an application.
+//!
+//! The layers are found and enabled with the same rules as vkloader-doctor, then ordered the way the loader orders
+//! them: implicit layers first, then `VK_INSTANCE_LAYERS`, `VK_LOADER_LAYERS_ENABLE` and the layers the application
+//! enables, with meta layers expanded into their components. The chains are printed like the loader's own
+//! `VK_LOADER_DEBUG=layer` output, as a Graphviz graph with `--dot` or as JSON with `--json`.
+//!
+//! Needs the `tools` feature:
+//! `cargo run --features tools --bin vkchain -- --layer VK_LAYER_KHRONOS_validation --dot | dot -Tsvg`.
+
+use std::{env, path::PathBuf, process::ExitCode};
+
+use vulkan_loader_sys::{
+    call_chain::{CallChain, InstanceRequest},
+    discovery::{LayerDiscovery, LayerFilters, LoaderSettings},
+};
+
+const USAGE: &str = "\
+Usage: vkchain [--dot | --json] [--app <executable>] [--portability] [--layer <name>]...
+
+Prints the layer call chains the loader would build with the current environment.
+
+Options:
+  --layer <name>        A layer the application enables in ppEnabledLayerNames, can be repeated
+  --portability         The application sets VK_INSTANCE_CREATE_ENUMERATE_PORTABILITY_BIT_KHR
+  --app <executable>    Apply the loader settings file entries whose app_keys name this executable
+  --dot                 Print the chains as a Graphviz graph
+  --json                Print the chains as JSON
+  -h, --help            Print this message";
+
+#[derive(Default)]
+struct Options {
+    dot: bool,
+    json: bool,
+    app: Option<PathBuf>,
+    request: InstanceRequest,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dot" => options.dot = true,
+            "--json" => options.json = true,
+            "--portability" => options.request.enumerate_portability = true,
+            "--layer" => {
+                let layer = args.next().ok_or("--layer needs a layer name")?;
+                options.request.enabled_layers.push(layer);
+            }
+            "--app" => {
+                let app = args.next().ok_or("--app needs an executable path")?;
+                options.app = Some(app.into());
+            }
+            "-h" | "--help" => return Err(String::new()),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    if options.dot && options.json {
+        return Err(String::from("--dot and --json can't be combined"));
+    }
+
+    Ok(options)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) if error.is_empty() => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("vkchain: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let settings = LoaderSettings::load(options.app.as_deref());
+    let filters = LayerFilters::from_env();
+    let discovery = LayerDiscovery::scan(&filters, settings.as_ref());
+    let chain = CallChain::build(&options.request, &discovery, &filters, settings.as_ref());
+
+    if options.dot {
+        print!("{}", chain.to_dot());
+    } else if options.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&chain.to_json()).unwrap()
+        );
+    } else {
+        print!("{}", chain.to_text());
+    }
+
+    // vkCreateInstance fails with VK_ERROR_LAYER_NOT_PRESENT when a layer the application asked for is missing.
+    if chain
+        .skipped
+        .iter()
+        .any(|skipped| options.request.enabled_layers.contains(&skipped.name))
+    {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
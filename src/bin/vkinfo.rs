@@ -0,0 +1,707 @@
+//! Prints what the Vulkan loader and the drivers behind it offer, in the spirit of vulkaninfo.
+//!
+//! The report covers the instance version, instance extensions, layers with their extensions, and for every physical
+//! device its properties, extensions, features (queried through `vkGetPhysicalDeviceFeatures2` chains up to the
+//! `VkPhysicalDeviceVulkan13Features`), queue families, memory heaps and types, format support and the displays and
+//! planes `VK_KHR_display` reports without a surface. `--json` prints it as JSON and `--diffable` as sorted
+//! `path = value` lines that can be compared between machines or driver versions.
+//!
+//! `--driver` loads a single driver library through `VK_LUNARG_direct_driver_loading` and ignores every other driver,
+//! which makes it possible to look at a mock driver on a machine without a GPU.
+//!
+//! Needs the `tools` feature: `cargo run --features tools --bin vkinfo -- --json`.
+
+use std::{
+    env,
+    ffi::{c_void, CStr},
+    mem::zeroed,
+    os::raw::c_char,
+    path::PathBuf,
+    process::ExitCode,
+    ptr::{null, null_mut},
+};
+
+use serde_json::{json, Map, Value};
+use vulkan_loader_sys::{
+    direct_driver::{DirectDriver, DirectDriverList, DirectDriverLoadingMode},
+    *,
+};
+
+const USAGE: &str = "\
+Usage: vkinfo [--json | --diffable] [--driver <library>]
+
+Prints the Vulkan instance version, layers and extensions, and what every physical device supports.
+
+Options:
+  --json                Print the report as JSON
+  --diffable            Print one sorted `path = value` line per value, for comparing two reports
+  --driver <library>    Use only this driver library, through VK_LUNARG_direct_driver_loading
+  -h, --help            Print this message";
+
+// The newest version whose feature and property structs are printed.
+const NEWEST_KNOWN_API_VERSION: (u32, u32) = (1, 3);
+
+enum OutputFormat {
+    Text,
+    Json,
+    Diffable,
+}
+
+struct Options {
+    format: OutputFormat,
+    driver: Option<PathBuf>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        format: OutputFormat::Text,
+        driver: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.format = OutputFormat::Json,
+            "--diffable" => options.format = OutputFormat::Diffable,
+            "--driver" => {
+                let driver = args.next().ok_or("--driver needs a library path")?;
+                options.driver = Some(driver.into());
+            }
+            "-h" | "--help" => return Err(String::new()),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Turns a Vulkan struct into JSON. The impls for the Vk structs are generated by build.rs from the bindings.
+trait Dump {
+    fn dump(&self) -> Value;
+}
+
+macro_rules! dump_as_number {
+    ($($type:ty),*) => {
+        $(
+            impl Dump for $type {
+                fn dump(&self) -> Value {
+                    json!(*self)
+                }
+            }
+        )*
+    };
+}
+
+dump_as_number!(u8, i8, u16, i16, u32, i32, u64, i64, usize, f32);
+
+impl<T: Dump, const N: usize> Dump for [T; N] {
+    fn dump(&self) -> Value {
+        Value::Array(self.iter().map(Dump::dump).collect())
+    }
+}
+
+fn c_chars(chars: &[c_char]) -> Value {
+    let bytes = chars
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8)
+        .collect::<Vec<_>>();
+    Value::String(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn hex(bytes: &[u8]) -> Value {
+    Value::String(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn enum_name(names: &[(i64, &str)], value: i64) -> Value {
+    names
+        .iter()
+        .find(|(known, _)| *known == value)
+        .map_or(json!(value), |(_, name)| json!(name))
+}
+
+// Lists the names of the bits set in `flags`. Bits without a name are kept as a hexadecimal remainder.
+fn flag_names(names: &[(i64, &str)], flags: u64) -> Value {
+    let mut set = Vec::new();
+    let mut remaining = flags;
+    for &(bit, name) in names {
+        let bit = bit as u64;
+        if bit.is_power_of_two() && flags & bit != 0 {
+            set.push(json!(name));
+            remaining &= !bit;
+        }
+    }
+    if remaining != 0 {
+        set.push(json!(format!("{:#x}", remaining)));
+    }
+    Value::Array(set)
+}
+
+fn api_version_string(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        (version >> 22) & 0x7f,
+        (version >> 12) & 0x3ff,
+        version & 0xfff
+    )
+}
+
+fn api_version(version: u32) -> Value {
+    Value::String(api_version_string(version))
+}
+
+fn result_name(result: VkResult) -> String {
+    match enum_name(VK_RESULT_NAMES, result as i64) {
+        Value::String(name) => name,
+        other => other.to_string(),
+    }
+}
+
+fn failed(call: &'static str) -> impl Fn(VkResult) -> String {
+    move |result| format!("{} failed with {}", call, result_name(result))
+}
+
+include!(concat!(env!("OUT_DIR"), "/vkinfo.rs"));
+
+// Runs a two-call enumeration, starting over when the count changed in between.
+unsafe fn enumerate<T>(
+    mut f: impl FnMut(*mut u32, *mut T) -> VkResult,
+) -> Result<Vec<T>, VkResult> {
+    loop {
+        let mut count = 0;
+        let result = f(&mut count, null_mut());
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+
+        let mut items = Vec::with_capacity(count as usize);
+        match f(&mut count, items.as_mut_ptr()) {
+            VK_INCOMPLETE => continue,
+            VK_SUCCESS => {
+                items.set_len(count as usize);
+                return Ok(items);
+            }
+            result => return Err(result),
+        }
+    }
+}
+
+fn extensions_to_json(extensions: &[VkExtensionProperties]) -> Value {
+    let mut object = Map::new();
+    for extension in extensions {
+        if let Value::String(name) = c_chars(&extension.extensionName) {
+            object.insert(name, json!(extension.specVersion));
+        }
+    }
+    Value::Object(object)
+}
+
+fn has_extension(extensions: &[VkExtensionProperties], name: &[u8]) -> bool {
+    let name = &name[..name.len() - 1];
+    extensions.iter().any(|extension| {
+        extension
+            .extensionName
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8)
+            .eq(name.iter().copied())
+    })
+}
+
+/// An extension that adds formats.
+struct FormatExtension {
+    /// The block of `VkFormat` values reserved by the extension number, which is one more than this.
+    block: i64,
+    name: &'static [u8],
+    /// The core version the formats were promoted to, if any.
+    promoted_to: Option<(u32, u32)>,
+}
+
+// Formats of extensions that aren't listed here are never queried.
+const FORMAT_EXTENSIONS: &[FormatExtension] = &[
+    FormatExtension {
+        block: 54,
+        name: VK_IMG_FORMAT_PVRTC_EXTENSION_NAME,
+        promoted_to: None,
+    },
+    FormatExtension {
+        block: 66,
+        name: VK_EXT_TEXTURE_COMPRESSION_ASTC_HDR_EXTENSION_NAME,
+        promoted_to: Some((1, 3)),
+    },
+    FormatExtension {
+        block: 156,
+        name: VK_KHR_SAMPLER_YCBCR_CONVERSION_EXTENSION_NAME,
+        promoted_to: Some((1, 1)),
+    },
+    FormatExtension {
+        block: 330,
+        name: VK_EXT_YCBCR_2PLANE_444_FORMATS_EXTENSION_NAME,
+        promoted_to: Some((1, 3)),
+    },
+    FormatExtension {
+        block: 340,
+        name: VK_EXT_4444_FORMATS_EXTENSION_NAME,
+        promoted_to: Some((1, 3)),
+    },
+    FormatExtension {
+        block: 464,
+        name: VK_NV_OPTICAL_FLOW_EXTENSION_NAME,
+        promoted_to: None,
+    },
+];
+
+// Whether a device may be asked about a format: the Vulkan 1.0 formats always, the others only when the device's
+// version includes them or it reports their extension.
+fn has_format(format: i64, version: (u32, u32), extensions: &[VkExtensionProperties]) -> bool {
+    if format < 1_000_000_000 {
+        return true;
+    }
+    let block = (format - 1_000_000_000) / 1000;
+    FORMAT_EXTENSIONS
+        .iter()
+        .find(|extension| extension.block == block)
+        .is_some_and(|extension| {
+            extension
+                .promoted_to
+                .is_some_and(|promoted_to| version >= promoted_to)
+                || has_extension(extensions, extension.name)
+        })
+}
+
+unsafe fn layers_to_json() -> Result<Value, VkResult> {
+    let layers =
+        enumerate(|count, properties| vkEnumerateInstanceLayerProperties(count, properties))?;
+
+    let mut list = Vec::new();
+    for layer in &layers {
+        let extensions = enumerate(|count, properties| {
+            vkEnumerateInstanceExtensionProperties(layer.layerName.as_ptr(), count, properties)
+        })?;
+        list.push(json!({
+            "layerName": c_chars(&layer.layerName),
+            "specVersion": api_version(layer.specVersion),
+            "implementationVersion": layer.implementationVersion,
+            "description": c_chars(&layer.description),
+            "extensions": extensions_to_json(&extensions),
+        }));
+    }
+    Ok(Value::Array(list))
+}
+
+unsafe fn create_instance(
+    options: &Options,
+    api_version: u32,
+    flags: VkInstanceCreateFlags,
+    extensions: &[&[u8]],
+) -> Result<Instance, String> {
+    let application_info = VkApplicationInfo {
+        sType: VK_STRUCTURE_TYPE_APPLICATION_INFO,
+        pNext: null(),
+        pApplicationName: c"vkinfo".as_ptr(),
+        applicationVersion: 0,
+        pEngineName: null(),
+        engineVersion: 0,
+        apiVersion: api_version,
+    };
+    let extension_names = extensions
+        .iter()
+        .map(|name| name.as_ptr() as *const c_char)
+        .collect::<Vec<_>>();
+    let create_info = VkInstanceCreateInfo {
+        sType: VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+        pNext: null(),
+        flags,
+        pApplicationInfo: &application_info,
+        enabledLayerCount: 0,
+        ppEnabledLayerNames: null(),
+        enabledExtensionCount: extension_names.len() as u32,
+        ppEnabledExtensionNames: extension_names.as_ptr(),
+    };
+
+    let instance = match &options.driver {
+        Some(path) => {
+            let driver = DirectDriver::open(path)
+                .map_err(|error| format!("cannot load {}: {}", path.display(), error))?;
+            DirectDriverList::new(DirectDriverLoadingMode::Exclusive)
+                .push(driver)
+                .create_instance(&create_info)
+        }
+        None => Instance::new(&create_info),
+    };
+    instance.map_err(failed("vkCreateInstance"))
+}
+
+// The Vulkan 1.1 to 1.3 feature and property structs the device version allows, chained behind the 2 variants.
+unsafe fn features_to_json(physical_device: VkPhysicalDevice, version: (u32, u32)) -> Value {
+    let mut features11: VkPhysicalDeviceVulkan11Features = zeroed();
+    features11.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_1_FEATURES;
+    let mut features12: VkPhysicalDeviceVulkan12Features = zeroed();
+    features12.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_2_FEATURES;
+    let mut features13: VkPhysicalDeviceVulkan13Features = zeroed();
+    features13.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_3_FEATURES;
+    let mut features2: VkPhysicalDeviceFeatures2 = zeroed();
+    features2.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_FEATURES_2;
+
+    // VkPhysicalDeviceVulkan11Features only exists since Vulkan 1.2.
+    if version >= (1, 3) {
+        features13.pNext = features2.pNext;
+        features2.pNext = &mut features13 as *mut _ as *mut c_void;
+    }
+    if version >= (1, 2) {
+        features12.pNext = features2.pNext;
+        features2.pNext = &mut features12 as *mut _ as *mut c_void;
+        features11.pNext = features2.pNext;
+        features2.pNext = &mut features11 as *mut _ as *mut c_void;
+    }
+    vkGetPhysicalDeviceFeatures2(physical_device, &mut features2);
+
+    let mut object = Map::new();
+    object.insert(
+        "VkPhysicalDeviceFeatures".to_string(),
+        features2.features.dump(),
+    );
+    if version >= (1, 2) {
+        object.insert(
+            "VkPhysicalDeviceVulkan11Features".to_string(),
+            features11.dump(),
+        );
+        object.insert(
+            "VkPhysicalDeviceVulkan12Features".to_string(),
+            features12.dump(),
+        );
+    }
+    if version >= (1, 3) {
+        object.insert(
+            "VkPhysicalDeviceVulkan13Features".to_string(),
+            features13.dump(),
+        );
+    }
+    Value::Object(object)
+}
+
+unsafe fn properties_to_json(physical_device: VkPhysicalDevice, version: (u32, u32)) -> Value {
+    let mut properties11: VkPhysicalDeviceVulkan11Properties = zeroed();
+    properties11.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_1_PROPERTIES;
+    let mut properties12: VkPhysicalDeviceVulkan12Properties = zeroed();
+    properties12.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_2_PROPERTIES;
+    let mut properties13: VkPhysicalDeviceVulkan13Properties = zeroed();
+    properties13.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_3_PROPERTIES;
+    let mut properties2: VkPhysicalDeviceProperties2 = zeroed();
+    properties2.sType = VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_PROPERTIES_2;
+
+    if version >= (1, 3) {
+        properties13.pNext = properties2.pNext;
+        properties2.pNext = &mut properties13 as *mut _ as *mut c_void;
+    }
+    if version >= (1, 2) {
+        properties12.pNext = properties2.pNext;
+        properties2.pNext = &mut properties12 as *mut _ as *mut c_void;
+        properties11.pNext = properties2.pNext;
+        properties2.pNext = &mut properties11 as *mut _ as *mut c_void;
+    }
+    vkGetPhysicalDeviceProperties2(physical_device, &mut properties2);
+
+    let mut object = Map::new();
+    object.insert(
+        "VkPhysicalDeviceProperties".to_string(),
+        properties2.properties.dump(),
+    );
+    if version >= (1, 2) {
+        object.insert(
+            "VkPhysicalDeviceVulkan11Properties".to_string(),
+            properties11.dump(),
+        );
+        object.insert(
+            "VkPhysicalDeviceVulkan12Properties".to_string(),
+            properties12.dump(),
+        );
+    }
+    if version >= (1, 3) {
+        object.insert(
+            "VkPhysicalDeviceVulkan13Properties".to_string(),
+            properties13.dump(),
+        );
+    }
+    Value::Object(object)
+}
+
+unsafe fn physical_device_to_json(
+    physical_device: VkPhysicalDevice,
+    instance_version: u32,
+    has_display: bool,
+) -> Result<Value, VkResult> {
+    let mut properties: VkPhysicalDeviceProperties = zeroed();
+    vkGetPhysicalDeviceProperties(physical_device, &mut properties);
+    let device_version = properties.apiVersion.min(instance_version);
+    let version = (
+        (device_version >> 22) & 0x7f,
+        (device_version >> 12) & 0x3ff,
+    );
+
+    let extensions = enumerate(|count, properties| {
+        vkEnumerateDeviceExtensionProperties(physical_device, null(), count, properties)
+    })?;
+
+    let queue_families = enumerate(|count, properties| {
+        vkGetPhysicalDeviceQueueFamilyProperties(physical_device, count, properties);
+        VK_SUCCESS
+    })?;
+
+    let mut memory: VkPhysicalDeviceMemoryProperties = zeroed();
+    vkGetPhysicalDeviceMemoryProperties(physical_device, &mut memory);
+    let memory_heaps = &memory.memoryHeaps[..memory.memoryHeapCount as usize];
+    let memory_types = &memory.memoryTypes[..memory.memoryTypeCount as usize];
+
+    // Only formats with at least some support are listed.
+    let mut formats = Map::new();
+    for &(format, name) in VK_FORMAT_NAMES
+        .iter()
+        .filter(|&&(format, _)| format != 0 && has_format(format, version, &extensions))
+    {
+        let mut format_properties: VkFormatProperties = zeroed();
+        vkGetPhysicalDeviceFormatProperties(
+            physical_device,
+            format as VkFormat,
+            &mut format_properties,
+        );
+        let is_supported = format_properties.linearTilingFeatures != 0
+            || format_properties.optimalTilingFeatures != 0
+            || format_properties.bufferFeatures != 0;
+        if is_supported {
+            formats.insert(name.to_string(), format_properties.dump());
+        }
+    }
+
+    let mut device = json!({
+        "properties": properties_to_json(physical_device, version),
+        "extensions": extensions_to_json(&extensions),
+        "features": features_to_json(physical_device, version),
+        "queue_families": queue_families.iter().map(Dump::dump).collect::<Vec<_>>(),
+        "memory_heaps": memory_heaps.iter().map(Dump::dump).collect::<Vec<_>>(),
+        "memory_types": memory_types.iter().map(Dump::dump).collect::<Vec<_>>(),
+        "formats": formats,
+    });
+
+    // VK_KHR_display needs no surface, so it is the one window system whose capabilities can always be shown.
+    if has_display {
+        let displays = enumerate(|count, properties| {
+            vkGetPhysicalDeviceDisplayPropertiesKHR(physical_device, count, properties)
+        })?;
+        let planes = enumerate(|count, properties| {
+            vkGetPhysicalDeviceDisplayPlanePropertiesKHR(physical_device, count, properties)
+        })?;
+
+        let displays = displays
+            .iter()
+            .map(|display| {
+                let mut value = display.dump();
+                if !display.displayName.is_null() {
+                    let name = CStr::from_ptr(display.displayName);
+                    value["displayName"] = json!(name.to_string_lossy());
+                }
+                value
+            })
+            .collect::<Vec<_>>();
+        device["displays"] = Value::Array(displays);
+        device["display_planes"] = Value::Array(planes.iter().map(Dump::dump).collect());
+    }
+
+    Ok(device)
+}
+
+unsafe fn gather(options: &Options) -> Result<Value, String> {
+    let mut instance_version = 0;
+    let result = vkEnumerateInstanceVersion(&mut instance_version);
+    if result != VK_SUCCESS {
+        return Err(failed("vkEnumerateInstanceVersion")(result));
+    }
+
+    let instance_extensions = enumerate(|count, properties| {
+        vkEnumerateInstanceExtensionProperties(null(), count, properties)
+    })
+    .map_err(failed("vkEnumerateInstanceExtensionProperties"))?;
+    let layers = layers_to_json().map_err(failed("vkEnumerateInstanceLayerProperties"))?;
+
+    let (newest_major, newest_minor) = NEWEST_KNOWN_API_VERSION;
+    let api_version = instance_version.min(VK_MAKE_API_VERSION(0, newest_major, newest_minor, 0));
+
+    let mut flags = 0;
+    let mut extensions: Vec<&[u8]> = Vec::new();
+    if has_extension(
+        &instance_extensions,
+        VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME,
+    ) {
+        flags |= VK_INSTANCE_CREATE_ENUMERATE_PORTABILITY_BIT_KHR as VkInstanceCreateFlags;
+        extensions.push(VK_KHR_PORTABILITY_ENUMERATION_EXTENSION_NAME);
+    }
+    let mut has_display = has_extension(&instance_extensions, VK_KHR_DISPLAY_EXTENSION_NAME);
+    if has_display {
+        extensions.push(VK_KHR_DISPLAY_EXTENSION_NAME);
+    }
+
+    // The extensions were listed for the drivers found through manifests, which --driver leaves out, so a driver that
+    // lacks VK_KHR_display gets a second try without it.
+    let instance = match create_instance(options, api_version, flags, &extensions) {
+        Err(_) if has_display && options.driver.is_some() => {
+            has_display = false;
+            extensions.retain(|&name| name != VK_KHR_DISPLAY_EXTENSION_NAME.as_slice());
+            create_instance(options, api_version, flags, &extensions)?
+        }
+        instance => instance?,
+    };
+
+    let physical_devices =
+        enumerate(|count, devices| vkEnumeratePhysicalDevices(instance.handle(), count, devices))
+            .map_err(failed("vkEnumeratePhysicalDevices"))?;
+
+    let mut devices = Vec::new();
+    for &physical_device in &physical_devices {
+        let device = physical_device_to_json(physical_device, api_version, has_display)
+            .map_err(failed("querying a physical device"))?;
+        devices.push(device);
+    }
+
+    Ok(json!({
+        "instance": {
+            "api_version": api_version_string(instance_version),
+            "extensions": extensions_to_json(&instance_extensions),
+            "layers": layers,
+        },
+        "physical_devices": devices,
+    }))
+}
+
+fn is_scalar_array(values: &[Value]) -> bool {
+    values
+        .iter()
+        .all(|value| !value.is_object() && !value.is_array())
+}
+
+fn scalar_to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn print_tree(key: &str, value: &Value, indent: usize) {
+    let padding = " ".repeat(indent);
+    match value {
+        Value::Object(object) if object.is_empty() => println!("{}{}: none", padding, key),
+        Value::Object(object) => {
+            println!("{}{}:", padding, key);
+            for (key, value) in object {
+                print_tree(key, value, indent + 2);
+            }
+        }
+        Value::Array(values) if values.is_empty() => println!("{}{}: none", padding, key),
+        Value::Array(values) if is_scalar_array(values) => {
+            let items = values.iter().map(scalar_to_text).collect::<Vec<_>>();
+            println!("{}{}: {}", padding, key, items.join(", "));
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                print_tree(&format!("{}[{}]", key, index), value, indent);
+            }
+        }
+        scalar => println!("{}{}: {}", padding, key, scalar_to_text(scalar)),
+    }
+}
+
+fn print_text(report: &Value) {
+    let instance = &report["instance"];
+    println!(
+        "Vulkan instance version: {}",
+        scalar_to_text(&instance["api_version"])
+    );
+    println!();
+    print_tree("Instance extensions", &instance["extensions"], 0);
+    println!();
+    match instance["layers"].as_array() {
+        Some(layers) if !layers.is_empty() => {
+            println!("Layers:");
+            for layer in layers {
+                print_tree(&scalar_to_text(&layer["layerName"]), layer, 2);
+            }
+        }
+        _ => println!("Layers: none"),
+    }
+
+    let devices = report["physical_devices"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice);
+    if devices.is_empty() {
+        println!();
+        println!("No physical devices.");
+    }
+    for (index, device) in devices.iter().enumerate() {
+        let name = &device["properties"]["VkPhysicalDeviceProperties"]["deviceName"];
+        println!();
+        println!("Physical device {}: {}", index, scalar_to_text(name));
+        if let Some(sections) = device.as_object() {
+            for (key, value) in sections {
+                print_tree(key, value, 2);
+            }
+        }
+    }
+}
+
+// Flattens the report into `path = value` lines. Arrays of plain values, such as bit names, stay on one line.
+fn flatten(path: &str, value: &Value, lines: &mut Vec<String>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                flatten(&format!("{}.{}", path, key), value, lines);
+            }
+        }
+        Value::Array(values) if !is_scalar_array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                flatten(&format!("{}[{}]", path, index), value, lines);
+            }
+        }
+        value => lines.push(format!("{} = {}", path, value)),
+    }
+}
+
+fn print_diffable(report: &Value) {
+    let mut lines = Vec::new();
+    if let Some(object) = report.as_object() {
+        for (key, value) in object {
+            flatten(key, value, &mut lines);
+        }
+    }
+    lines.sort();
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) if error.is_empty() => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("vkinfo: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = match unsafe { gather(&options) } {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("vkinfo: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match options.format {
+        OutputFormat::Text => print_text(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+        OutputFormat::Diffable => print_diffable(&report),
+    }
+    ExitCode::SUCCESS
+}
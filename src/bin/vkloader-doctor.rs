@@ -0,0 +1,540 @@
+//! Explains which drivers and layers the Vulkan loader would use in the current environment, and why.
+//!
+//! Every driver and layer manifest on the loader's search path is read and checked with the loader's own rules, its
+//! library is opened and asked for its interface version, and the `VK_LOADER_*` filters, `VK_INSTANCE_LAYERS`, the
+//! `enable_environment`/`disable_environment` of implicit layers, the override layer and the loader settings file are
+//! applied to reach a verdict for each of them. `--json` prints the same report in a form that can be attached to a
+//! bug report.
+//!
+//! Needs the `tools` feature: `cargo run --features tools --bin vkloader-doctor -- --json`.
+
+use std::{collections::HashSet, env, path::PathBuf, process::ExitCode};
+
+use serde_json::{json, Value};
+use vulkan_loader_sys::{
+    discovery::*,
+    library::{check_driver_library, check_layer_library, LibraryCheck},
+    manifest::{self, Diagnostic, DriverManifest, Layer, Severity},
+};
+
+const USAGE: &str = "\
+Usage: vkloader-doctor [--json] [--app <executable>]
+
+Reports which Vulkan drivers and layers the loader would find and use with the current environment.
+
+Options:
+  --json                Print the report as JSON
+  --app <executable>    Apply the loader settings file entries whose app_keys name this executable
+  -h, --help            Print this message";
+
+struct Options {
+    json: bool,
+    app: Option<PathBuf>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        json: false,
+        app: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--app" => {
+                let app = args.next().ok_or("--app needs an executable path")?;
+                options.app = Some(app.into());
+            }
+            "-h" | "--help" => return Err(String::new()),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn first_error(diagnostics: &[Diagnostic]) -> Option<&str> {
+    diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.severity == Severity::Error)
+        .map(|diagnostic| diagnostic.message.as_str())
+}
+
+fn diagnostics_json(diagnostics: &[Diagnostic]) -> Value {
+    diagnostics
+        .iter()
+        .map(|diagnostic| json!({ "severity": diagnostic.severity.as_str(), "message": diagnostic.message }))
+        .collect()
+}
+
+/// What the loader ends up doing with a driver or layer.
+struct Verdict {
+    used: bool,
+    reason: String,
+}
+
+impl Verdict {
+    fn used(reason: impl Into<String>) -> Verdict {
+        Verdict {
+            used: true,
+            reason: reason.into(),
+        }
+    }
+
+    fn unused(reason: impl Into<String>) -> Verdict {
+        Verdict {
+            used: false,
+            reason: reason.into(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "used": self.used, "reason": self.reason })
+    }
+}
+
+struct DriverReport {
+    manifest: DriverManifest,
+    library: Option<LibraryCheck>,
+    verdict: Verdict,
+}
+
+fn check_driver(path: PathBuf, filters: &DriverFilters) -> DriverReport {
+    let manifest = DriverManifest::load(path);
+
+    if !manifest.is_usable() {
+        let verdict = Verdict::unused(first_error(&manifest.diagnostics).unwrap_or_default());
+        return DriverReport {
+            manifest,
+            library: None,
+            verdict,
+        };
+    }
+
+    let library = manifest.resolved_library_path().map(check_driver_library);
+    let verdict = if let Some(rejection) = filters.rejection(&manifest.path) {
+        Verdict::unused(rejection)
+    } else if let Some(error) = library.as_ref().and_then(|library| library.error.as_ref()) {
+        Verdict::unused(format!("library failed to load: {}", error))
+    } else if manifest.is_portability_driver {
+        Verdict::unused("portability driver, only used when the application enables VK_KHR_portability_enumeration")
+    } else {
+        Verdict::used("found on the search path")
+    };
+
+    DriverReport {
+        manifest,
+        library,
+        verdict,
+    }
+}
+
+struct LayerReport {
+    manifest_path: PathBuf,
+    layer: Layer,
+    /// The control the loader settings file gives the layer, if it lists it.
+    control: Option<LayerControl>,
+    library: Option<LibraryCheck>,
+    verdict: Verdict,
+}
+
+struct Report {
+    environment: Vec<(&'static str, String)>,
+    settings: Option<LoaderSettings>,
+    driver_search_paths: SearchPaths,
+    implicit_layer_search_paths: SearchPaths,
+    explicit_layer_search_paths: SearchPaths,
+    drivers: Vec<DriverReport>,
+    layers: Vec<LayerReport>,
+    broken_layer_manifests: Vec<BrokenLayerManifest>,
+}
+
+impl Report {
+    fn gather(options: &Options) -> Report {
+        let environment = ENVIRONMENT_VARIABLES
+            .iter()
+            .filter_map(|&name| env::var(name).ok().map(|value| (name, value)))
+            .collect();
+        let settings = LoaderSettings::load(options.app.as_deref());
+
+        let driver_search_paths = SearchPaths::new(ManifestKind::Driver, None);
+        let driver_filters = DriverFilters::from_env();
+        let drivers = driver_search_paths
+            .manifests()
+            .into_iter()
+            .map(|path| check_driver(path, &driver_filters))
+            .collect();
+
+        let filters = LayerFilters::from_env();
+        let discovery = LayerDiscovery::scan(&filters, settings.as_ref());
+        let layers = Report::decide_layers(
+            discovery.layers,
+            &filters,
+            discovery.override_layer.as_ref(),
+            settings.as_ref(),
+        );
+
+        Report {
+            environment,
+            settings,
+            driver_search_paths,
+            implicit_layer_search_paths: discovery.implicit_search_paths,
+            explicit_layer_search_paths: discovery.explicit_search_paths,
+            drivers,
+            layers,
+            broken_layer_manifests: discovery.broken_manifests,
+        }
+    }
+
+    fn decide_layers(
+        layers: Vec<(PathBuf, Layer)>,
+        filters: &LayerFilters,
+        override_layer: Option<&Layer>,
+        settings: Option<&LoaderSettings>,
+    ) -> Vec<LayerReport> {
+        let instance_layers = instance_layers_from_env();
+        let usable_names = layers
+            .iter()
+            .filter(|(_, layer)| layer.is_usable())
+            .map(|(_, layer)| layer.name.clone())
+            .collect::<HashSet<_>>();
+        let blacklisted = override_layer
+            .map(|layer| layer.blacklisted_layers.clone())
+            .unwrap_or_default();
+        let mut seen = HashSet::new();
+
+        layers
+            .into_iter()
+            .map(|(manifest_path, layer)| {
+                let control = settings
+                    .and_then(|settings| settings.configuration(&layer.name, &manifest_path))
+                    .map(|configuration| configuration.control);
+
+                if !layer.is_usable() {
+                    let verdict =
+                        Verdict::unused(first_error(&layer.diagnostics).unwrap_or_default());
+                    return LayerReport {
+                        manifest_path,
+                        layer,
+                        control,
+                        library: None,
+                        verdict,
+                    };
+                }
+
+                let library = layer
+                    .resolved_library_path(&manifest_path)
+                    .map(|path| check_layer_library(&layer, path));
+                let missing_components = layer
+                    .component_layers
+                    .iter()
+                    .filter(|component| !usable_names.contains(*component))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                let verdict = if !seen.insert(layer.name.clone()) {
+                    Verdict::unused("an earlier layer with the same name takes precedence")
+                } else if blacklisted.contains(&layer.name) {
+                    Verdict::unused(format!("blacklisted by {}", manifest::OVERRIDE_LAYER_NAME))
+                } else if let (Some(settings), None) = (settings, control) {
+                    if settings.has_unordered_layer_location() {
+                        Report::environment_verdict(
+                            &layer,
+                            filters,
+                            &instance_layers,
+                            override_layer,
+                        )
+                    } else {
+                        Verdict::unused(format!(
+                            "not listed in the loader settings file {}",
+                            settings.path.display()
+                        ))
+                    }
+                } else if control == Some(LayerControl::Off) {
+                    Verdict::unused("turned off by the loader settings file")
+                } else if control == Some(LayerControl::On)
+                    && !filters.is_disabled(&layer.name, layer.is_implicit)
+                {
+                    Verdict::used("turned on by the loader settings file")
+                } else {
+                    Report::environment_verdict(&layer, filters, &instance_layers, override_layer)
+                };
+
+                let verdict = if !verdict.used {
+                    verdict
+                } else if !missing_components.is_empty() {
+                    Verdict::unused(format!(
+                        "meta layer with missing component layers: {}",
+                        missing_components.join(", ")
+                    ))
+                } else if let Some(error) =
+                    library.as_ref().and_then(|library| library.error.as_ref())
+                {
+                    Verdict::unused(format!("library failed to load: {}", error))
+                } else {
+                    verdict
+                };
+
+                LayerReport {
+                    manifest_path,
+                    layer,
+                    control,
+                    library,
+                    verdict,
+                }
+            })
+            .collect()
+    }
+
+    fn environment_verdict(
+        layer: &Layer,
+        filters: &LayerFilters,
+        instance_layers: &[String],
+        override_layer: Option<&Layer>,
+    ) -> Verdict {
+        let decision = if layer.is_implicit {
+            implicit_layer_decision(layer, filters, override_layer)
+        } else {
+            explicit_layer_decision(layer, filters, instance_layers, override_layer)
+        };
+        Verdict {
+            used: decision.enabled,
+            reason: decision.reason,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let search_paths = |search_paths: &SearchPaths| {
+            json!({
+                "overridden_by": search_paths.overridden_by,
+                "paths": search_paths
+                    .paths
+                    .iter()
+                    .map(|path| json!({ "path": path.path, "source": path.source }))
+                    .collect::<Vec<_>>(),
+            })
+        };
+
+        json!({
+            "environment": self
+                .environment
+                .iter()
+                .map(|(name, value)| (name.to_string(), Value::from(value.as_str())))
+                .collect::<serde_json::Map<_, _>>(),
+            "settings_file": self.settings.as_ref().map(|settings| json!({
+                "path": settings.path,
+                "stderr_log": settings.stderr_log,
+                "layers": settings
+                    .layers
+                    .iter()
+                    .map(|layer| json!({
+                        "control": layer.control.as_str(),
+                        "name": layer.name,
+                        "path": layer.path,
+                        "treat_as_implicit_manifest": layer.treat_as_implicit_manifest,
+                    }))
+                    .collect::<Vec<_>>(),
+                "diagnostics": diagnostics_json(&settings.diagnostics),
+            })),
+            "search_paths": {
+                "drivers": search_paths(&self.driver_search_paths),
+                "implicit_layers": search_paths(&self.implicit_layer_search_paths),
+                "explicit_layers": search_paths(&self.explicit_layer_search_paths),
+            },
+            "drivers": self
+                .drivers
+                .iter()
+                .map(|driver| json!({
+                    "manifest": driver.manifest.path,
+                    "file_format_version": driver.manifest.file_format_version.map(|version| version.to_string()),
+                    "library_path": driver.manifest.library_path,
+                    "api_version": driver.manifest.api_version.map(manifest::version_to_string),
+                    "is_portability_driver": driver.manifest.is_portability_driver,
+                    "library": driver.library.as_ref().map(LibraryCheck::to_json),
+                    "diagnostics": diagnostics_json(&driver.manifest.diagnostics),
+                    "verdict": driver.verdict.to_json(),
+                }))
+                .collect::<Vec<_>>(),
+            "layers": self
+                .layers
+                .iter()
+                .map(|report| json!({
+                    "manifest": report.manifest_path,
+                    "name": report.layer.name,
+                    "type": if report.layer.is_implicit { "implicit" } else { "explicit" },
+                    "library_path": report.layer.library_path,
+                    "component_layers": report.layer.component_layers,
+                    "api_version": manifest::version_to_string(report.layer.api_version),
+                    "implementation_version": report.layer.implementation_version,
+                    "settings_control": report.control.map(LayerControl::as_str),
+                    "library": report.library.as_ref().map(LibraryCheck::to_json),
+                    "diagnostics": diagnostics_json(&report.layer.diagnostics),
+                    "verdict": report.verdict.to_json(),
+                }))
+                .chain(self.broken_layer_manifests.iter().map(|broken| json!({
+                    "manifest": broken.path,
+                    "type": if broken.is_implicit { "implicit" } else { "explicit" },
+                    "diagnostics": diagnostics_json(&broken.diagnostics),
+                    "verdict": Verdict::unused(first_error(&broken.diagnostics).unwrap_or("no layers in the manifest")).to_json(),
+                })))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn print_text(&self) {
+        println!("Environment");
+        if self.environment.is_empty() {
+            println!("  none of the loader's environment variables are set");
+        }
+        for (name, value) in &self.environment {
+            println!("  {}={}", name, value);
+        }
+
+        println!();
+        match &self.settings {
+            Some(settings) => {
+                println!("Loader settings file {}", settings.path.display());
+                for layer in &settings.layers {
+                    match &layer.name {
+                        Some(name) => println!("  {:<8} {}", layer.control.as_str(), name),
+                        None => println!("  {}", layer.control.as_str()),
+                    }
+                }
+                if let Some(stderr_log) = &settings.stderr_log {
+                    println!("  stderr_log: {}", stderr_log.join(","));
+                }
+                print_diagnostics(&settings.diagnostics, "  ", None);
+            }
+            None => println!("No loader settings file in use"),
+        }
+
+        for (title, search_paths) in [
+            ("Driver", &self.driver_search_paths),
+            ("Implicit layer", &self.implicit_layer_search_paths),
+            ("Explicit layer", &self.explicit_layer_search_paths),
+        ] {
+            println!();
+            match &search_paths.overridden_by {
+                Some(source) => println!("{} search path, replaced by {}", title, source),
+                None => println!("{} search path", title),
+            }
+            for path in &search_paths.paths {
+                let missing = if path.path.exists() { "" } else { ", missing" };
+                println!("  {} ({}{})", path.path.display(), path.source, missing);
+            }
+        }
+
+        println!();
+        println!("Drivers");
+        if self.drivers.is_empty() {
+            println!("  no driver manifests found");
+        }
+        for driver in &self.drivers {
+            println!("  {}", driver.manifest.path.display());
+            if let Some(api_version) = driver.manifest.api_version {
+                println!(
+                    "    api_version {}",
+                    manifest::version_to_string(api_version)
+                );
+            }
+            if let Some(library) = &driver.library {
+                println!("    library {}", library.summary());
+                print_notes(&library.notes);
+            }
+            print_diagnostics(&driver.manifest.diagnostics, "    ", Some(&driver.verdict));
+            print_verdict(&driver.verdict);
+        }
+
+        println!();
+        println!("Layers");
+        if self.layers.is_empty() && self.broken_layer_manifests.is_empty() {
+            println!("  no layer manifests found");
+        }
+        for report in &self.layers {
+            let kind = if report.layer.is_implicit {
+                "implicit"
+            } else {
+                "explicit"
+            };
+            println!(
+                "  {} ({}, {})",
+                report.layer.name,
+                kind,
+                report.manifest_path.display()
+            );
+            if let Some(control) = report.control {
+                println!("    settings file control {}", control.as_str());
+            }
+            if !report.layer.component_layers.is_empty() {
+                println!(
+                    "    components {}",
+                    report.layer.component_layers.join(", ")
+                );
+            }
+            if let Some(library) = &report.library {
+                println!("    library {}", library.summary());
+                print_notes(&library.notes);
+            }
+            print_diagnostics(&report.layer.diagnostics, "    ", Some(&report.verdict));
+            print_verdict(&report.verdict);
+        }
+        for broken in &self.broken_layer_manifests {
+            println!("  {}", broken.path.display());
+            print_diagnostics(&broken.diagnostics, "    ", None);
+            print_verdict(&Verdict::unused("the manifest is skipped"));
+        }
+    }
+}
+
+fn print_notes(notes: &[String]) {
+    for note in notes {
+        println!("      note: {}", note);
+    }
+}
+
+/// Prints the diagnostics, leaving out the one the verdict already repeats.
+fn print_diagnostics(diagnostics: &[Diagnostic], indent: &str, verdict: Option<&Verdict>) {
+    for diagnostic in diagnostics {
+        if verdict.is_some_and(|verdict| verdict.reason == diagnostic.message) {
+            continue;
+        }
+        println!("{}{}", indent, diagnostic);
+    }
+}
+
+fn print_verdict(verdict: &Verdict) {
+    let status = if verdict.used { "used" } else { "not used" };
+    println!("    => {}: {}", status, verdict.reason);
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) if error.is_empty() => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("vkloader-doctor: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = Report::gather(&options);
+    if options.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report.to_json()).unwrap()
+        );
+    } else {
+        report.print_text();
+    }
+
+    // Lets scripts notice an environment in which instance creation cannot succeed.
+    if report.drivers.iter().any(|driver| driver.verdict.used) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,220 @@
+//! Makes sense of the loader's `VK_LOADER_DEBUG` output.
+//!
+//! Reads a log captured with `VK_LOADER_DEBUG=all` (or any other filter) and prints what happened as a timeline: the
+//! manifests searched for and found, the drivers and layers that were skipped and why, the layer chains that were
+//! built, and every error and warning. `--json` prints every message with what it was recognised as, `--summary` a
+//! condensed JSON report meant for attaching to bug reports.
+//!
+//! Needs the `tools` feature:
+//! `VK_LOADER_DEBUG=all my_app 2> loader.log; cargo run --features tools --bin vklog -- loader.log`.
+
+use std::{
+    env, fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use serde_json::Value;
+use vulkan_loader_sys::debug_log::{self, category_names, ChainLayer, Event, Record};
+
+const USAGE: &str = "\
+Usage: vklog [--json | --summary] [--all] [<log>]
+
+Reads the loader's VK_LOADER_DEBUG output from <log>, or from stdin, and prints what the loader did.
+
+Options:
+  --json       Print every loader message as JSON, along with what it was recognised as
+  --summary    Print a JSON summary: manifests found, drivers and layers skipped and why, the layer chains that were
+               built, errors and warnings
+  --all        Include every loader message in the timeline, not only the recognised ones, errors and warnings
+  -h, --help   Print this message";
+
+#[derive(Default)]
+struct Options {
+    json: bool,
+    summary: bool,
+    all: bool,
+    log: Option<String>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options::default();
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--summary" => options.summary = true,
+            "--all" => options.all = true,
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option {}", arg))
+            }
+            _ if options.log.is_some() => return Err(String::from("only one log can be read")),
+            _ => options.log = Some(arg),
+        }
+    }
+
+    if options.json && options.summary {
+        return Err(String::from("--json and --summary can't be combined"));
+    }
+
+    Ok(options)
+}
+
+fn read_log(path: Option<&str>) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    match path {
+        Some(path) if path != "-" => bytes = fs::read(path)?,
+        _ => {
+            io::stdin().read_to_end(&mut bytes)?;
+        }
+    }
+    // Paths and driver names aren't necessarily UTF-8, which is no reason to give up on the rest of the log.
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn chain(layers: &[ChainLayer], end: &str) -> String {
+    let mut names = vec!["<Application>", "<Loader>"];
+    names.extend(layers.iter().map(|layer| layer.name.as_str()));
+    names.push(end);
+    names.join(" -> ")
+}
+
+/// One timeline line for `record`, or `None` if it isn't worth one.
+fn describe(record: &Record, all: bool) -> Option<String> {
+    let description = match &record.event {
+        Event::LoaderVersion(version) => format!("loader version {}", version),
+        Event::ManifestSearch {
+            component,
+            locations,
+            files,
+        } => format!(
+            "searched {} for {} manifests, found {}",
+            if locations.is_empty() {
+                String::from("nowhere")
+            } else {
+                locations.join(", ")
+            },
+            component.as_str(),
+            if files.is_empty() {
+                String::from("none")
+            } else {
+                files.join(", ")
+            },
+        ),
+        Event::ManifestFound {
+            component,
+            path,
+            file_format_version,
+        } => format!(
+            "found {} manifest {} (file format {})",
+            component.as_str(),
+            path,
+            file_format_version
+        ),
+        Event::DriverSkipped { path, reason } => format!(
+            "skipped driver {}: {}",
+            path.as_deref().unwrap_or("<unknown>"),
+            reason
+        ),
+        Event::LayerSkipped {
+            name,
+            manifest,
+            reason,
+        } => format!(
+            "skipped layer {}: {}",
+            name.as_deref()
+                .or(manifest.as_deref())
+                .unwrap_or("<unknown>"),
+            reason
+        ),
+        Event::LayerLibraryLoaded(library) => format!("loaded layer library {}", library),
+        Event::LayerInserted {
+            name,
+            library,
+            is_device,
+        } => format!(
+            "inserted {} layer {} ({})",
+            if *is_device { "device" } else { "instance" },
+            name,
+            library
+        ),
+        Event::CallChain { is_device, layers } if *is_device => {
+            format!("device chain: {}", chain(layers, "<Device>"))
+        }
+        Event::CallChain { layers, .. } => {
+            format!("instance chain: {}", chain(layers, "<Drivers>"))
+        }
+        // The category column already tells errors and warnings apart.
+        Event::Other if all || record.level() <= log::Level::Warn => {
+            record.message.trim().to_string()
+        }
+        Event::Other => return None,
+    };
+
+    Some(description)
+}
+
+fn print_timeline(records: &[Record], all: bool) {
+    let width = records
+        .last()
+        .map_or(1, |record| record.line.to_string().len());
+
+    for record in records {
+        if let Some(description) = describe(record, all) {
+            println!(
+                "{:>width$}  {:<16}  {}",
+                record.line,
+                category_names(record.flags),
+                description,
+                width = width
+            );
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) if error.is_empty() => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("vklog: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let log = match read_log(options.log.as_deref()) {
+        Ok(log) => log,
+        Err(error) => {
+            eprintln!(
+                "vklog: can't read {}: {}",
+                options.log.as_deref().unwrap_or("stdin"),
+                error
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let records = debug_log::parse(&log);
+    if options.json {
+        let records = records.iter().map(Record::to_json).collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&Value::from(records)).unwrap()
+        );
+    } else if options.summary {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&debug_log::summary(&records)).unwrap()
+        );
+    } else if records.is_empty() {
+        eprintln!("vklog: no loader messages found, was VK_LOADER_DEBUG set?");
+    } else {
+        print_timeline(&records, options.all);
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,456 @@
+//! Checks driver and layer manifests, and writes new ones for a built library.
+//!
+//! `lint` reads each manifest with the loader's own rules, the same ones vkloader-doctor applies, and additionally
+//! checks it against the schema of its `file_format_version`. The library it refers to is then opened, its exported
+//! entry points looked up and its interface version negotiated, so a manifest that passes is one the loader will load.
+//!
+//! `generate` does the opposite: it opens a driver or layer library, checks that it exports what the loader needs, and
+//! prints a manifest with the lowest file format version that can describe it.
+//!
+//! Needs the `tools` feature: `cargo run --features tools --bin vkmanifest -- lint my_layer.json`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use serde_json::{json, Map, Value};
+use vulkan_loader_sys::{
+    library::{check_driver_library, check_layer_library, LibraryCheck},
+    manifest::{
+        self, parse_version_string, version_to_string, Diagnostic, DriverManifest, LayerManifest,
+        Severity,
+    },
+    VK_HEADER_VERSION, VK_MAKE_API_VERSION,
+};
+
+const USAGE: &str = "\
+Usage: vkmanifest lint [--implicit | --explicit] [--strict] <manifest>...
+       vkmanifest generate driver <library> [options]
+       vkmanifest generate layer <library> --name <name> [options]
+
+Checks Vulkan driver and layer manifests the way the loader reads them, or writes a manifest for a library.
+
+Lint options:
+  --implicit, --explicit       Treat layer manifests as implicit or explicit layers. By default manifests in an
+                               implicit_layer.d directory are implicit and all others explicit
+  --strict                     Fail on warnings as well as on errors
+
+Generate options:
+  --output <file>              Write the manifest to <file>, with library_path relative to it when possible
+  --api-version <version>      The api_version to write. Drivers are asked through vkEnumerateInstanceVersion,
+                               layers default to the headers' version
+  --portability                Mark a driver as a portability driver
+  --name <name>                The layer's name
+  --description <text>         The layer's description
+  --implementation-version <n> The layer's implementation_version, 1 by default
+  --implicit                   Write an implicit layer, which needs --disable-environment
+  --disable-environment <var>=<value>
+  --enable-environment <var>=<value>
+                               The variables that disable and enable an implicit layer
+
+  -h, --help                   Print this message";
+
+struct LintOptions {
+    is_implicit: Option<bool>,
+    strict: bool,
+    manifests: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+struct GenerateOptions {
+    is_layer: bool,
+    library: PathBuf,
+    output: Option<PathBuf>,
+    api_version: Option<u32>,
+    is_portability_driver: bool,
+    name: Option<String>,
+    description: String,
+    implementation_version: Option<String>,
+    is_implicit: bool,
+    disable_environment: Option<(String, String)>,
+    enable_environment: Option<(String, String)>,
+}
+
+enum Command {
+    Lint(LintOptions),
+    Generate(GenerateOptions),
+}
+
+fn environment_variable(option: &str, value: String) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.into(), value.into())),
+        _ => Err(format!("{} needs a <var>=<value> argument", option)),
+    }
+}
+
+fn parse_lint_options(args: impl Iterator<Item = String>) -> Result<LintOptions, String> {
+    let mut options = LintOptions {
+        is_implicit: None,
+        strict: false,
+        manifests: Vec::new(),
+    };
+    for arg in args {
+        match arg.as_str() {
+            "--implicit" => options.is_implicit = Some(true),
+            "--explicit" => options.is_implicit = Some(false),
+            "--strict" => options.strict = true,
+            "-h" | "--help" => return Err(String::new()),
+            other if other.starts_with('-') => return Err(format!("unknown argument '{}'", other)),
+            manifest => options.manifests.push(manifest.into()),
+        }
+    }
+    if options.manifests.is_empty() {
+        return Err("lint needs at least one manifest".into());
+    }
+    Ok(options)
+}
+
+fn parse_generate_options(
+    mut args: impl Iterator<Item = String>,
+) -> Result<GenerateOptions, String> {
+    let mut options = GenerateOptions::default();
+    match args.next().as_deref() {
+        Some("driver") => options.is_layer = false,
+        Some("layer") => options.is_layer = true,
+        Some("-h" | "--help") => return Err(String::new()),
+        _ => return Err("generate needs 'driver' or 'layer'".into()),
+    }
+    options.library = args.next().ok_or("generate needs a library path")?.into();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--output" => options.output = Some(value()?.into()),
+            "--api-version" => options.api_version = Some(parse_version_string(&value()?)),
+            "--portability" => options.is_portability_driver = true,
+            "--name" => options.name = Some(value()?),
+            "--description" => options.description = value()?,
+            "--implementation-version" => options.implementation_version = Some(value()?),
+            "--implicit" => options.is_implicit = true,
+            "--disable-environment" => {
+                options.disable_environment = Some(environment_variable(&arg, value()?)?)
+            }
+            "--enable-environment" => {
+                options.enable_environment = Some(environment_variable(&arg, value()?)?)
+            }
+            "-h" | "--help" => return Err(String::new()),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    if options.is_layer {
+        if options.name.is_none() {
+            return Err("a layer needs a --name".into());
+        }
+        if options.is_implicit && options.disable_environment.is_none() {
+            return Err("an implicit layer needs a --disable-environment".into());
+        }
+    } else if options.name.is_some() || options.is_implicit {
+        return Err("--name and --implicit only apply to layers".into());
+    }
+    Ok(options)
+}
+
+fn parse_command() -> Result<Command, String> {
+    let mut args = env::args().skip(1);
+    match args.next().as_deref() {
+        Some("lint") => parse_lint_options(args).map(Command::Lint),
+        Some("generate") => parse_generate_options(args).map(Command::Generate),
+        Some("-h" | "--help") => Err(String::new()),
+        Some(other) => Err(format!("unknown command '{}'", other)),
+        None => Err("missing command".into()),
+    }
+}
+
+/// Everything found out about one manifest file.
+struct Findings {
+    path: PathBuf,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Findings {
+    fn push(&mut self, severity: Severity, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            message: message.into(),
+        });
+    }
+
+    fn extend(&mut self, prefix: &str, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            self.push(
+                diagnostic.severity,
+                format!("{}{}", prefix, diagnostic.message),
+            );
+        }
+    }
+
+    fn add_library(&mut self, prefix: &str, check: &LibraryCheck) {
+        let library = check.path.display();
+        match &check.error {
+            Some(error) => self.push(
+                Severity::Error,
+                format!("{}library {}: {}", prefix, library, error),
+            ),
+            None => {
+                if let Some(version) = check.interface_version {
+                    self.push(
+                        Severity::Info,
+                        format!(
+                            "{}library {}: interface version {}",
+                            prefix, library, version
+                        ),
+                    );
+                }
+                for note in &check.notes {
+                    self.push(
+                        Severity::Warning,
+                        format!("{}library {}: {}", prefix, library, note),
+                    );
+                }
+            }
+        }
+    }
+
+    fn worst(&self) -> Option<Severity> {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .max()
+    }
+}
+
+// Whether the loader would treat a layer manifest at `path` as implicit, going by the directory it sits in.
+fn is_in_implicit_layer_dir(path: &Path) -> bool {
+    path.parent()
+        .and_then(Path::file_name)
+        .is_some_and(|dir| dir == "implicit_layer.d")
+}
+
+fn lint_driver(findings: &mut Findings, json: &Value) {
+    let manifest = DriverManifest::parse(&findings.path, json);
+    findings.extend("", &manifest.diagnostics);
+    findings.extend("", &manifest::check_driver_schema(json));
+
+    if manifest.is_usable() {
+        if let Some(library) = manifest.resolved_library_path() {
+            findings.add_library("", &check_driver_library(library));
+        }
+    }
+}
+
+fn lint_layers(findings: &mut Findings, json: &Value, is_implicit: bool) {
+    let manifest = LayerManifest::parse(&findings.path, json, is_implicit);
+    findings.extend("", &manifest.diagnostics);
+    findings.extend("", &manifest::check_layer_schema(json, is_implicit));
+
+    for layer in &manifest.layers {
+        let prefix = format!("layer {}: ", layer.name);
+        findings.extend(&prefix, &layer.diagnostics);
+        if layer.is_usable() {
+            if let Some(library) = layer.resolved_library_path(&manifest.path) {
+                findings.add_library(&prefix, &check_layer_library(layer, library));
+            }
+        }
+    }
+}
+
+fn lint(path: &Path, is_implicit: Option<bool>) -> Findings {
+    let mut findings = Findings {
+        path: path.into(),
+        diagnostics: Vec::new(),
+    };
+
+    let json = match fs::read(path) {
+        Ok(contents) => match serde_json::from_slice::<Value>(&contents) {
+            Ok(json) => json,
+            Err(error) => {
+                findings.push(Severity::Error, format!("not valid JSON: {}", error));
+                return findings;
+            }
+        },
+        Err(error) => {
+            findings.push(Severity::Error, format!("cannot be read: {}", error));
+            return findings;
+        }
+    };
+
+    if json.get("ICD").is_some() {
+        lint_driver(&mut findings, &json);
+    } else if json.get("layer").is_some() || json.get("layers").is_some() {
+        let is_implicit = is_implicit.unwrap_or_else(|| is_in_implicit_layer_dir(path));
+        lint_layers(&mut findings, &json, is_implicit);
+    } else {
+        findings.push(
+            Severity::Error,
+            "neither a driver manifest with an 'ICD' object nor a layer manifest with 'layer' or 'layers'",
+        );
+    }
+    findings
+}
+
+fn run_lint(options: &LintOptions) -> ExitCode {
+    let mut failed = false;
+    for path in &options.manifests {
+        let findings = lint(path, options.is_implicit);
+        for diagnostic in &findings.diagnostics {
+            println!("{}: {}", path.display(), diagnostic);
+        }
+        let worst = findings.worst();
+        if worst.is_none_or(|worst| worst < Severity::Warning) {
+            println!("{}: ok", path.display());
+        }
+        let fails_at = if options.strict {
+            Severity::Warning
+        } else {
+            Severity::Error
+        };
+        failed |= worst.is_some_and(|worst| worst >= fails_at);
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+// The library_path to write: relative to the manifest when the library is in the same directory or below it, which
+// keeps the two relocatable together, and absolute otherwise.
+fn library_path_for(library: &Path, output: Option<&Path>) -> String {
+    let relative = output
+        .and_then(|output| output.parent())
+        .and_then(|dir| fs::canonicalize(dir).ok())
+        .and_then(|dir| library.strip_prefix(dir).ok().map(Path::to_path_buf));
+    match relative {
+        Some(relative) => format!("./{}", relative.display()),
+        None => library.display().to_string(),
+    }
+}
+
+fn environment_json((name, value): &(String, String)) -> Value {
+    let mut object = Map::new();
+    object.insert(name.clone(), json!(value));
+    Value::Object(object)
+}
+
+fn generate(options: &GenerateOptions) -> Result<Value, String> {
+    let library = fs::canonicalize(&options.library)
+        .map_err(|error| format!("cannot find {}: {}", options.library.display(), error))?;
+    let library_path = library_path_for(&library, options.output.as_deref());
+    // Where the manifest will be, to resolve library_path against when checking the result.
+    let manifest_path = options
+        .output
+        .clone()
+        .unwrap_or_else(|| library.with_extension("json"));
+
+    let (json, findings) = if options.is_layer {
+        let mut layer = Map::new();
+        layer.insert(
+            "name".into(),
+            json!(options.name.as_deref().unwrap_or_default()),
+        );
+        layer.insert("type".into(), json!("GLOBAL"));
+        layer.insert("library_path".into(), json!(library_path));
+        let api_version = options
+            .api_version
+            .unwrap_or_else(|| VK_MAKE_API_VERSION(0, 1, 3, VK_HEADER_VERSION));
+        layer.insert("api_version".into(), json!(version_to_string(api_version)));
+        let implementation_version = options.implementation_version.as_deref().unwrap_or("1");
+        layer.insert(
+            "implementation_version".into(),
+            json!(implementation_version),
+        );
+        layer.insert("description".into(), json!(options.description));
+        if let Some(variable) = &options.disable_environment {
+            layer.insert("disable_environment".into(), environment_json(variable));
+        }
+        if let Some(variable) = &options.enable_environment {
+            layer.insert("enable_environment".into(), environment_json(variable));
+        }
+
+        // 1.1.0 is the first version in which the loader negotiates with the layer.
+        let json = json!({ "file_format_version": "1.1.0", "layer": layer });
+        let mut findings = Findings {
+            path: manifest_path.clone(),
+            diagnostics: Vec::new(),
+        };
+        lint_layers(&mut findings, &json, options.is_implicit);
+        (json, findings)
+    } else {
+        let check = check_driver_library(library.clone());
+        if let Some(error) = &check.error {
+            return Err(format!("{}: {}", library.display(), error));
+        }
+        let api_version = options
+            .api_version
+            .or(check.api_version)
+            .unwrap_or(VK_MAKE_API_VERSION(0, 1, 0, 0));
+
+        let mut icd = Map::new();
+        icd.insert("library_path".into(), json!(library_path));
+        icd.insert("api_version".into(), json!(version_to_string(api_version)));
+        // is_portability_driver needs 1.0.1.
+        let file_format_version = if options.is_portability_driver {
+            icd.insert("is_portability_driver".into(), json!(true));
+            "1.0.1"
+        } else {
+            "1.0.0"
+        };
+
+        let json = json!({ "file_format_version": file_format_version, "ICD": icd });
+        let mut findings = Findings {
+            path: manifest_path.clone(),
+            diagnostics: Vec::new(),
+        };
+        lint_driver(&mut findings, &json);
+        (json, findings)
+    };
+
+    for diagnostic in &findings.diagnostics {
+        eprintln!("vkmanifest: {}", diagnostic);
+    }
+    if findings.worst() == Some(Severity::Error) {
+        return Err("the library cannot be described by a manifest the loader accepts".into());
+    }
+    Ok(json)
+}
+
+fn run_generate(options: &GenerateOptions) -> ExitCode {
+    let json = match generate(options) {
+        Ok(json) => json,
+        Err(error) => {
+            eprintln!("vkmanifest: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let text = serde_json::to_string_pretty(&json).unwrap() + "\n";
+    match &options.output {
+        Some(output) => {
+            if let Err(error) = fs::write(output, text) {
+                eprintln!("vkmanifest: cannot write {}: {}", output.display(), error);
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{}", text),
+    }
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    match parse_command() {
+        Ok(Command::Lint(options)) => run_lint(&options),
+        Ok(Command::Generate(options)) => run_generate(&options),
+        Err(error) if error.is_empty() => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("vkmanifest: {}\n\n{}", error, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
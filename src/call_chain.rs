@@ -0,0 +1,604 @@
+//! The instance and device call chains the loader would build for an application.
+//!
+//! [`CallChain::build`] takes the layers [`LayerDiscovery`] found and orders them the way
+//! `loader_enable_instance_layers` does: the enabled implicit layers first, then the layers named in
+//! `VK_INSTANCE_LAYERS`, the explicit layers forced on by `VK_LOADER_LAYERS_ENABLE` and finally the application's
+//! `ppEnabledLayerNames`, in that order, with meta layers replaced by their component layers. When a loader settings
+//! file is in use its order wins, like in `enable_correct_layers_from_settings`. The device chain is the instance chain
+//! without the layers that have no `vkGetDeviceProcAddr`.
+//!
+//! Layer libraries are opened to find out whether they load and what they export, so building a chain runs their
+//! initialisers, but nothing calls into the loader.
+//!
+//! Available with the `tools` feature.
+
+use std::{
+    collections::HashMap,
+    ffi::CStr,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    discovery::{
+        explicit_layer_decision, implicit_layer_decision, instance_layers_from_env, DriverFilters,
+        LayerControl, LayerDiscovery, LayerFilters, LoaderSettings, ManifestKind, SearchPaths,
+        INSTANCE_LAYERS_ENV_VAR, LAYERS_DISABLE_ENV_VAR, LAYERS_ENABLE_ENV_VAR,
+    },
+    library::{check_driver_library, check_layer_library},
+    manifest::{DriverManifest, Layer, OVERRIDE_LAYER_NAME},
+    VkInstanceCreateFlags, VkInstanceCreateInfo, VK_INSTANCE_CREATE_ENUMERATE_PORTABILITY_BIT_KHR,
+};
+
+/// The parts of a `VkInstanceCreateInfo` that decide what the call chain looks like.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstanceRequest {
+    /// `ppEnabledLayerNames`, in order.
+    pub enabled_layers: Vec<String>,
+    /// Whether `VK_INSTANCE_CREATE_ENUMERATE_PORTABILITY_BIT_KHR` is set, which lets portability drivers in.
+    pub enumerate_portability: bool,
+}
+
+impl InstanceRequest {
+    /// Reads the layers and flags out of `create_info`.
+    ///
+    /// # Safety
+    ///
+    /// `ppEnabledLayerNames` must point to `enabledLayerCount` valid, nul-terminated strings, as `vkCreateInstance`
+    /// requires.
+    pub unsafe fn from_create_info(create_info: &VkInstanceCreateInfo) -> InstanceRequest {
+        let enabled_layers = if create_info.ppEnabledLayerNames.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(
+                create_info.ppEnabledLayerNames,
+                create_info.enabledLayerCount as usize,
+            )
+            .iter()
+            .map(|&name| CStr::from_ptr(name).to_string_lossy().into_owned())
+            .collect()
+        };
+
+        InstanceRequest {
+            enabled_layers,
+            enumerate_portability: create_info.flags
+                & VK_INSTANCE_CREATE_ENUMERATE_PORTABILITY_BIT_KHR as VkInstanceCreateFlags
+                != 0,
+        }
+    }
+}
+
+/// A layer in the call chain.
+#[derive(Debug, Clone)]
+pub struct ActiveLayer {
+    pub name: String,
+    pub manifest_path: PathBuf,
+    pub library_path: PathBuf,
+    pub is_implicit: bool,
+    /// Why the layer is in the chain.
+    pub reason: String,
+    /// The meta layer the layer is a component of, when it was enabled through one.
+    pub meta_layer: Option<String>,
+    /// Whether the layer is part of the device chain as well.
+    pub has_device_proc_addr: bool,
+}
+
+impl ActiveLayer {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "manifest": self.manifest_path,
+            "library": self.library_path,
+            "implicit": self.is_implicit,
+            "reason": self.reason,
+            "meta_layer": self.meta_layer,
+            "device": self.has_device_proc_addr,
+        })
+    }
+}
+
+/// A layer that was asked for but did not make it into the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedLayer {
+    pub name: String,
+    pub reason: String,
+}
+
+/// The call chains of an instance and of the devices created from it.
+#[derive(Debug, Clone, Default)]
+pub struct CallChain {
+    /// The instance chain, starting with the layer closest to the application.
+    pub layers: Vec<ActiveLayer>,
+    pub skipped: Vec<SkippedLayer>,
+    /// The driver manifests whose drivers sit at the end of the chain, behind the loader's terminators.
+    pub drivers: Vec<PathBuf>,
+    /// The loader settings file that decided the order, if one is in use.
+    pub settings_path: Option<PathBuf>,
+}
+
+impl CallChain {
+    /// Builds the chains for `request`, using the layers in `discovery` and the current environment.
+    pub fn build(
+        request: &InstanceRequest,
+        discovery: &LayerDiscovery,
+        filters: &LayerFilters,
+        settings: Option<&LoaderSettings>,
+    ) -> CallChain {
+        let mut builder = Builder::new(request, discovery, filters);
+        match settings {
+            Some(settings) => builder.enable_from_settings(settings),
+            None => builder.enable_from_environment(),
+        }
+
+        let mut chain = builder.chain;
+        chain.drivers = drivers(request);
+        chain.settings_path = settings.map(|settings| settings.path.clone());
+        chain
+    }
+
+    /// The device chain, which only keeps the layers that intercept device functions.
+    pub fn device_layers(&self) -> impl Iterator<Item = &ActiveLayer> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.has_device_proc_addr)
+    }
+
+    /// Both chains drawn the way the loader logs them, followed by the layers that were left out.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        write_text_chain(&mut text, "Instance", self.layers.iter(), "<Drivers>");
+        for driver in &self.drivers {
+            text += &format!("      {}\n", driver.display());
+        }
+        text.push('\n');
+        write_text_chain(&mut text, "Device", self.device_layers(), "<Device>");
+
+        if let Some(settings_path) = &self.settings_path {
+            text += &format!(
+                "\nOrdered by the loader settings file {}\n",
+                settings_path.display()
+            );
+        }
+        if !self.skipped.is_empty() {
+            text += "\nSkipped layers:\n";
+            for skipped in &self.skipped {
+                text += &format!("  {}: {}\n", skipped.name, skipped.reason);
+            }
+        }
+        text
+    }
+
+    /// Both chains as a Graphviz digraph, one cluster per chain.
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("digraph call_chain {\n    rankdir=TB;\n    node [shape=box];\n");
+        write_dot_chain(
+            &mut dot,
+            "instance",
+            "Instance chain",
+            self.layers.iter(),
+            "Drivers",
+        );
+        write_dot_chain(
+            &mut dot,
+            "device",
+            "Device chain",
+            self.device_layers(),
+            "Device",
+        );
+        for (index, driver) in self.drivers.iter().enumerate() {
+            dot += &format!(
+                "    instance_driver_{} [label={}, shape=note];\n    instance_end -> instance_driver_{};\n",
+                index,
+                dot_string(&driver.display().to_string()),
+                index
+            );
+        }
+        dot += "}\n";
+        dot
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "instance": self.layers.iter().map(ActiveLayer::to_json).collect::<Vec<_>>(),
+            "device": self.device_layers().map(|layer| layer.name.as_str()).collect::<Vec<_>>(),
+            "drivers": self.drivers,
+            "skipped": self
+                .skipped
+                .iter()
+                .map(|skipped| json!({ "name": skipped.name, "reason": skipped.reason }))
+                .collect::<Vec<_>>(),
+            "settings": self.settings_path,
+        })
+    }
+}
+
+fn write_text_chain<'a>(
+    text: &mut String,
+    title: &str,
+    layers: impl Iterator<Item = &'a ActiveLayer>,
+    end: &str,
+) {
+    *text += &format!(
+        "{} chain:\n  <Application>\n    ||\n  <Loader>\n    ||\n",
+        title
+    );
+    for layer in layers {
+        *text += &format!(
+            "  {}\n      Type: {}\n",
+            layer.name,
+            if layer.is_implicit {
+                "Implicit"
+            } else {
+                "Explicit"
+            }
+        );
+        if let Some(meta_layer) = &layer.meta_layer {
+            *text += &format!("      Component of: {}\n", meta_layer);
+        }
+        *text += &format!(
+            "      Enabled: {}\n      Manifest: {}\n      Library: {}\n    ||\n",
+            layer.reason,
+            layer.manifest_path.display(),
+            layer.library_path.display()
+        );
+    }
+    *text += &format!("  {}\n", end);
+}
+
+fn write_dot_chain<'a>(
+    dot: &mut String,
+    id: &str,
+    title: &str,
+    layers: impl Iterator<Item = &'a ActiveLayer>,
+    end: &str,
+) {
+    *dot += &format!(
+        "    subgraph cluster_{id} {{\n        label={};\n        {id}_application [label=\"Application\", shape=ellipse];\n        {id}_loader [label=\"Loader\"];\n",
+        dot_string(title),
+        id = id
+    );
+    let mut previous = format!("{}_loader", id);
+    *dot += &format!("        {}_application -> {};\n", id, previous);
+    for (index, layer) in layers.enumerate() {
+        let node = format!("{}_layer_{}", id, index);
+        *dot += &format!(
+            "        {} [label={}{}];\n        {} -> {};\n",
+            node,
+            dot_string(&layer.name),
+            if layer.is_implicit {
+                ", style=dashed"
+            } else {
+                ""
+            },
+            previous,
+            node
+        );
+        previous = node;
+    }
+    *dot += &format!(
+        "        {id}_end [label={}, shape=ellipse];\n        {} -> {id}_end;\n    }}\n",
+        dot_string(end),
+        previous,
+        id = id
+    );
+}
+
+/// `value` as a quoted Graphviz string.
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The drivers the loader would load, following the same rules as vkloader-doctor.
+fn drivers(request: &InstanceRequest) -> Vec<PathBuf> {
+    let filters = DriverFilters::from_env();
+    SearchPaths::new(ManifestKind::Driver, None)
+        .manifests()
+        .into_iter()
+        .map(DriverManifest::load)
+        .filter(|manifest| {
+            manifest.is_usable()
+                && filters.rejection(&manifest.path).is_none()
+                && (request.enumerate_portability || !manifest.is_portability_driver)
+                && manifest
+                    .resolved_library_path()
+                    .is_some_and(|path| check_driver_library(path).error.is_none())
+        })
+        .map(|manifest| manifest.path)
+        .collect()
+}
+
+struct Builder<'a> {
+    request: &'a InstanceRequest,
+    filters: &'a LayerFilters,
+    override_layer: Option<&'a Layer>,
+    instance_layers: Vec<String>,
+    /// The layers the loader keeps after scanning, the first of each name.
+    layers: Vec<(&'a Path, &'a Layer)>,
+    /// Layers the loader drops while scanning, and why.
+    removed: HashMap<&'a str, String>,
+    /// The meta layers being expanded, to stop at ones that include themselves.
+    expanding: Vec<&'a str>,
+    chain: CallChain,
+}
+
+impl<'a> Builder<'a> {
+    fn new(
+        request: &'a InstanceRequest,
+        discovery: &'a LayerDiscovery,
+        filters: &'a LayerFilters,
+    ) -> Builder<'a> {
+        let mut builder = Builder {
+            request,
+            filters,
+            override_layer: discovery.override_layer.as_ref(),
+            instance_layers: instance_layers_from_env(),
+            layers: Vec::new(),
+            removed: HashMap::new(),
+            expanding: Vec::new(),
+            chain: CallChain::default(),
+        };
+
+        let blacklisted = builder
+            .override_layer
+            .map(|layer| layer.blacklisted_layers.as_slice())
+            .unwrap_or_default();
+        for (manifest_path, layer) in &discovery.layers {
+            if !layer.is_usable() || builder.find(&layer.name).is_some() {
+                continue;
+            }
+            if blacklisted.contains(&layer.name) {
+                builder.removed.insert(
+                    &layer.name,
+                    format!("blacklisted by {}", OVERRIDE_LAYER_NAME),
+                );
+                continue;
+            }
+            builder.layers.push((manifest_path, layer));
+        }
+
+        // Like `verify_all_meta_layers`, meta layers go when any of their components is missing.
+        let missing = builder
+            .layers
+            .iter()
+            .copied()
+            .filter_map(|(_, layer)| {
+                let missing = layer
+                    .component_layers
+                    .iter()
+                    .filter(|component| builder.find(component).is_none())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (!missing.is_empty()).then_some((layer.name.as_str(), missing))
+            })
+            .collect::<Vec<_>>();
+        for (name, components) in missing {
+            builder.layers.retain(|(_, layer)| layer.name != name);
+            builder.removed.insert(
+                name,
+                format!(
+                    "meta layer with missing component layers: {}",
+                    components.join(", ")
+                ),
+            );
+        }
+
+        builder
+    }
+
+    fn find(&self, name: &str) -> Option<(&'a Path, &'a Layer)> {
+        self.layers
+            .iter()
+            .find(|(_, layer)| layer.name == name)
+            .copied()
+    }
+
+    fn skip(&mut self, name: &str, reason: impl Into<String>) {
+        if !self
+            .chain
+            .skipped
+            .iter()
+            .any(|skipped| skipped.name == name)
+        {
+            self.chain.skipped.push(SkippedLayer {
+                name: name.to_string(),
+                reason: reason.into(),
+            });
+        }
+    }
+
+    /// Adds a layer to the chain, or its components if it is a meta layer, unless it is already there.
+    fn activate(&mut self, name: &str, reason: &str, meta_layer: Option<&str>) {
+        if self.chain.layers.iter().any(|layer| layer.name == name) {
+            return;
+        }
+        let Some((manifest_path, layer)) = self.find(name) else {
+            let reason = self
+                .removed
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| String::from("no manifest describes it"));
+            self.skip(name, reason);
+            return;
+        };
+
+        if layer.is_meta_layer() {
+            if self.expanding.contains(&layer.name.as_str()) {
+                self.skip(name, "meta layer that includes itself");
+                return;
+            }
+            self.expanding.push(&layer.name);
+            for component in &layer.component_layers {
+                self.activate(component, reason, Some(&layer.name));
+            }
+            self.expanding.pop();
+            return;
+        }
+
+        let Some(library_path) = layer.resolved_library_path(manifest_path) else {
+            self.skip(name, "the manifest has no library_path");
+            return;
+        };
+        let library = check_layer_library(layer, library_path.clone());
+        if let Some(error) = library.error {
+            self.skip(name, format!("library failed to load: {}", error));
+            return;
+        }
+
+        self.chain.layers.push(ActiveLayer {
+            name: layer.name.clone(),
+            manifest_path: manifest_path.to_path_buf(),
+            library_path,
+            is_implicit: layer.is_implicit,
+            reason: reason.to_string(),
+            meta_layer: meta_layer.map(String::from),
+            has_device_proc_addr: library.has_device_proc_addr,
+        });
+    }
+
+    /// Enables an application layer unless `VK_LOADER_LAYERS_DISABLE` takes it away, like
+    /// `loader_add_layer_names_to_list`.
+    fn enable_for_application(&mut self, name: &str) {
+        match self.find(name) {
+            Some((_, layer))
+                if !layer.is_implicit && self.filters.is_disabled(&layer.name, false) =>
+            {
+                self.skip(name, format!("disabled by {}", LAYERS_DISABLE_ENV_VAR));
+            }
+            _ => self.activate(name, "enabled by the application", None),
+        }
+    }
+
+    fn enable_from_environment(&mut self) {
+        for (_, layer) in self.layers.clone() {
+            if layer.is_implicit {
+                let decision = implicit_layer_decision(layer, self.filters, self.override_layer);
+                if decision.enabled {
+                    self.activate(&layer.name, &decision.reason, None);
+                }
+            }
+        }
+
+        for name in self.instance_layers.clone() {
+            self.activate(
+                &name,
+                &format!("listed in {}", INSTANCE_LAYERS_ENV_VAR),
+                None,
+            );
+        }
+
+        for (_, layer) in self.layers.clone() {
+            if !layer.is_implicit && self.filters.is_enabled(&layer.name) {
+                self.activate(
+                    &layer.name,
+                    &format!("forced on by {}", LAYERS_ENABLE_ENV_VAR),
+                    None,
+                );
+            }
+        }
+
+        let request = self.request;
+        for name in &request.enabled_layers {
+            self.enable_for_application(name);
+        }
+    }
+
+    /// Why the environment and the application would enable `layer`, if they do.
+    fn environment_reason(&self, layer: &Layer) -> Option<String> {
+        let decision = if layer.is_implicit {
+            implicit_layer_decision(layer, self.filters, self.override_layer)
+        } else {
+            explicit_layer_decision(
+                layer,
+                self.filters,
+                &self.instance_layers,
+                self.override_layer,
+            )
+        };
+        if decision.enabled {
+            Some(decision.reason)
+        } else if self.request.enabled_layers.contains(&layer.name)
+            && !self.filters.is_disabled(&layer.name, layer.is_implicit)
+        {
+            Some(String::from("enabled by the application"))
+        } else {
+            None
+        }
+    }
+
+    /// Walks the layers in the order of the settings file, with the layers it doesn't list at its
+    /// `unordered_layer_location`.
+    fn enable_from_settings(&mut self, settings: &LoaderSettings) {
+        let unlisted = self
+            .layers
+            .iter()
+            .filter(|(manifest_path, layer)| {
+                settings.configuration(&layer.name, manifest_path).is_none()
+            })
+            .map(|(_, layer)| *layer)
+            .collect::<Vec<_>>();
+
+        for configuration in &settings.layers {
+            if configuration.control == LayerControl::UnorderedLayerLocation {
+                for layer in &unlisted {
+                    if let Some(reason) = self.environment_reason(layer) {
+                        self.activate(&layer.name, &reason, None);
+                    }
+                }
+                continue;
+            }
+
+            let Some(name) = configuration.name.as_deref() else {
+                continue;
+            };
+            let Some((_, layer)) = self.find(name) else {
+                if configuration.control != LayerControl::Off {
+                    self.activate(name, "listed in the loader settings file", None);
+                }
+                continue;
+            };
+            match configuration.control {
+                LayerControl::Off => {
+                    if self
+                        .request
+                        .enabled_layers
+                        .iter()
+                        .any(|enabled| enabled == name)
+                    {
+                        self.skip(name, "turned off by the loader settings file");
+                    }
+                }
+                LayerControl::On if !self.filters.is_disabled(name, layer.is_implicit) => {
+                    self.activate(name, "turned on by the loader settings file", None);
+                }
+                _ => {
+                    if let Some(reason) = self.environment_reason(layer) {
+                        self.activate(name, &reason, None);
+                    }
+                }
+            }
+        }
+
+        if !settings.has_unordered_layer_location() {
+            for layer in unlisted {
+                if self.request.enabled_layers.contains(&layer.name) {
+                    self.skip(
+                        &layer.name,
+                        format!(
+                            "not listed in the loader settings file {}",
+                            settings.path.display()
+                        ),
+                    );
+                }
+            }
+        }
+
+        let request = self.request;
+        for name in &request.enabled_layers {
+            if self.find(name).is_none() {
+                self.activate(name, "enabled by the application", None);
+            }
+        }
+    }
+}
@@ -0,0 +1,832 @@
+//! Reading the output the loader writes to `stderr` when `VK_LOADER_DEBUG` is set.
+//!
+//! `loader_log` in loader/log.c starts every line with the message's categories, such as `ERROR | DRIVER:`, padded to
+//! 19 columns. [`parse_line`] undoes that, and [`Parser`] goes on to recognise the messages that matter when working
+//! out why an application did not end up with the drivers and layers it expected: where manifests were searched for,
+//! which ones were found, which drivers and layers were skipped and why, and the layer chains that were built. Every
+//! other message is kept as [`Event::Other`], so a log can still be replayed in full.
+//!
+//! The patterns are the format strings of loader/loader.c, so this has to follow them when they change.
+//!
+//! Available with the `tools` feature.
+
+use serde_json::{json, Value};
+
+use crate::loader_log::{
+    self, VULKAN_LOADER_DEBUG_BIT, VULKAN_LOADER_DRIVER_BIT, VULKAN_LOADER_ERROR_BIT,
+    VULKAN_LOADER_INFO_BIT, VULKAN_LOADER_LAYER_BIT, VULKAN_LOADER_PERF_BIT,
+    VULKAN_LOADER_WARN_BIT,
+};
+
+/// The width `loader_log` pads the category header to.
+const HEADER_WIDTH: usize = 19;
+
+/// Some applications and log collectors put this in front of every line they forward from the loader.
+const FORWARDED_PREFIX: &str = "[Vulkan Loader] ";
+
+/// The category names of the header, in the order `loader_log` writes them.
+const CATEGORIES: &[(&str, u32)] = &[
+    ("ERROR", VULKAN_LOADER_ERROR_BIT),
+    ("WARNING", VULKAN_LOADER_WARN_BIT),
+    ("INFO", VULKAN_LOADER_INFO_BIT),
+    ("DEBUG", VULKAN_LOADER_DEBUG_BIT),
+    ("PERF", VULKAN_LOADER_PERF_BIT),
+    ("DRIVER", VULKAN_LOADER_DRIVER_BIT),
+    ("LAYER", VULKAN_LOADER_LAYER_BIT),
+];
+
+/// Splits a line written by `loader_log` into its `VULKAN_LOADER_*_BIT` flags and the message. Returns `None` for lines
+/// that did not come from the loader.
+pub fn parse_line(line: &str) -> Option<(u32, &str)> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let line = line.strip_prefix(FORWARDED_PREFIX).unwrap_or(line);
+    let (header, message) = line.split_once(": ")?;
+
+    let mut flags = 0;
+    // A message without any of the categories above still gets a header, just an empty one.
+    if !header.is_empty() {
+        for name in header.split(" | ") {
+            let (_, bit) = CATEGORIES.iter().find(|(category, _)| *category == name)?;
+            flags |= bit;
+        }
+    }
+
+    // Only strip the padding, the message itself may well start with spaces.
+    let padding = HEADER_WIDTH.saturating_sub(header.len() + 2);
+    let padding = message
+        .bytes()
+        .take(padding)
+        .take_while(|&byte| byte == b' ')
+        .count();
+    Some((flags, &message[padding..]))
+}
+
+/// The header `loader_log` would write for `flags`, without the colon.
+pub fn category_names(flags: u32) -> String {
+    CATEGORIES
+        .iter()
+        .filter(|(_, bit)| flags & bit != 0)
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Matches `message` against a `loader_log` format string in which every conversion has been replaced with `{}`, and
+/// returns what each of them stood for.
+fn scan<'a>(message: &'a str, format: &str) -> Option<Vec<&'a str>> {
+    let mut literals = format.split("{}");
+    let mut rest = message.strip_prefix(literals.next()?)?;
+    let literals = literals.collect::<Vec<_>>();
+
+    let mut captures = Vec::new();
+    for (index, literal) in literals.iter().enumerate() {
+        let end = if index + 1 == literals.len() {
+            rest.strip_suffix(literal)?.len()
+        } else {
+            rest.find(literal)?
+        };
+        captures.push(&rest[..end]);
+        rest = &rest[end + literal.len()..];
+    }
+
+    rest.is_empty().then_some(captures)
+}
+
+/// Drops the `function_name: ` most loader messages start with.
+fn strip_function_name(message: &str) -> &str {
+    match message.split_once(": ") {
+        Some((function, rest))
+            if !function.is_empty()
+                && function
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+        {
+            rest.trim_start()
+        }
+        _ => message,
+    }
+}
+
+/// Driver messages naming the manifest or library that is skipped as their first conversion.
+const SKIPPED_DRIVERS: &[(&str, &str)] = &[
+    (
+        "loader_parse_icd_manifest: ICD JSON {} does not have a 'file_format_version' field. Skipping ICD JSON.",
+        "the manifest has no file_format_version",
+    ),
+    (
+        "loader_parse_icd_manifest: Failed retrieving ICD JSON {} 'file_format_version' field. Skipping ICD JSON",
+        "the manifest's file_format_version is not a string",
+    ),
+    (
+        "loader_parse_icd_manifest: Can not find 'ICD' object in ICD JSON file {}. Skipping ICD JSON",
+        "the manifest has no ICD object",
+    ),
+    (
+        "loader_parse_icd_manifest: Failed to find 'library_path' object in ICD JSON file {}. Skipping ICD JSON.",
+        "the manifest has no library_path",
+    ),
+    (
+        "loader_parse_icd_manifest: Failed retrieving ICD JSON {} 'library_path' field. Skipping ICD JSON.",
+        "the manifest's library_path is not a string",
+    ),
+    (
+        "loader_parse_icd_manifest: ICD JSON {} 'library_path' field is empty. Skipping ICD JSON.",
+        "the manifest's library_path is empty",
+    ),
+    (
+        "loader_parse_icd_manifest: ICD JSON {} does not have an 'api_version' field. Skipping ICD JSON.",
+        "the manifest has no api_version",
+    ),
+    (
+        "loader_parse_icd_manifest: Failed retrieving ICD JSON {} 'api_version' field. Skipping ICD JSON.",
+        "the manifest's api_version is not a string",
+    ),
+    (
+        "loader_parse_icd_manifest: Driver's ICD JSON {} 'api_version' field contains a non-zero variant value of {}.  \
+         Skipping ICD JSON.",
+        "the manifest's api_version has a non-zero variant",
+    ),
+    (
+        "Driver \"{}\" ignored because it was disabled by env var '{}'",
+        "disabled by VK_LOADER_DRIVERS_DISABLE",
+    ),
+    (
+        "Driver \"{}\" ignored because not selected by env var '{}'",
+        "not selected by VK_LOADER_DRIVERS_SELECT",
+    ),
+    (
+        "loader_icd_scan: Failed loading library associated with ICD JSON {}. Ignoring this JSON",
+        "the library failed to load",
+    ),
+    (
+        "Requested layer {} was wrong bit-type. Ignoring this JSON",
+        "the library is built for a different architecture",
+    ),
+    (
+        "loader_scanned_icd_add: ICD {} doesn't support interface version compatible with loader, skip this ICD.",
+        "the library doesn't support an interface version the loader supports",
+    ),
+    (
+        "loader_scanned_icd_add: ICD {} reports an interface version of {} but doesn't export vk_icdGetInstanceProcAddr, \
+         skip this ICD.",
+        "the library negotiates an interface version but doesn't export vk_icdGetInstanceProcAddr",
+    ),
+    (
+        "loader_scanned_icd_add: Attempt to retrieve either 'vkGetInstanceProcAddr' or 'vk_icdGetInstanceProcAddr' from \
+         ICD {} failed.",
+        "the library exports neither vk_icdGetInstanceProcAddr nor vkGetInstanceProcAddr",
+    ),
+    (
+        "loader_scanned_icd_add:  Failed querying 'vkCreateInstance' via dlsym/loadlibrary for ICD {}",
+        "the library doesn't export vkCreateInstance",
+    ),
+    (
+        "loader_scanned_icd_add: Could not get 'vkEnumerateInstanceExtensionProperties' via dlsym/loadlibrary for ICD {}",
+        "the library doesn't export vkEnumerateInstanceExtensionProperties",
+    ),
+    (
+        "loader_scanned_icd_add: Could not get 'vkCreateInstance' via 'vk_icdGetInstanceProcAddr' for ICD {}",
+        "vk_icdGetInstanceProcAddr doesn't return vkCreateInstance",
+    ),
+    (
+        "loader_scanned_icd_add: Could not get 'vkEnumerateInstanceExtensionProperties' via 'vk_icdGetInstanceProcAddr' \
+         for ICD {}",
+        "vk_icdGetInstanceProcAddr doesn't return vkEnumerateInstanceExtensionProperties",
+    ),
+];
+
+/// Layer messages naming the layer that is skipped or left out as their first conversion.
+const SKIPPED_LAYERS: &[(&str, &str)] = &[
+    (
+        "Layer \"{}\" forced disabled because name matches filter of env var '{}'.",
+        "disabled by VK_LOADER_LAYERS_DISABLE",
+    ),
+    (
+        "Implicit layer \"{}\" forced disabled because name matches filter of env var '{}'.",
+        "disabled by VK_LOADER_LAYERS_DISABLE",
+    ),
+    (
+        "loader_add_layer_names_to_list: Unable to find layer \"{}\"",
+        "no manifest provides it",
+    ),
+    (
+        "Layer \"{}\" has an 'api_version' field which contains a non-zero variant value of {}.  Skipping Layer.",
+        "the manifest's api_version has a non-zero variant",
+    ),
+    (
+        "Requested layer \"{}\" was not loaded{}",
+        "the library was not loaded",
+    ),
+    (
+        "Requested layer \"{}\" was wrong bit-type{}",
+        "the library is built for a different architecture",
+    ),
+    (
+        "Requested layer \"{}\" failed to load{}",
+        "the library failed to load",
+    ),
+];
+
+/// Layer messages naming the manifest that is skipped as their first conversion.
+const SKIPPED_LAYER_MANIFESTS: &[(&str, &str)] = &[
+    (
+        "Layer located at {} didn't find required layer value \"name\" in manifest JSON file, skipping this layer",
+        "the manifest has no name",
+    ),
+    (
+        "Layer located at {} didn't find required layer value \"type\" in manifest JSON file, skipping this layer",
+        "the manifest has no type",
+    ),
+    (
+        "Layer located at {} didn't find required layer value \"api_version\" in manifest JSON file, skipping this layer",
+        "the manifest has no api_version",
+    ),
+    (
+        "Layer located at {} didn't find required layer value \"implementation_version\" in manifest JSON file, \
+         skipping this layer",
+        "the manifest has no implementation_version",
+    ),
+    (
+        "Layer located at {} didn't find required layer value \"description\" in manifest JSON file, skipping this layer",
+        "the manifest has no description",
+    ),
+];
+
+/// Whether a message the tables above don't know still says a driver is skipped.
+fn skips_driver(message: &str) -> bool {
+    [
+        "Skipping ICD JSON",
+        "skip this ICD",
+        "skipping this driver",
+        "Ignoring this JSON",
+    ]
+    .iter()
+    .any(|phrase| message.contains(phrase))
+}
+
+/// Whether a message the tables above don't know still says a layer is skipped.
+fn skips_layer(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains("skipping this layer") || message.contains("skipping layer")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Component {
+    Driver,
+    Layer,
+}
+
+impl Component {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Component::Driver => "driver",
+            Component::Layer => "layer",
+        }
+    }
+}
+
+/// One layer of a chain as printed by `vkCreateInstance` and `vkCreateDevice`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChainLayer {
+    pub name: String,
+    pub is_implicit: bool,
+    pub disable_environment: Option<String>,
+    pub manifest: Option<String>,
+    pub library: Option<String>,
+}
+
+impl ChainLayer {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "implicit": self.is_implicit,
+            "disable_environment": self.disable_environment,
+            "manifest": self.manifest,
+            "library": self.library,
+        })
+    }
+}
+
+/// What a loader message says, as far as it was recognised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// `Vulkan Loader Version x.y.z`, logged when the loader is first used.
+    LoaderVersion(String),
+    /// One pass over a manifest search path, with the directories searched and the files that turned up.
+    ManifestSearch {
+        component: Component,
+        locations: Vec<String>,
+        files: Vec<String>,
+    },
+    /// A manifest the loader read, with its `file_format_version`.
+    ManifestFound {
+        component: Component,
+        path: String,
+        file_format_version: String,
+    },
+    /// A driver the loader skipped. `path` is the manifest or library named in the message, or the manifest found last
+    /// when the message names neither.
+    DriverSkipped {
+        path: Option<String>,
+        reason: String,
+    },
+    /// A layer the loader skipped, disabled or failed to load. Messages about a broken manifest usually only name the
+    /// manifest, or nothing at all, in which case `manifest` is the one found last.
+    LayerSkipped {
+        name: Option<String>,
+        manifest: Option<String>,
+        reason: String,
+    },
+    /// A layer library that was opened.
+    LayerLibraryLoaded(String),
+    /// A layer added to an instance or device chain.
+    LayerInserted {
+        name: String,
+        library: String,
+        is_device: bool,
+    },
+    /// The chain set up by `vkCreateInstance` or `vkCreateDevice`, from the application down to the drivers. Only
+    /// logged when the layer category is enabled.
+    CallChain {
+        is_device: bool,
+        layers: Vec<ChainLayer>,
+    },
+    /// Anything else.
+    Other,
+}
+
+impl Event {
+    pub fn to_json(&self) -> Value {
+        match self {
+            Event::LoaderVersion(version) => {
+                json!({ "type": "loader_version", "version": version })
+            }
+            Event::ManifestSearch {
+                component,
+                locations,
+                files,
+            } => json!({
+                "type": "manifest_search",
+                "component": component.as_str(),
+                "locations": locations,
+                "files": files,
+            }),
+            Event::ManifestFound {
+                component,
+                path,
+                file_format_version,
+            } => json!({
+                "type": "manifest_found",
+                "component": component.as_str(),
+                "path": path,
+                "file_format_version": file_format_version,
+            }),
+            Event::DriverSkipped { path, reason } => {
+                json!({ "type": "driver_skipped", "path": path, "reason": reason })
+            }
+            Event::LayerSkipped {
+                name,
+                manifest,
+                reason,
+            } => json!({
+                "type": "layer_skipped",
+                "name": name,
+                "manifest": manifest,
+                "reason": reason,
+            }),
+            Event::LayerLibraryLoaded(library) => {
+                json!({ "type": "layer_library_loaded", "library": library })
+            }
+            Event::LayerInserted {
+                name,
+                library,
+                is_device,
+            } => json!({
+                "type": "layer_inserted",
+                "name": name,
+                "library": library,
+                "device": is_device,
+            }),
+            Event::CallChain { is_device, layers } => json!({
+                "type": "call_chain",
+                "device": is_device,
+                "layers": layers.iter().map(ChainLayer::to_json).collect::<Vec<_>>(),
+            }),
+            Event::Other => json!({ "type": "other" }),
+        }
+    }
+}
+
+/// A loader message and what it was recognised as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The line the message starts on, counting from 1.
+    pub line: usize,
+    /// The `VULKAN_LOADER_*_BIT` flags from the header.
+    pub flags: u32,
+    /// The message, or the first line of it for the ones spread over several lines.
+    pub message: String,
+    pub event: Event,
+}
+
+impl Record {
+    pub fn level(&self) -> log::Level {
+        loader_log::level(self.flags)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "line": self.line,
+            "categories": category_names(self.flags),
+            "message": self.message,
+            "event": self.event.to_json(),
+        })
+    }
+}
+
+/// A message the loader spreads over several calls to `loader_log`.
+enum Block {
+    Search {
+        component: Component,
+        locations: Vec<String>,
+        files: Vec<String>,
+        in_files: bool,
+    },
+    Chain {
+        is_device: bool,
+        layers: Vec<ChainLayer>,
+    },
+}
+
+/// Turns a log into [`Record`]s, one line at a time.
+#[derive(Default)]
+pub struct Parser {
+    records: Vec<Record>,
+    line: usize,
+    /// The block being read, with the line and flags it started with and its first message.
+    block: Option<(usize, u32, String, Block)>,
+    /// The manifest found last, which messages that don't name one refer to.
+    manifest: Option<(Component, String)>,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser::default()
+    }
+
+    /// Reads the next line of the log. Lines that didn't come from the loader are skipped, but still counted.
+    pub fn push_line(&mut self, line: &str) {
+        self.line += 1;
+        let Some((flags, message)) = parse_line(line) else {
+            return;
+        };
+
+        if self.continue_block(message) {
+            return;
+        }
+        self.finish_block();
+
+        if let Some(block) = start_block(message) {
+            self.block = Some((self.line, flags, message.to_string(), block));
+            return;
+        }
+
+        let event = self.classify(message);
+        self.records.push(Record {
+            line: self.line,
+            flags,
+            message: message.to_string(),
+            event,
+        });
+    }
+
+    /// Returns everything read so far.
+    pub fn finish(mut self) -> Vec<Record> {
+        self.finish_block();
+        self.records
+    }
+
+    /// Adds `message` to the block being read, if it belongs to it.
+    fn continue_block(&mut self, message: &str) -> bool {
+        let Some((_, _, _, block)) = self.block.as_mut() else {
+            return false;
+        };
+
+        let mut is_last = false;
+        match block {
+            Block::Search {
+                locations,
+                files,
+                in_files,
+                ..
+            } => {
+                if message == "   In following locations:" {
+                    *in_files = false;
+                } else if message == "   Found the following files:" {
+                    *in_files = true;
+                } else if message == "   Found no files" {
+                    is_last = true;
+                } else if let Some(path) = message.strip_prefix("      ") {
+                    if *in_files {
+                        files.push(path.to_string());
+                    } else {
+                        locations.push(path.to_string());
+                    }
+                } else {
+                    return false;
+                }
+            }
+            Block::Chain { layers, .. } => {
+                let trimmed = message.trim();
+                if let Some(kind) = trimmed.strip_prefix("Type:") {
+                    if let Some(layer) = layers.last_mut() {
+                        layer.is_implicit = kind.trim() == "Implicit";
+                    }
+                } else if let Some(variable) = trimmed.strip_prefix("Disable Env Var:") {
+                    if let Some(layer) = layers.last_mut() {
+                        layer.disable_environment = Some(variable.trim().to_string());
+                    }
+                } else if let Some(manifest) = trimmed.strip_prefix("Manifest:") {
+                    if let Some(layer) = layers.last_mut() {
+                        layer.manifest = Some(manifest.trim().to_string());
+                    }
+                } else if let Some(library) = trimmed.strip_prefix("Library:") {
+                    if let Some(layer) = layers.last_mut() {
+                        layer.library = Some(library.trim().to_string());
+                    }
+                } else if trimmed == "<Drivers>" || trimmed == "<Device>" {
+                    is_last = true;
+                } else if trimmed == "||" || trimmed == "<Application>" || trimmed == "<Loader>" {
+                    // The arrows and the application and loader ends of the chain say nothing new.
+                } else if let Some(name) = message
+                    .strip_prefix("   ")
+                    .filter(|name| !name.starts_with(' ') && !name.is_empty())
+                {
+                    layers.push(ChainLayer {
+                        name: name.to_string(),
+                        ..Default::default()
+                    });
+                } else {
+                    return false;
+                }
+            }
+        }
+
+        if is_last {
+            self.finish_block();
+        }
+        true
+    }
+
+    fn finish_block(&mut self) {
+        let Some((line, flags, message, block)) = self.block.take() else {
+            return;
+        };
+
+        let event = match block {
+            Block::Search {
+                component,
+                locations,
+                files,
+                ..
+            } => Event::ManifestSearch {
+                component,
+                locations,
+                files,
+            },
+            Block::Chain { is_device, layers } => Event::CallChain { is_device, layers },
+        };
+        self.records.push(Record {
+            line,
+            flags,
+            message,
+            event,
+        });
+    }
+
+    fn current_manifest(&self, component: Component) -> Option<String> {
+        self.manifest
+            .as_ref()
+            .filter(|(kind, _)| *kind == component)
+            .map(|(_, path)| path.clone())
+    }
+
+    fn classify(&mut self, message: &str) -> Event {
+        if let Some([version]) = scan(message, "Vulkan Loader Version {}").as_deref() {
+            return Event::LoaderVersion(version.to_string());
+        }
+
+        // The versions are printed with cJSON, quotes included.
+        let found = scan(message, "Found ICD manifest file {}, version {}")
+            .map(|captures| (Component::Driver, captures))
+            .or_else(|| {
+                scan(message, "Found manifest file {} (file version {})")
+                    .map(|captures| (Component::Layer, captures))
+            });
+        if let Some((component, captures)) = found {
+            self.manifest = Some((component, captures[0].to_string()));
+            return Event::ManifestFound {
+                component,
+                path: captures[0].to_string(),
+                file_format_version: captures[1].trim_matches('"').to_string(),
+            };
+        }
+
+        for (format, is_device) in [
+            ("Insert instance layer \"{}\" ({})", false),
+            ("Inserted device layer \"{}\" ({})", true),
+        ] {
+            if let Some(captures) = scan(message, format) {
+                return Event::LayerInserted {
+                    name: captures[0].to_string(),
+                    library: captures[1].to_string(),
+                    is_device,
+                };
+            }
+        }
+
+        if let Some([library]) = scan(message, "Loading layer library {}").as_deref() {
+            return Event::LayerLibraryLoaded(library.to_string());
+        }
+
+        for (format, reason) in SKIPPED_DRIVERS {
+            if let Some(captures) = scan(message, format) {
+                let path = captures[0];
+                // A library that fails to open is preceded by the dynamic linker's own explanation.
+                let reason = match self.records.last() {
+                    Some(previous)
+                        if *reason == "the library failed to load"
+                            && previous.event == Event::Other
+                            && previous.message.starts_with(path) =>
+                    {
+                        format!("{}: {}", reason, previous.message)
+                    }
+                    _ => reason.to_string(),
+                };
+                return Event::DriverSkipped {
+                    path: Some(path.to_string()),
+                    reason,
+                };
+            }
+        }
+
+        for (format, reason) in SKIPPED_LAYERS {
+            if let Some(captures) = scan(message, format) {
+                return Event::LayerSkipped {
+                    name: Some(captures[0].to_string()),
+                    manifest: None,
+                    reason: reason.to_string(),
+                };
+            }
+        }
+
+        for (format, reason) in SKIPPED_LAYER_MANIFESTS {
+            if let Some(captures) = scan(message, format) {
+                return Event::LayerSkipped {
+                    name: None,
+                    manifest: Some(captures[0].to_string()),
+                    reason: reason.to_string(),
+                };
+            }
+        }
+
+        if skips_driver(message) {
+            return Event::DriverSkipped {
+                path: self.current_manifest(Component::Driver),
+                reason: strip_function_name(message).to_string(),
+            };
+        }
+
+        if skips_layer(message) {
+            return Event::LayerSkipped {
+                name: None,
+                manifest: self.current_manifest(Component::Layer),
+                reason: strip_function_name(message).to_string(),
+            };
+        }
+
+        Event::Other
+    }
+}
+
+/// Starts a block for the first line of the messages the loader spreads over several lines.
+fn start_block(message: &str) -> Option<Block> {
+    let search = |component| Block::Search {
+        component,
+        locations: Vec::new(),
+        files: Vec::new(),
+        in_files: false,
+    };
+    let chain = |is_device| Block::Chain {
+        is_device,
+        layers: Vec::new(),
+    };
+
+    match message {
+        "Searching for driver manifest files" => Some(search(Component::Driver)),
+        "Searching for layer manifest files" => Some(search(Component::Layer)),
+        "vkCreateInstance layer callstack setup to:" => Some(chain(false)),
+        "vkCreateDevice layer callstack setup to:" => Some(chain(true)),
+        _ => None,
+    }
+}
+
+/// Parses a whole log.
+pub fn parse(log: &str) -> Vec<Record> {
+    let mut parser = Parser::new();
+    for line in log.lines() {
+        parser.push_line(line);
+    }
+    parser.finish()
+}
+
+fn push_unique(list: &mut Vec<String>, item: &str) {
+    if !list.iter().any(|existing| existing == item) {
+        list.push(item.to_string());
+    }
+}
+
+/// Condenses a parsed log into what usually matters: the manifests the loader searched for and found, the drivers and
+/// layers it skipped and why, the layer chains it built, and every error and warning.
+pub fn summary(records: &[Record]) -> Value {
+    let mut loader_version = None;
+    let mut locations = [Vec::new(), Vec::new()];
+    let mut manifests = [Vec::new(), Vec::new()];
+    let mut skipped_drivers = Vec::new();
+    let mut skipped_layers = Vec::new();
+    let mut inserted_layers = Vec::new();
+    let mut chains = Vec::new();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    for record in records {
+        match &record.event {
+            Event::LoaderVersion(version) => loader_version = Some(version.clone()),
+            Event::ManifestSearch {
+                component,
+                locations: searched,
+                ..
+            } => {
+                for location in searched {
+                    push_unique(&mut locations[*component as usize], location);
+                }
+            }
+            Event::ManifestFound {
+                component, path, ..
+            } => push_unique(&mut manifests[*component as usize], path),
+            Event::DriverSkipped { path, reason } => skipped_drivers.push(json!({
+                "line": record.line,
+                "path": path,
+                "reason": reason,
+            })),
+            Event::LayerSkipped {
+                name,
+                manifest,
+                reason,
+            } => skipped_layers.push(json!({
+                "line": record.line,
+                "name": name,
+                "manifest": manifest,
+                "reason": reason,
+            })),
+            Event::LayerInserted {
+                name,
+                is_device: false,
+                ..
+            } => push_unique(&mut inserted_layers, name),
+            Event::CallChain { is_device, layers } => chains.push(json!({
+                "line": record.line,
+                "device": is_device,
+                "layers": layers.iter().map(ChainLayer::to_json).collect::<Vec<_>>(),
+            })),
+            _ => {}
+        }
+
+        let entry = || json!({ "line": record.line, "message": record.message });
+        match record.level() {
+            log::Level::Error => errors.push(entry()),
+            log::Level::Warn => warnings.push(entry()),
+            _ => {}
+        }
+    }
+
+    let [driver_locations, layer_locations] = locations;
+    let [driver_manifests, layer_manifests] = manifests;
+    json!({
+        "loader_version": loader_version,
+        "drivers": {
+            "search_locations": driver_locations,
+            "manifests": driver_manifests,
+            "skipped": skipped_drivers,
+        },
+        "layers": {
+            "search_locations": layer_locations,
+            "manifests": layer_manifests,
+            "skipped": skipped_layers,
+            "inserted": inserted_layers,
+        },
+        "call_chains": chains,
+        "errors": errors,
+        "warnings": warnings,
+    })
+}
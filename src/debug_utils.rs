@@ -0,0 +1,196 @@
+//! `VK_EXT_debug_utils` messengers backed by Rust closures.
+//!
+//! The closure receives a [`DebugUtilsMessage`], an owned copy of the `VkDebugUtilsMessengerCallbackDataEXT` the
+//! callback was invoked with, so it is free to keep any part of it around. Panics raised by the closure are caught
+//! before they reach the caller of the Vulkan function that triggered the message.
+
+use std::{
+    ffi::{c_void, CStr},
+    os::raw::c_char,
+    ptr::{null, null_mut},
+    slice,
+};
+
+use crate::*;
+
+/// The closure type invoked for every message a [`DebugUtilsMessenger`] receives. Vulkan may call it from any thread
+/// that uses the instance, at the same time.
+pub type DebugUtilsCallback = dyn Fn(&DebugUtilsMessage) + Send + Sync + 'static;
+
+/// An owned copy of a `VkDebugUtilsLabelEXT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugUtilsLabel {
+    pub name: String,
+    pub color: [f32; 4],
+}
+
+/// An owned copy of a `VkDebugUtilsObjectNameInfoEXT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugUtilsObject {
+    pub object_type: VkObjectType,
+    pub handle: u64,
+    pub name: Option<String>,
+}
+
+/// An owned copy of the data passed to a debug-utils messenger callback.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugUtilsMessage {
+    pub severity: VkDebugUtilsMessageSeverityFlagBitsEXT,
+    pub types: VkDebugUtilsMessageTypeFlagsEXT,
+    pub message_id_name: Option<String>,
+    pub message_id_number: i32,
+    pub message: String,
+    pub queue_labels: Vec<DebugUtilsLabel>,
+    pub cmd_buf_labels: Vec<DebugUtilsLabel>,
+    pub objects: Vec<DebugUtilsObject>,
+}
+
+unsafe fn optional_string(string: *const c_char) -> Option<String> {
+    if string.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(string).to_string_lossy().into_owned())
+    }
+}
+
+unsafe fn array<'a, T>(pointer: *const T, count: u32) -> &'a [T] {
+    if pointer.is_null() || count == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(pointer, count as usize)
+    }
+}
+
+unsafe fn labels(pointer: *const VkDebugUtilsLabelEXT, count: u32) -> Vec<DebugUtilsLabel> {
+    array(pointer, count)
+        .iter()
+        .map(|label| DebugUtilsLabel {
+            name: optional_string(label.pLabelName).unwrap_or_default(),
+            color: label.color,
+        })
+        .collect()
+}
+
+impl DebugUtilsMessage {
+    /// Copies everything out of the callback data.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to a valid `VkDebugUtilsMessengerCallbackDataEXT`.
+    pub unsafe fn from_raw(
+        severity: VkDebugUtilsMessageSeverityFlagBitsEXT,
+        types: VkDebugUtilsMessageTypeFlagsEXT,
+        data: &VkDebugUtilsMessengerCallbackDataEXT,
+    ) -> DebugUtilsMessage {
+        DebugUtilsMessage {
+            severity,
+            types,
+            message_id_name: optional_string(data.pMessageIdName),
+            message_id_number: data.messageIdNumber,
+            message: optional_string(data.pMessage).unwrap_or_default(),
+            queue_labels: labels(data.pQueueLabels, data.queueLabelCount),
+            cmd_buf_labels: labels(data.pCmdBufLabels, data.cmdBufLabelCount),
+            objects: array(data.pObjects, data.objectCount)
+                .iter()
+                .map(|object| DebugUtilsObject {
+                    object_type: object.objectType,
+                    handle: object.objectHandle,
+                    name: optional_string(object.pObjectName),
+                })
+                .collect(),
+        }
+    }
+}
+
+unsafe extern "system" fn trampoline(
+    message_severity: VkDebugUtilsMessageSeverityFlagBitsEXT,
+    message_types: VkDebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const VkDebugUtilsMessengerCallbackDataEXT,
+    user_data: *mut c_void,
+) -> VkBool32 {
+    if callback_data.is_null() || user_data.is_null() {
+        return VK_FALSE;
+    }
+
+    let callback = &*(user_data as *const Box<DebugUtilsCallback>);
+    let message = DebugUtilsMessage::from_raw(message_severity, message_types, &*callback_data);
+
+    // Unwinding through the layers and the loader is undefined behaviour, so the panic ends here.
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| callback(&message)));
+
+    VK_FALSE
+}
+
+/// A `VkDebugUtilsMessengerEXT` that forwards its messages to a Rust closure and destroys itself when dropped.
+///
+/// Messengers created through [`Instance::create_debug_utils_messenger`] are owned by the instance and are destroyed
+/// right before it.
+pub struct DebugUtilsMessenger {
+    instance: VkInstance,
+    handle: VkDebugUtilsMessengerEXT,
+    destroy: PFN_vkDestroyDebugUtilsMessengerEXT,
+    // Double boxed so that the pointer handed to Vulkan is thin and stays put.
+    _callback: Box<Box<DebugUtilsCallback>>,
+}
+
+impl DebugUtilsMessenger {
+    /// Creates a messenger on `instance` that reports messages matching `severities` and `types` to `callback`.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created with `VK_EXT_debug_utils` enabled, and the messenger must be dropped before
+    /// the instance is destroyed.
+    pub unsafe fn new(
+        instance: VkInstance,
+        severities: VkDebugUtilsMessageSeverityFlagsEXT,
+        types: VkDebugUtilsMessageTypeFlagsEXT,
+        callback: impl Fn(&DebugUtilsMessage) + Send + Sync + 'static,
+    ) -> Result<DebugUtilsMessenger, VkResult> {
+        let create: PFN_vkCreateDebugUtilsMessengerEXT = std::mem::transmute(
+            vkGetInstanceProcAddr(instance, c"vkCreateDebugUtilsMessengerEXT".as_ptr()),
+        );
+        let destroy: PFN_vkDestroyDebugUtilsMessengerEXT = std::mem::transmute(
+            vkGetInstanceProcAddr(instance, c"vkDestroyDebugUtilsMessengerEXT".as_ptr()),
+        );
+        let create = match (create, destroy) {
+            (Some(create), Some(_)) => create,
+            _ => return Err(VK_ERROR_EXTENSION_NOT_PRESENT),
+        };
+
+        let callback: Box<Box<DebugUtilsCallback>> = Box::new(Box::new(callback));
+        let create_info = VkDebugUtilsMessengerCreateInfoEXT {
+            sType: VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
+            pNext: null(),
+            flags: 0,
+            messageSeverity: severities,
+            messageType: types,
+            pfnUserCallback: Some(trampoline),
+            pUserData: &*callback as *const Box<DebugUtilsCallback> as *mut c_void,
+        };
+
+        let mut handle = null_mut();
+        let result = create(instance, &create_info, null(), &mut handle);
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+
+        Ok(DebugUtilsMessenger {
+            instance,
+            handle,
+            destroy,
+            _callback: callback,
+        })
+    }
+
+    pub fn handle(&self) -> VkDebugUtilsMessengerEXT {
+        self.handle
+    }
+}
+
+impl Drop for DebugUtilsMessenger {
+    fn drop(&mut self) {
+        if let Some(destroy) = self.destroy {
+            unsafe { destroy(self.instance, self.handle, null()) };
+        }
+    }
+}
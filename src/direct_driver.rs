@@ -0,0 +1,184 @@
+//! `VK_LUNARG_direct_driver_loading`: handing drivers to the loader without going through manifest files.
+//!
+//! A [`DirectDriverList`] collects drivers, either as a `vk_icdGetInstanceProcAddr` function that already lives in the
+//! process or as a shared library that gets opened here, and chains the matching `VkDirectDriverLoadingListLUNARG` into
+//! instance creation. In [`DirectDriverLoadingMode::Exclusive`] mode the loader ignores every driver it would otherwise
+//! have found, which makes it possible to run against a mock driver without touching the file system or the
+//! environment.
+
+use std::{
+    ffi::{c_void, CStr, OsStr},
+    os::raw::c_char,
+    ptr::null_mut,
+    sync::Arc,
+};
+
+use libloading::Library;
+
+use crate::*;
+
+/// Whether the loader should use only the drivers in the list or also the ones it discovers on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DirectDriverLoadingMode {
+    /// Only the drivers in the list are used.
+    #[default]
+    Exclusive,
+    /// The drivers in the list are used in addition to the ones found through manifests.
+    Inclusive,
+}
+
+impl DirectDriverLoadingMode {
+    pub fn as_raw(self) -> VkDirectDriverLoadingModeLUNARG {
+        match self {
+            DirectDriverLoadingMode::Exclusive => VK_DIRECT_DRIVER_LOADING_MODE_EXCLUSIVE_LUNARG,
+            DirectDriverLoadingMode::Inclusive => VK_DIRECT_DRIVER_LOADING_MODE_INCLUSIVE_LUNARG,
+        }
+    }
+}
+
+/// A single driver, identified by its `vk_icdGetInstanceProcAddr`.
+#[derive(Clone)]
+pub struct DirectDriver {
+    get_instance_proc_addr: PFN_vkGetInstanceProcAddrLUNARG,
+    // Keeps the library the function came from loaded.
+    library: Option<Arc<Library>>,
+}
+
+impl DirectDriver {
+    /// Uses a driver that is already part of the process, for example one that is linked into a test binary.
+    ///
+    /// # Safety
+    ///
+    /// `get_instance_proc_addr` must behave like the `vk_icdGetInstanceProcAddr` of a driver supporting loader driver
+    /// interface version 7 or newer, and stay valid for as long as any instance using it exists.
+    pub unsafe fn from_fn(get_instance_proc_addr: PFN_vkGetInstanceProcAddrLUNARG) -> DirectDriver {
+        DirectDriver {
+            get_instance_proc_addr,
+            library: None,
+        }
+    }
+
+    /// Opens the driver library at `path` and looks up its `vk_icdGetInstanceProcAddr`.
+    ///
+    /// # Safety
+    ///
+    /// Opening a library runs its initialisation code, and the library has to actually be a Vulkan driver.
+    pub unsafe fn open(path: impl AsRef<OsStr>) -> Result<DirectDriver, libloading::Error> {
+        let library = Library::new(path)?;
+        let get_instance_proc_addr =
+            *library
+                .get::<unsafe extern "system" fn(VkInstance, *const c_char) -> PFN_vkVoidFunction>(
+                    b"vk_icdGetInstanceProcAddr\0",
+                )?;
+
+        Ok(DirectDriver {
+            get_instance_proc_addr: Some(get_instance_proc_addr),
+            library: Some(Arc::new(library)),
+        })
+    }
+
+    pub fn get_instance_proc_addr(&self) -> PFN_vkGetInstanceProcAddrLUNARG {
+        self.get_instance_proc_addr
+    }
+}
+
+/// A set of drivers to hand to the loader through `VkDirectDriverLoadingListLUNARG`.
+#[derive(Clone, Default)]
+pub struct DirectDriverList {
+    pub mode: DirectDriverLoadingMode,
+    pub drivers: Vec<DirectDriver>,
+}
+
+impl DirectDriverList {
+    pub fn new(mode: DirectDriverLoadingMode) -> DirectDriverList {
+        DirectDriverList {
+            mode,
+            drivers: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, driver: DirectDriver) -> &mut DirectDriverList {
+        self.drivers.push(driver);
+        self
+    }
+
+    /// Calls `f` with a copy of `create_info` that has the driver list chained in front of its `pNext` chain and
+    /// `VK_LUNARG_direct_driver_loading` added to its enabled extensions.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer in `create_info` must be valid, and `f` must not let the create info it receives escape.
+    pub unsafe fn chain<R>(
+        &self,
+        create_info: &VkInstanceCreateInfo,
+        f: impl FnOnce(&VkInstanceCreateInfo) -> R,
+    ) -> R {
+        let drivers = self
+            .drivers
+            .iter()
+            .map(|driver| VkDirectDriverLoadingInfoLUNARG {
+                sType: VK_STRUCTURE_TYPE_DIRECT_DRIVER_LOADING_INFO_LUNARG,
+                pNext: null_mut(),
+                flags: 0,
+                pfnGetInstanceProcAddr: driver.get_instance_proc_addr,
+            })
+            .collect::<Vec<_>>();
+
+        let list = VkDirectDriverLoadingListLUNARG {
+            sType: VK_STRUCTURE_TYPE_DIRECT_DRIVER_LOADING_LIST_LUNARG,
+            pNext: create_info.pNext as *mut c_void,
+            mode: self.mode.as_raw(),
+            driverCount: drivers.len().try_into().unwrap(),
+            pDrivers: drivers.as_ptr(),
+        };
+
+        let extension_name =
+            VK_LUNARG_DIRECT_DRIVER_LOADING_EXTENSION_NAME.as_ptr() as *const c_char;
+        let mut extensions = if create_info.enabledExtensionCount == 0 {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(
+                create_info.ppEnabledExtensionNames,
+                create_info.enabledExtensionCount as usize,
+            )
+            .to_vec()
+        };
+        let already_enabled = extensions.iter().any(|name| {
+            CStr::from_ptr(*name).to_bytes_with_nul()
+                == VK_LUNARG_DIRECT_DRIVER_LOADING_EXTENSION_NAME
+        });
+        if !already_enabled {
+            extensions.push(extension_name);
+        }
+
+        let create_info = VkInstanceCreateInfo {
+            pNext: &list as *const VkDirectDriverLoadingListLUNARG as *const c_void,
+            enabledExtensionCount: extensions.len().try_into().unwrap(),
+            ppEnabledExtensionNames: extensions.as_ptr(),
+            ..*create_info
+        };
+
+        f(&create_info)
+    }
+
+    /// Creates an instance that uses the drivers in the list. Libraries opened with [`DirectDriver::open`] stay loaded
+    /// for as long as the instance exists.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer in `create_info`, including its `pNext` chain, must be valid for the duration of the call.
+    pub unsafe fn create_instance(
+        &self,
+        create_info: &VkInstanceCreateInfo,
+    ) -> Result<Instance, VkResult> {
+        let mut instance = self.chain(create_info, |create_info| Instance::new(create_info))?;
+        for library in self
+            .drivers
+            .iter()
+            .filter_map(|driver| driver.library.clone())
+        {
+            instance.keep_alive(library);
+        }
+        Ok(instance)
+    }
+}
@@ -0,0 +1,787 @@
+//! Where the loader looks for manifests, and which of the drivers and layers it finds end up being used.
+//!
+//! This mirrors the decisions `loader.c`, `loader_environment.c` and `settings.c` make for the current environment:
+//! the search path assembled in `read_data_files_in_search_paths`, the `VK_LOADER_DRIVERS_*` and `VK_LOADER_LAYERS_*`
+//! filters, `enable_environment`/`disable_environment` of implicit layers, the override layer and the loader settings
+//! file. Nothing here calls into the loader itself, so the answers describe what a freshly started process with this
+//! environment would see.
+//!
+//! Available with the `tools` feature. Only the search path of Linux and the BSDs is modelled.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::manifest::{Diagnostic, Layer, LayerManifest, Severity};
+
+pub use crate::search_paths::{FALLBACK_CONFIG_DIRS, FALLBACK_DATA_DIRS};
+
+/// The system configuration directories compiled into the loader, see
+/// [`SYSCONF_DIRS`](crate::search_paths::SYSCONF_DIRS).
+pub fn sysconf_dirs() -> Vec<&'static str> {
+    crate::search_paths::SYSCONF_DIRS.to_vec()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ManifestKind {
+    Driver,
+    ImplicitLayer,
+    ExplicitLayer,
+}
+
+impl ManifestKind {
+    /// The folder appended to every system search directory.
+    pub fn relative_dir(self) -> &'static str {
+        match self {
+            ManifestKind::Driver => "vulkan/icd.d",
+            ManifestKind::ImplicitLayer => "vulkan/implicit_layer.d",
+            ManifestKind::ExplicitLayer => "vulkan/explicit_layer.d",
+        }
+    }
+
+    /// The environment variable replacing the whole search path, if there is one.
+    pub fn override_vars(self) -> &'static [&'static str] {
+        match self {
+            ManifestKind::Driver => &["VK_DRIVER_FILES", "VK_ICD_FILENAMES"],
+            ManifestKind::ImplicitLayer => &[],
+            ManifestKind::ExplicitLayer => &["VK_LAYER_PATH"],
+        }
+    }
+
+    /// The environment variable adding to the front of the search path, if there is one.
+    pub fn additional_var(self) -> Option<&'static str> {
+        match self {
+            ManifestKind::Driver => Some("VK_ADD_DRIVER_FILES"),
+            ManifestKind::ImplicitLayer => None,
+            ManifestKind::ExplicitLayer => Some("VK_ADD_LAYER_PATH"),
+        }
+    }
+}
+
+/// A single entry of the search path, either a manifest file or a folder of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPath {
+    pub path: PathBuf,
+    /// What put the entry there: an environment variable, `SYSCONFDIR`, the override layer and so on.
+    pub source: String,
+}
+
+/// The search path for one kind of manifest.
+#[derive(Debug, Clone, Default)]
+pub struct SearchPaths {
+    /// Set when the default locations were replaced, naming what replaced them.
+    pub overridden_by: Option<String>,
+    pub paths: Vec<SearchPath>,
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn push_list(paths: &mut Vec<SearchPath>, list: &str, relative_dir: Option<&str>, source: &str) {
+    for entry in list.split(':').filter(|entry| !entry.is_empty()) {
+        let path = match relative_dir {
+            Some(relative_dir) => Path::new(entry).join(relative_dir),
+            None => entry.into(),
+        };
+        paths.push(SearchPath {
+            path,
+            source: source.into(),
+        });
+    }
+}
+
+impl SearchPaths {
+    /// Builds the search path like `read_data_files_in_search_paths`. `path_override` stands for the `override_paths`
+    /// of an active override layer, which replace the explicit layer search path.
+    pub fn new(kind: ManifestKind, path_override: Option<&[String]>) -> SearchPaths {
+        let mut search_paths = SearchPaths::default();
+        let paths = &mut search_paths.paths;
+
+        let override_env = kind
+            .override_vars()
+            .iter()
+            .find_map(|&name| env::var(name).ok().map(|value| (name, value)));
+
+        if let Some(path_override) = path_override.filter(|_| kind == ManifestKind::ExplicitLayer) {
+            search_paths.overridden_by = Some(crate::manifest::OVERRIDE_LAYER_NAME.into());
+            push_list(paths, &path_override.join(":"), None, "override_paths");
+        } else if let Some((name, value)) = override_env {
+            search_paths.overridden_by = Some(name.into());
+            push_list(paths, &value, None, name);
+        } else {
+            if let Some(name) = kind.additional_var() {
+                if let Ok(value) = env::var(name) {
+                    push_list(paths, &value, None, name);
+                }
+            }
+
+            let relative_dir = Some(kind.relative_dir());
+            let home = non_empty_var("HOME");
+            let config_home = non_empty_var("XDG_CONFIG_HOME");
+            let data_home = non_empty_var("XDG_DATA_HOME");
+
+            match (&config_home, &home) {
+                (Some(config_home), _) => {
+                    push_list(paths, config_home, relative_dir, "XDG_CONFIG_HOME")
+                }
+                (None, Some(home)) => {
+                    push_list(paths, &format!("{}/.config", home), relative_dir, "HOME")
+                }
+                (None, None) => {}
+            }
+            let config_dirs = non_empty_var("XDG_CONFIG_DIRS");
+            push_list(
+                paths,
+                config_dirs.as_deref().unwrap_or(FALLBACK_CONFIG_DIRS),
+                relative_dir,
+                if config_dirs.is_some() {
+                    "XDG_CONFIG_DIRS"
+                } else {
+                    "FALLBACK_CONFIG_DIRS"
+                },
+            );
+            for sysconfdir in sysconf_dirs() {
+                push_list(paths, sysconfdir, relative_dir, "SYSCONFDIR");
+            }
+            match (&data_home, &home) {
+                (Some(data_home), _) => push_list(paths, data_home, relative_dir, "XDG_DATA_HOME"),
+                (None, Some(home)) => push_list(
+                    paths,
+                    &format!("{}/.local/share", home),
+                    relative_dir,
+                    "HOME",
+                ),
+                (None, None) => {}
+            }
+            let data_dirs = non_empty_var("XDG_DATA_DIRS");
+            push_list(
+                paths,
+                data_dirs.as_deref().unwrap_or(FALLBACK_DATA_DIRS),
+                relative_dir,
+                if data_dirs.is_some() {
+                    "XDG_DATA_DIRS"
+                } else {
+                    "FALLBACK_DATA_DIRS"
+                },
+            );
+        }
+
+        // The loader drops repeated entries, keeping the first one.
+        let mut seen = Vec::new();
+        paths.retain(|search_path| {
+            let new = !seen.contains(&search_path.path);
+            seen.push(search_path.path.clone());
+            new
+        });
+
+        search_paths
+    }
+
+    /// Every manifest file on the search path, in the order the loader reads them. Files are taken as they are when
+    /// they end in `.json`, anything else is read as a folder.
+    pub fn manifests(&self) -> Vec<PathBuf> {
+        let is_json = |path: &Path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        };
+
+        let mut manifests = Vec::new();
+        for search_path in &self.paths {
+            if is_json(&search_path.path) {
+                if search_path.path.is_file() {
+                    manifests.push(search_path.path.clone());
+                }
+                continue;
+            }
+
+            let Ok(entries) = fs::read_dir(&search_path.path) else {
+                continue;
+            };
+            // The loader goes by readdir order, which is not stable. Sorting at least keeps the report reproducible.
+            let mut files = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| is_json(path) && path.is_file())
+                .collect::<Vec<_>>();
+            files.sort();
+            manifests.extend(files);
+        }
+        manifests
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pattern {
+    /// `~all~`, `~implicit~`, `~explicit~` and other `~` names. Only `~all~` matches anything.
+    Special(String),
+    Substring(String),
+    Suffix(String),
+    Prefix(String),
+    FullName(String),
+}
+
+/// A comma separated filter list as used by `VK_LOADER_DRIVERS_SELECT` and friends, matched case insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    patterns: Vec<Pattern>,
+}
+
+impl Filter {
+    /// Parses a filter list with the rules of `determine_filter_type`: `*text*` matches a substring, `*text` a suffix,
+    /// `text*` a prefix and `text` the full name. `~all~` and `*` match everything.
+    pub fn parse(value: &str) -> Filter {
+        let value = value.to_ascii_lowercase();
+        let patterns = value
+            .split(',')
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                if token.starts_with('~') {
+                    return Pattern::Special(token.into());
+                }
+                // The loader meant `*` and `**` to match everything, but only `*` ends up doing so: it becomes an empty
+                // prefix, while `**` becomes the suffix `*`.
+                let star_begin = token.len() > 1 && token.starts_with('*');
+                let star_end = token.len() != 2 && token.ends_with('*');
+                match (star_begin, star_end) {
+                    (true, true) => Pattern::Substring(token[1..token.len() - 1].into()),
+                    (true, false) => Pattern::Suffix(token[1..].into()),
+                    (false, true) => Pattern::Prefix(token[..token.len() - 1].into()),
+                    (false, false) => Pattern::FullName(token.into()),
+                }
+            })
+            .collect();
+
+        Filter { patterns }
+    }
+
+    /// Reads the filter from an environment variable. Unset and empty variables give an empty filter.
+    pub fn from_env(name: &str) -> Filter {
+        env::var(name)
+            .map(|value| Filter::parse(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    fn has_special(&self, special: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| matches!(pattern, Pattern::Special(name) if name == special))
+    }
+
+    fn matches_all(&self) -> bool {
+        self.has_special("~all~")
+    }
+
+    /// Whether `name` matches any of the filter's patterns, as `check_name_matches_filter_environment_var` decides it.
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.patterns.iter().any(|pattern| match pattern {
+            Pattern::Special(special) => special == "~all~",
+            Pattern::Substring(text) => name.contains(text.as_str()),
+            Pattern::Suffix(text) => name.ends_with(text.as_str()),
+            Pattern::Prefix(text) => name.starts_with(text.as_str()),
+            Pattern::FullName(text) => name == *text,
+        })
+    }
+}
+
+pub const DRIVERS_SELECT_ENV_VAR: &str = "VK_LOADER_DRIVERS_SELECT";
+pub const DRIVERS_DISABLE_ENV_VAR: &str = "VK_LOADER_DRIVERS_DISABLE";
+pub const LAYERS_ENABLE_ENV_VAR: &str = "VK_LOADER_LAYERS_ENABLE";
+pub const LAYERS_DISABLE_ENV_VAR: &str = "VK_LOADER_LAYERS_DISABLE";
+pub const LAYERS_ALLOW_ENV_VAR: &str = "VK_LOADER_LAYERS_ALLOW";
+pub const INSTANCE_LAYERS_ENV_VAR: &str = "VK_INSTANCE_LAYERS";
+
+/// Every environment variable that changes which drivers and layers the loader finds or uses.
+pub const ENVIRONMENT_VARIABLES: &[&str] = &[
+    "VK_DRIVER_FILES",
+    "VK_ICD_FILENAMES",
+    "VK_ADD_DRIVER_FILES",
+    "VK_LAYER_PATH",
+    "VK_ADD_LAYER_PATH",
+    INSTANCE_LAYERS_ENV_VAR,
+    LAYERS_ENABLE_ENV_VAR,
+    LAYERS_DISABLE_ENV_VAR,
+    LAYERS_ALLOW_ENV_VAR,
+    DRIVERS_SELECT_ENV_VAR,
+    DRIVERS_DISABLE_ENV_VAR,
+    "VK_LOADER_DEBUG",
+    "HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_CONFIG_DIRS",
+    "XDG_DATA_HOME",
+    "XDG_DATA_DIRS",
+];
+
+/// The driver filters, which are matched against the manifest's file name.
+#[derive(Debug, Clone, Default)]
+pub struct DriverFilters {
+    pub select: Filter,
+    pub disable: Filter,
+}
+
+impl DriverFilters {
+    pub fn from_env() -> DriverFilters {
+        DriverFilters {
+            select: Filter::from_env(DRIVERS_SELECT_ENV_VAR),
+            disable: Filter::from_env(DRIVERS_DISABLE_ENV_VAR),
+        }
+    }
+
+    /// Why the driver with the given manifest is filtered out, if it is.
+    pub fn rejection(&self, manifest_path: &Path) -> Option<String> {
+        let file_name = manifest_path.file_name()?.to_string_lossy();
+        let selected = self.select.matches(&file_name);
+
+        if self.disable.matches(&file_name) && !selected {
+            Some(format!("disabled by {}", DRIVERS_DISABLE_ENV_VAR))
+        } else if !self.select.is_empty() && !selected {
+            Some(format!("not selected by {}", DRIVERS_SELECT_ENV_VAR))
+        } else {
+            None
+        }
+    }
+}
+
+/// The layer filters, which are matched against layer names.
+#[derive(Debug, Clone, Default)]
+pub struct LayerFilters {
+    pub enable: Filter,
+    pub disable: Filter,
+    pub allow: Filter,
+}
+
+impl LayerFilters {
+    pub fn from_env() -> LayerFilters {
+        LayerFilters {
+            enable: Filter::from_env(LAYERS_ENABLE_ENV_VAR),
+            disable: Filter::from_env(LAYERS_DISABLE_ENV_VAR),
+            allow: Filter::from_env(LAYERS_ALLOW_ENV_VAR),
+        }
+    }
+
+    /// Whether `VK_LOADER_LAYERS_DISABLE` turns the layer off, taking `VK_LOADER_LAYERS_ALLOW` into account.
+    pub fn is_disabled(&self, name: &str, is_implicit: bool) -> bool {
+        let disabled_by_type = if is_implicit {
+            self.disable.has_special("~implicit~")
+        } else {
+            self.disable.has_special("~explicit~")
+        };
+        (self.disable.matches_all() || disabled_by_type || self.disable.matches(name))
+            && !self.allow.matches(name)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enable.matches(name)
+    }
+}
+
+/// The layers named in `VK_INSTANCE_LAYERS`.
+pub fn instance_layers_from_env() -> Vec<String> {
+    env::var(INSTANCE_LAYERS_ENV_VAR)
+        .map(|value| {
+            value
+                .split(':')
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether a layer would be active, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub enabled: bool,
+    pub reason: String,
+}
+
+impl Decision {
+    fn enabled(reason: impl Into<String>) -> Decision {
+        Decision {
+            enabled: true,
+            reason: reason.into(),
+        }
+    }
+
+    fn disabled(reason: impl Into<String>) -> Decision {
+        Decision {
+            enabled: false,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Decides whether an implicit layer is active, following `loader_implicit_layer_is_enabled`. `override_layer` is the
+/// active override layer, whose components are always enabled.
+pub fn implicit_layer_decision(
+    layer: &Layer,
+    filters: &LayerFilters,
+    override_layer: Option<&Layer>,
+) -> Decision {
+    let forced_disabled = filters.is_disabled(&layer.name, true);
+    let forced_enabled = filters.is_enabled(&layer.name);
+
+    let mut decision = match &layer.enable_environment {
+        None => Decision::enabled("implicit layers are on by default"),
+        Some(variable) => match env::var(&variable.name) {
+            Ok(value) if value == variable.value => Decision::enabled(format!(
+                "enable_environment {}={} is set",
+                variable.name, variable.value
+            )),
+            _ => Decision::disabled(format!(
+                "enable_environment {}={} is not set",
+                variable.name, variable.value
+            )),
+        },
+    };
+
+    if forced_enabled {
+        if !decision.enabled {
+            decision = Decision::enabled(format!("forced on by {}", LAYERS_ENABLE_ENV_VAR));
+        }
+    } else if decision.enabled && forced_disabled {
+        return Decision::disabled(format!("forced off by {}", LAYERS_DISABLE_ENV_VAR));
+    }
+
+    if let Some(variable) = &layer.disable_environment {
+        if env::var_os(&variable.name).is_some() {
+            decision = Decision::disabled(format!("disable_environment {} is set", variable.name));
+        }
+    }
+
+    if override_layer
+        .is_some_and(|override_layer| override_layer.component_layers.contains(&layer.name))
+    {
+        decision = Decision::enabled(format!(
+            "component of {}",
+            crate::manifest::OVERRIDE_LAYER_NAME
+        ));
+    }
+
+    decision
+}
+
+/// Decides whether an explicit layer is active without the application asking for it, following
+/// `loader_add_environment_layers`.
+pub fn explicit_layer_decision(
+    layer: &Layer,
+    filters: &LayerFilters,
+    instance_layers: &[String],
+    override_layer: Option<&Layer>,
+) -> Decision {
+    if instance_layers.contains(&layer.name) {
+        Decision::enabled(format!("listed in {}", INSTANCE_LAYERS_ENV_VAR))
+    } else if filters.is_enabled(&layer.name) {
+        Decision::enabled(format!("forced on by {}", LAYERS_ENABLE_ENV_VAR))
+    } else if override_layer
+        .is_some_and(|override_layer| override_layer.component_layers.contains(&layer.name))
+    {
+        Decision::enabled(format!(
+            "component of {}",
+            crate::manifest::OVERRIDE_LAYER_NAME
+        ))
+    } else if filters.is_disabled(&layer.name, false) {
+        Decision::disabled(format!(
+            "disabled by {}, even when the application enables it",
+            LAYERS_DISABLE_ENV_VAR
+        ))
+    } else {
+        Decision::disabled("only if the application enables it")
+    }
+}
+
+/// The `control` of a layer configuration in the loader settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayerControl {
+    /// The layer follows the usual rules.
+    #[default]
+    Auto,
+    On,
+    Off,
+    /// Marks where layers not listed in the settings file go. Without such an entry they are not used at all.
+    UnorderedLayerLocation,
+}
+
+impl LayerControl {
+    fn parse(control: &str) -> LayerControl {
+        match control {
+            "on" => LayerControl::On,
+            "off" => LayerControl::Off,
+            "unordered_layer_location" => LayerControl::UnorderedLayerLocation,
+            _ => LayerControl::Auto,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LayerControl::Auto => "auto",
+            LayerControl::On => "on",
+            LayerControl::Off => "off",
+            LayerControl::UnorderedLayerLocation => "unordered_layer_location",
+        }
+    }
+}
+
+/// An entry of the `layers` array of the loader settings file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerConfiguration {
+    pub control: LayerControl,
+    pub name: Option<String>,
+    /// The layer's manifest.
+    pub path: Option<PathBuf>,
+    pub treat_as_implicit_manifest: bool,
+}
+
+pub const LOADER_SETTINGS_FILENAME: &str = "vk_loader_settings.json";
+
+/// The loader settings file, normally written by Vulkan Configurator.
+#[derive(Debug, Clone)]
+pub struct LoaderSettings {
+    pub path: PathBuf,
+    /// The layer configurations, in the order the loader applies them.
+    pub layers: Vec<LayerConfiguration>,
+    /// The `stderr_log` filters, replacing `VK_LOADER_DEBUG`.
+    pub stderr_log: Option<Vec<String>>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LoaderSettings {
+    /// The settings file the loader would read, following `get_unix_settings_path`.
+    pub fn find() -> Option<PathBuf> {
+        let suffix = Path::new("vulkan/loader_settings.d").join(LOADER_SETTINGS_FILENAME);
+        [
+            env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")),
+            env::var_os("XDG_DATA_HOME").map(PathBuf::from),
+            Some("/etc".into()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|base| base.join(&suffix))
+        .find(|path| path.exists())
+    }
+
+    /// Loads the settings that apply to the application at `executable`, or the global ones when it is `None` or no
+    /// entry lists it in its `app_keys`. Returns `None` when the loader would not use a settings file.
+    pub fn load(executable: Option<&Path>) -> Option<LoaderSettings> {
+        let path = LoaderSettings::find()?;
+        let json = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        LoaderSettings::parse(path, &json, executable)
+    }
+
+    /// Picks the settings that apply to `executable` out of an already parsed settings file, like
+    /// `get_loader_settings`.
+    pub fn parse(
+        path: impl Into<PathBuf>,
+        json: &Value,
+        executable: Option<&Path>,
+    ) -> Option<LoaderSettings> {
+        json.as_object()?.get("file_format_version")?;
+
+        let candidates = match (json.get("settings_array"), json.get("settings")) {
+            (Some(settings_array), _) => settings_array.as_array()?.iter().collect::<Vec<_>>(),
+            (None, Some(settings)) => vec![settings],
+            (None, None) => return None,
+        };
+
+        let app_settings = executable.and_then(|executable| {
+            candidates.iter().find(|settings| {
+                settings
+                    .get("app_keys")
+                    .and_then(Value::as_array)
+                    .is_some_and(|app_keys| {
+                        app_keys
+                            .iter()
+                            .any(|app_key| app_key.as_str().map(Path::new) == Some(executable))
+                    })
+            })
+        });
+        let settings = app_settings.or_else(|| {
+            candidates
+                .iter()
+                .find(|settings| settings.get("app_keys").is_none())
+        })?;
+
+        let mut diagnostics = Vec::new();
+        let stderr_log = settings.get("stderr_log").map(|stderr_log| {
+            stderr_log
+                .as_array()
+                .map(|filters| {
+                    filters
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        });
+
+        // Without a `layers` array, or with a malformed entry in it, the loader ignores the whole file.
+        let layers = settings
+            .get("layers")?
+            .as_array()?
+            .iter()
+            .map(|layer| {
+                let string = |key: &str| layer.get(key).and_then(Value::as_str).map(String::from);
+                let control = LayerControl::parse(&string("control")?);
+                if control == LayerControl::UnorderedLayerLocation {
+                    return Some(LayerConfiguration {
+                        control,
+                        name: None,
+                        path: None,
+                        treat_as_implicit_manifest: false,
+                    });
+                }
+                Some(LayerConfiguration {
+                    control,
+                    name: Some(string("name")?),
+                    path: Some(string("path")?.into()),
+                    treat_as_implicit_manifest: layer.get("treat_as_implicit_manifest")
+                        == Some(&Value::Bool(true)),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if !layers
+            .iter()
+            .any(|layer| layer.control == LayerControl::UnorderedLayerLocation)
+        {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Info,
+                message: "No unordered_layer_location entry, so only the layers listed in the settings file are used."
+                    .into(),
+            });
+        }
+
+        Some(LoaderSettings {
+            path: path.into(),
+            layers,
+            stderr_log,
+            diagnostics,
+        })
+    }
+
+    /// Whether layers that the settings file does not list are still considered.
+    pub fn has_unordered_layer_location(&self) -> bool {
+        self.layers
+            .iter()
+            .any(|layer| layer.control == LayerControl::UnorderedLayerLocation)
+    }
+
+    /// The configuration for a layer, matched by name and manifest like the loader does.
+    pub fn configuration(&self, name: &str, manifest_path: &Path) -> Option<&LayerConfiguration> {
+        self.layers.iter().find(|configuration| {
+            configuration.name.as_deref() == Some(name)
+                && configuration.path.as_deref() == Some(manifest_path)
+        })
+    }
+
+    /// The loader debug level the settings file sets, if it sets one.
+    pub fn debug_level(&self) -> Option<u32> {
+        self.stderr_log
+            .as_ref()
+            .map(|filters| crate::loader_log::parse_debug_level(&filters.join(",")))
+    }
+}
+
+/// A layer manifest that could not be read at all.
+#[derive(Debug, Clone)]
+pub struct BrokenLayerManifest {
+    pub path: PathBuf,
+    pub is_implicit: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Every layer manifest the loader reads, found the way `loader_scan_for_layers` finds them.
+#[derive(Debug, Clone)]
+pub struct LayerDiscovery {
+    pub implicit_search_paths: SearchPaths,
+    pub explicit_search_paths: SearchPaths,
+    /// The layers and the manifests they came from, in the order the loader reads them: implicit layers, explicit
+    /// layers, then the layers only the loader settings file points at.
+    pub layers: Vec<(PathBuf, Layer)>,
+    pub broken_manifests: Vec<BrokenLayerManifest>,
+    /// The override layer, when it is active.
+    pub override_layer: Option<Layer>,
+}
+
+impl LayerDiscovery {
+    pub fn scan(filters: &LayerFilters, settings: Option<&LoaderSettings>) -> LayerDiscovery {
+        let mut discovery = LayerDiscovery {
+            implicit_search_paths: SearchPaths::new(ManifestKind::ImplicitLayer, None),
+            explicit_search_paths: SearchPaths::default(),
+            layers: Vec::new(),
+            broken_manifests: Vec::new(),
+            override_layer: None,
+        };
+        discovery.load(discovery.implicit_search_paths.manifests(), true);
+
+        // An active override layer with override_paths replaces the explicit layer search path.
+        discovery.override_layer = discovery
+            .layers
+            .iter()
+            .map(|(_, layer)| layer)
+            .find(|layer| {
+                layer.is_override_layer()
+                    && layer.is_usable()
+                    && implicit_layer_decision(layer, filters, None).enabled
+            })
+            .cloned();
+        let path_override = discovery
+            .override_layer
+            .as_ref()
+            .map(|layer| layer.override_paths.clone())
+            .filter(|paths| !paths.is_empty());
+
+        discovery.explicit_search_paths =
+            SearchPaths::new(ManifestKind::ExplicitLayer, path_override.as_deref());
+        discovery.load(discovery.explicit_search_paths.manifests(), false);
+
+        // Layers named in the settings file are read from the manifest it points at.
+        if let Some(settings) = settings {
+            let listed = settings
+                .layers
+                .iter()
+                .filter_map(|configuration| {
+                    let path = configuration.path.as_ref()?;
+                    let known = discovery
+                        .layers
+                        .iter()
+                        .any(|(manifest_path, _)| manifest_path == path);
+                    (!known).then(|| (path.clone(), configuration.treat_as_implicit_manifest))
+                })
+                .collect::<Vec<_>>();
+            for (path, is_implicit) in listed {
+                discovery.load(vec![path], is_implicit);
+            }
+        }
+
+        discovery
+    }
+
+    fn load(&mut self, manifests: Vec<PathBuf>, is_implicit: bool) {
+        for path in manifests {
+            let manifest = LayerManifest::load(&path, is_implicit);
+            if !manifest.is_usable() || manifest.layers.is_empty() {
+                self.broken_manifests.push(BrokenLayerManifest {
+                    path,
+                    is_implicit,
+                    diagnostics: manifest.diagnostics,
+                });
+                continue;
+            }
+            self.layers.extend(
+                manifest
+                    .layers
+                    .into_iter()
+                    .map(|layer| (path.clone(), layer)),
+            );
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Surfaces that are not backed by any window, from `VK_EXT_headless_surface`.
+//!
+//! A headless surface lets swapchain code run on machines without a display server: the loader creates the surface
+//! itself and hands it to every driver that implements the extension. Drivers without it still work for everything that
+//! doesn't present. The instance has to be created with [`HEADLESS_SURFACE_EXTENSIONS`] enabled.
+//!
+//! Available with the `headless-surface` feature.
+
+use std::{
+    ffi::CStr,
+    mem::MaybeUninit,
+    ptr::{null, null_mut},
+};
+
+use crate::*;
+
+/// The instance extensions a headless surface needs.
+pub const HEADLESS_SURFACE_EXTENSIONS: [&CStr; 2] = [c"VK_KHR_surface", c"VK_EXT_headless_surface"];
+
+/// A `VkSurfaceKHR` created with `vkCreateHeadlessSurfaceEXT`, destroyed when dropped.
+///
+/// Surfaces created through [`Instance::create_headless_surface`] are owned by the instance and are destroyed right
+/// before it.
+///
+/// [`Instance::create_headless_surface`]: crate::instance::Instance::create_headless_surface
+pub struct HeadlessSurface {
+    instance: VkInstance,
+    handle: VkSurfaceKHR,
+}
+
+impl HeadlessSurface {
+    /// Creates a headless surface on `instance`.
+    ///
+    /// Returns `VK_ERROR_EXTENSION_NOT_PRESENT` when the instance was created without `VK_EXT_headless_surface`.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must be a valid instance, and the surface must be dropped before the instance is destroyed.
+    pub unsafe fn new(instance: VkInstance) -> Result<HeadlessSurface, VkResult> {
+        // The loader only hands out the function when the extension is enabled, which saves calling into a terminator
+        // that would log an error.
+        let create: PFN_vkCreateHeadlessSurfaceEXT = std::mem::transmute(vkGetInstanceProcAddr(
+            instance,
+            c"vkCreateHeadlessSurfaceEXT".as_ptr(),
+        ));
+        let Some(create) = create else {
+            return Err(VK_ERROR_EXTENSION_NOT_PRESENT);
+        };
+
+        let create_info = VkHeadlessSurfaceCreateInfoEXT {
+            sType: VK_STRUCTURE_TYPE_HEADLESS_SURFACE_CREATE_INFO_EXT,
+            pNext: null(),
+            flags: 0,
+        };
+
+        let mut handle = null_mut();
+        let result = create(instance, &create_info, null(), &mut handle);
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+
+        Ok(HeadlessSurface { instance, handle })
+    }
+
+    pub fn handle(&self) -> VkSurfaceKHR {
+        self.handle
+    }
+
+    /// Whether queue family `queue_family` of `physical_device` can present to the surface.
+    ///
+    /// # Safety
+    ///
+    /// `physical_device` must come from the instance the surface was created on.
+    pub unsafe fn supports_queue_family(
+        &self,
+        physical_device: VkPhysicalDevice,
+        queue_family: u32,
+    ) -> Result<bool, VkResult> {
+        let mut supported = VK_FALSE;
+        let result = vkGetPhysicalDeviceSurfaceSupportKHR(
+            physical_device,
+            queue_family,
+            self.handle,
+            &mut supported,
+        );
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+        Ok(supported == VK_TRUE)
+    }
+
+    /// The extents, image counts and transforms `physical_device` supports for swapchains on the surface.
+    ///
+    /// # Safety
+    ///
+    /// `physical_device` must come from the instance the surface was created on.
+    pub unsafe fn capabilities(
+        &self,
+        physical_device: VkPhysicalDevice,
+    ) -> Result<VkSurfaceCapabilitiesKHR, VkResult> {
+        let mut capabilities = MaybeUninit::uninit();
+        let result = vkGetPhysicalDeviceSurfaceCapabilitiesKHR(
+            physical_device,
+            self.handle,
+            capabilities.as_mut_ptr(),
+        );
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+        Ok(capabilities.assume_init())
+    }
+
+    /// The formats `physical_device` supports for swapchains on the surface.
+    ///
+    /// # Safety
+    ///
+    /// `physical_device` must come from the instance the surface was created on.
+    pub unsafe fn formats(
+        &self,
+        physical_device: VkPhysicalDevice,
+    ) -> Result<Vec<VkSurfaceFormatKHR>, VkResult> {
+        let mut count = 0;
+        let result = vkGetPhysicalDeviceSurfaceFormatsKHR(
+            physical_device,
+            self.handle,
+            &mut count,
+            null_mut(),
+        );
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+
+        let mut formats = Vec::with_capacity(count as usize);
+        let result = vkGetPhysicalDeviceSurfaceFormatsKHR(
+            physical_device,
+            self.handle,
+            &mut count,
+            formats.as_mut_ptr(),
+        );
+        if result != VK_SUCCESS && result != VK_INCOMPLETE {
+            return Err(result);
+        }
+        formats.set_len(count as usize);
+        Ok(formats)
+    }
+
+    /// The present modes `physical_device` supports for swapchains on the surface.
+    ///
+    /// # Safety
+    ///
+    /// `physical_device` must come from the instance the surface was created on.
+    pub unsafe fn present_modes(
+        &self,
+        physical_device: VkPhysicalDevice,
+    ) -> Result<Vec<VkPresentModeKHR>, VkResult> {
+        let mut count = 0;
+        let result = vkGetPhysicalDeviceSurfacePresentModesKHR(
+            physical_device,
+            self.handle,
+            &mut count,
+            null_mut(),
+        );
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+
+        let mut present_modes = Vec::with_capacity(count as usize);
+        let result = vkGetPhysicalDeviceSurfacePresentModesKHR(
+            physical_device,
+            self.handle,
+            &mut count,
+            present_modes.as_mut_ptr(),
+        );
+        if result != VK_SUCCESS && result != VK_INCOMPLETE {
+            return Err(result);
+        }
+        present_modes.set_len(count as usize);
+        Ok(present_modes)
+    }
+}
+
+impl Drop for HeadlessSurface {
+    fn drop(&mut self) {
+        unsafe { vkDestroySurfaceKHR(self.instance, self.handle, null()) };
+    }
+}
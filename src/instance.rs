@@ -0,0 +1,155 @@
+//! An owning wrapper around `VkInstance`.
+//!
+//! [`Instance`] destroys the instance when it is dropped, together with every object that was created through it, such
+//! as debug-utils messengers and headless surfaces. Instances created with [`AllocationCallbacks`] keep them alive
+//! until then as well.
+
+use std::{
+    ffi::CStr,
+    ptr::{null, null_mut},
+    sync::Arc,
+};
+
+use libloading::Library;
+
+#[cfg(feature = "headless-surface")]
+use crate::headless_surface::HeadlessSurface;
+use crate::{
+    allocation::AllocationCallbacks, debug_utils::DebugUtilsMessage,
+    debug_utils::DebugUtilsMessenger, *,
+};
+
+pub struct Instance {
+    handle: VkInstance,
+    allocator: Option<Arc<AllocationCallbacks>>,
+    messengers: Vec<DebugUtilsMessenger>,
+    #[cfg(feature = "headless-surface")]
+    surfaces: Vec<HeadlessSurface>,
+    // Driver libraries opened on behalf of the instance. They are only unloaded after the instance is gone.
+    libraries: Vec<Arc<Library>>,
+}
+
+impl Instance {
+    /// Calls `vkCreateInstance` with `create_info`.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer in `create_info`, including its `pNext` chain, must be valid for the duration of the call.
+    pub unsafe fn new(create_info: &VkInstanceCreateInfo) -> Result<Instance, VkResult> {
+        let mut handle = null_mut();
+        let result = vkCreateInstance(create_info, null(), &mut handle);
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+
+        Ok(Instance::from_raw(handle))
+    }
+
+    /// Calls `vkCreateInstance` with `create_info`, routing every host allocation made for the instance through
+    /// `allocator`.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer in `create_info`, including its `pNext` chain, must be valid for the duration of the call.
+    pub unsafe fn with_allocator(
+        create_info: &VkInstanceCreateInfo,
+        allocator: Arc<AllocationCallbacks>,
+    ) -> Result<Instance, VkResult> {
+        let mut handle = null_mut();
+        let result = vkCreateInstance(create_info, allocator.as_raw(), &mut handle);
+        if result != VK_SUCCESS {
+            return Err(result);
+        }
+
+        Ok(Instance {
+            handle,
+            allocator: Some(allocator),
+            messengers: Vec::new(),
+            #[cfg(feature = "headless-surface")]
+            surfaces: Vec::new(),
+            libraries: Vec::new(),
+        })
+    }
+
+    /// Takes ownership of an instance that was created elsewhere.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must be a valid instance created without allocation callbacks, and nothing else may destroy it.
+    pub unsafe fn from_raw(handle: VkInstance) -> Instance {
+        Instance {
+            handle,
+            allocator: None,
+            messengers: Vec::new(),
+            #[cfg(feature = "headless-surface")]
+            surfaces: Vec::new(),
+            libraries: Vec::new(),
+        }
+    }
+
+    pub fn handle(&self) -> VkInstance {
+        self.handle
+    }
+
+    /// The allocation callbacks the instance was created with, or null.
+    pub fn allocator(&self) -> *const VkAllocationCallbacks {
+        self.allocator
+            .as_ref()
+            .map_or(null(), |allocator| allocator.as_raw())
+    }
+
+    /// Looks up an instance level command with `vkGetInstanceProcAddr`.
+    pub fn get_proc_addr(&self, name: &CStr) -> PFN_vkVoidFunction {
+        unsafe { vkGetInstanceProcAddr(self.handle, name.as_ptr()) }
+    }
+
+    /// Creates a debug-utils messenger that reports messages matching `severities` and `types` to `callback`. The
+    /// messenger lives as long as the instance does.
+    ///
+    /// The instance must have been created with `VK_EXT_debug_utils` enabled, otherwise this returns
+    /// `VK_ERROR_EXTENSION_NOT_PRESENT`.
+    pub fn create_debug_utils_messenger(
+        &mut self,
+        severities: VkDebugUtilsMessageSeverityFlagsEXT,
+        types: VkDebugUtilsMessageTypeFlagsEXT,
+        callback: impl Fn(&DebugUtilsMessage) + Send + Sync + 'static,
+    ) -> Result<VkDebugUtilsMessengerEXT, VkResult> {
+        let messenger =
+            unsafe { DebugUtilsMessenger::new(self.handle, severities, types, callback)? };
+        let handle = messenger.handle();
+        self.messengers.push(messenger);
+        Ok(handle)
+    }
+
+    /// Creates a surface that isn't backed by a window, for running swapchain code without a display server. The
+    /// surface lives as long as the instance does.
+    ///
+    /// The instance must have been created with [`HEADLESS_SURFACE_EXTENSIONS`] enabled, otherwise this returns
+    /// `VK_ERROR_EXTENSION_NOT_PRESENT`.
+    ///
+    /// [`HEADLESS_SURFACE_EXTENSIONS`]: crate::headless_surface::HEADLESS_SURFACE_EXTENSIONS
+    #[cfg(feature = "headless-surface")]
+    pub fn create_headless_surface(&mut self) -> Result<VkSurfaceKHR, VkResult> {
+        let surface = unsafe { HeadlessSurface::new(self.handle)? };
+        let handle = surface.handle();
+        self.surfaces.push(surface);
+        Ok(handle)
+    }
+}
+
+impl Instance {
+    pub(crate) fn keep_alive(&mut self, library: Arc<Library>) {
+        self.libraries.push(library);
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        // Everything created from the instance has to go before the instance itself.
+        #[cfg(feature = "headless-surface")]
+        self.surfaces.clear();
+        self.messengers.clear();
+
+        unsafe { vkDestroyInstance(self.handle, self.allocator()) };
+    }
+}
@@ -14,9 +14,64 @@
 // }
 
 #![allow(nonstandard_style)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 
 include!(concat!(env!("OUT_DIR"), "/vulkan.rs"));
 
+/// The provisional extensions from `vulkan_beta.h`, such as `VK_KHR_portability_subset` and the video encode
+/// extensions, together with the video std types only they use.
+///
+/// These are not part of the stable Vulkan API. Khronos may change them incompatibly with any header update, and so
+/// they can change with any release of this crate, patch releases included. This includes the values the provisional
+/// extensions add to core enums, like their `VkStructureType`s, which are only declared in here as well. Everything in
+/// here is also available from the crate root.
+#[cfg(feature = "beta-extensions")]
+#[cfg_attr(docsrs, doc(cfg(feature = "beta-extensions")))]
+pub mod beta_extensions {
+    use super::*;
+
+    include!(concat!(env!("OUT_DIR"), "/beta_extensions.rs"));
+}
+#[cfg(feature = "beta-extensions")]
+#[cfg_attr(docsrs, doc(cfg(feature = "beta-extensions")))]
+pub use beta_extensions::*;
+
+pub mod allocation;
+#[cfg(feature = "tools")]
+pub mod call_chain;
+#[cfg(feature = "tools")]
+pub mod debug_log;
+pub mod debug_utils;
+pub mod direct_driver;
+#[cfg(feature = "tools")]
+pub mod discovery;
+#[cfg(feature = "headless-surface")]
+pub mod headless_surface;
+pub mod instance;
+#[cfg(feature = "tools")]
+pub mod library;
+pub mod loader_log;
+#[cfg(feature = "tools")]
+pub mod manifest;
+pub mod search_paths;
+
+pub use allocation::AllocationCallbacks;
+pub use instance::Instance;
+
+/// The commands declared as extern functions above, which are exactly the ones libvulkan exports. Every other command
+/// only has its `PFN_vk*` type and has to be looked up with `vkGetInstanceProcAddr` or `vkGetDeviceProcAddr`.
+pub const EXPORTED_COMMANDS: &[&str] = &include!(concat!(env!("OUT_DIR"), "/exported_commands.rs"));
+
+/// The directory holding the loader's test drivers and layers, when they were built through the `loader-tests` feature.
+/// Meant for the test-support crate, which uses it to locate them.
+#[doc(hidden)]
+pub const LOADER_TEST_BINARY_DIR: Option<&str> = option_env!("VULKAN_LOADER_SYS_TEST_BINARY_DIR");
+
+/// The directory the loader searches in place of `/etc` when it was built through the `loader-tests` feature. Meant for
+/// the test-support crate, which links it into its environments.
+#[doc(hidden)]
+pub const LOADER_TEST_SYSCONFDIR: Option<&str> = option_env!("VULKAN_LOADER_SYS_TEST_SYSCONFDIR");
+
 #[inline]
 pub fn VK_MAKE_API_VERSION(variant: u32, major: u32, minor: u32, patch: u32) -> u32 {
     (variant << 29) | (major << 22) | (minor << 12) | patch
@@ -0,0 +1,253 @@
+//! Opening driver and layer libraries the way the loader does.
+//!
+//! [`check_driver_library`] and [`check_layer_library`] load a library, look up the entry points the loader needs and
+//! negotiate the interface version with it, so that problems the loader would only log while creating an instance can
+//! be reported up front.
+//!
+//! Available with the `tools` feature.
+
+use std::{
+    ffi::{c_void, CString},
+    os::raw::c_char,
+    path::{Path, PathBuf},
+};
+
+use libloading::Library;
+use serde_json::{json, Value};
+
+use crate::{
+    manifest::{version_to_string, Layer},
+    VkResult, VK_ERROR_INCOMPATIBLE_DRIVER, VK_SUCCESS,
+};
+
+// The interface versions this loader implements, from vk_icd.h and vk_layer.h.
+pub const CURRENT_LOADER_ICD_INTERFACE_VERSION: u32 = 7;
+pub const CURRENT_LOADER_LAYER_INTERFACE_VERSION: u32 = 2;
+pub const MIN_SUPPORTED_LOADER_LAYER_INTERFACE_VERSION: u32 = 1;
+
+pub type NegotiateIcdInterfaceVersion = unsafe extern "system" fn(*mut u32) -> VkResult;
+pub type EnumerateInstanceVersion = unsafe extern "system" fn(*mut u32) -> VkResult;
+pub type GetInstanceProcAddr =
+    unsafe extern "system" fn(*mut c_void, *const c_char) -> Option<unsafe extern "system" fn()>;
+pub type GetDeviceProcAddr =
+    unsafe extern "system" fn(*mut c_void, *const c_char) -> Option<unsafe extern "system" fn()>;
+
+/// `VkNegotiateLayerInterface` from vk_layer.h, which is not part of the bindings.
+#[repr(C)]
+pub struct NegotiateLayerInterface {
+    pub s_type: u32,
+    pub p_next: *mut c_void,
+    pub loader_layer_interface_version: u32,
+    pub pfn_get_instance_proc_addr: Option<unsafe extern "system" fn()>,
+    pub pfn_get_device_proc_addr: Option<unsafe extern "system" fn()>,
+    pub pfn_get_physical_device_proc_addr: Option<unsafe extern "system" fn()>,
+}
+
+pub const LAYER_NEGOTIATE_INTERFACE_STRUCT: u32 = 1;
+
+pub type NegotiateLayerInterfaceVersion =
+    unsafe extern "system" fn(*mut NegotiateLayerInterface) -> VkResult;
+
+/// What came of opening a driver or layer library.
+#[derive(Default)]
+pub struct LibraryCheck {
+    pub path: PathBuf,
+    pub error: Option<String>,
+    pub interface_version: Option<u32>,
+    /// What a driver's `vkEnumerateInstanceVersion` reports, if it has one.
+    pub api_version: Option<u32>,
+    /// Whether a layer has a `vkGetDeviceProcAddr`, without which the loader leaves it out of device call chains.
+    pub has_device_proc_addr: bool,
+    pub notes: Vec<String>,
+}
+
+impl LibraryCheck {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "path": self.path,
+            "error": self.error,
+            "interface_version": self.interface_version,
+            "api_version": self.api_version.map(version_to_string),
+            "notes": self.notes,
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        match (&self.error, self.interface_version) {
+            (Some(error), _) => format!("{} ({})", self.path.display(), error),
+            (None, Some(version)) => {
+                format!("{} (interface version {})", self.path.display(), version)
+            }
+            (None, None) => format!("{} (loaded)", self.path.display()),
+        }
+    }
+}
+
+/// Opens a library with the platform's default flags, like `loader_platform_open_library`.
+pub fn open_library(path: &Path) -> Result<Library, String> {
+    unsafe { Library::new(path) }.map_err(|error| error.to_string())
+}
+
+/// Looks up an exported function. `T` has to be the function pointer type the symbol really has.
+pub fn symbol<T: Copy>(library: &Library, name: &str) -> Option<T> {
+    let name = CString::new(name).ok()?;
+    unsafe { library.get::<T>(name.as_bytes_with_nul()) }
+        .ok()
+        .map(|symbol| *symbol)
+}
+
+/// Loads a driver the way `loader_scanned_icd_add` does, up to and including the interface version negotiation.
+pub fn check_driver_library(path: PathBuf) -> LibraryCheck {
+    let mut check = LibraryCheck {
+        path,
+        ..Default::default()
+    };
+    let library = match open_library(&check.path) {
+        Ok(library) => library,
+        Err(error) => {
+            check.error = Some(error);
+            return check;
+        }
+    };
+
+    let get_instance_proc_addr =
+        symbol::<GetInstanceProcAddr>(&library, "vk_icdGetInstanceProcAddr");
+    let negotiate = symbol::<NegotiateIcdInterfaceVersion>(
+        &library,
+        "vk_icdNegotiateLoaderICDInterfaceVersion",
+    )
+    .or_else(|| {
+        // Drivers for interface version 7 and up may hand it out through vk_icdGetInstanceProcAddr only.
+        let get_instance_proc_addr = get_instance_proc_addr?;
+        let function = unsafe {
+            get_instance_proc_addr(
+                std::ptr::null_mut(),
+                c"vk_icdNegotiateLoaderICDInterfaceVersion".as_ptr(),
+            )
+        }?;
+        Some(unsafe {
+            std::mem::transmute::<unsafe extern "system" fn(), NegotiateIcdInterfaceVersion>(
+                function,
+            )
+        })
+    });
+
+    let mut version = 0;
+    if let Some(negotiate) = negotiate {
+        version = CURRENT_LOADER_ICD_INTERFACE_VERSION;
+        let result = unsafe { negotiate(&mut version) };
+        if result == VK_ERROR_INCOMPATIBLE_DRIVER {
+            check.error = Some(
+                "the driver does not support an interface version compatible with the loader"
+                    .into(),
+            );
+            return check;
+        }
+    }
+
+    if get_instance_proc_addr.is_none() {
+        if version != 0 {
+            check.error = Some(format!(
+                "reports interface version {} but doesn't export vk_icdGetInstanceProcAddr",
+                version
+            ));
+            return check;
+        }
+        if symbol::<GetInstanceProcAddr>(&library, "vkGetInstanceProcAddr").is_none() {
+            check.error =
+                Some("exports neither vk_icdGetInstanceProcAddr nor vkGetInstanceProcAddr".into());
+            return check;
+        }
+        check.notes.push(
+            "uses the deprecated vkGetInstanceProcAddr export instead of vk_icdGetInstanceProcAddr"
+                .into(),
+        );
+    } else if version == 0 {
+        check.notes.push(
+            "doesn't export vk_icdNegotiateLoaderICDInterfaceVersion, the loader assumes interface version 1".into(),
+        );
+        version = 1;
+    }
+
+    check.interface_version = Some(version);
+
+    // Asked for the same way as when the loader creates an instance with the driver.
+    if let Some(get_instance_proc_addr) = get_instance_proc_addr {
+        let function = unsafe {
+            get_instance_proc_addr(std::ptr::null_mut(), c"vkEnumerateInstanceVersion".as_ptr())
+        };
+        if let Some(function) = function {
+            let enumerate_instance_version = unsafe {
+                std::mem::transmute::<unsafe extern "system" fn(), EnumerateInstanceVersion>(
+                    function,
+                )
+            };
+            let mut api_version = 0;
+            if unsafe { enumerate_instance_version(&mut api_version) } == VK_SUCCESS {
+                check.api_version = Some(api_version);
+            }
+        }
+    }
+
+    check
+}
+
+/// Loads a layer and negotiates its interface version, like `loader_get_layer_interface_version`.
+pub fn check_layer_library(layer: &Layer, path: PathBuf) -> LibraryCheck {
+    let mut check = LibraryCheck {
+        path,
+        ..Default::default()
+    };
+    let library = match open_library(&check.path) {
+        Ok(library) => library,
+        Err(error) => {
+            check.error = Some(error);
+            return check;
+        }
+    };
+
+    if let Some(negotiate) =
+        symbol::<NegotiateLayerInterfaceVersion>(&library, layer.negotiate_function_name())
+    {
+        let mut interface = NegotiateLayerInterface {
+            s_type: LAYER_NEGOTIATE_INTERFACE_STRUCT,
+            p_next: std::ptr::null_mut(),
+            loader_layer_interface_version: CURRENT_LOADER_LAYER_INTERFACE_VERSION,
+            pfn_get_instance_proc_addr: None,
+            pfn_get_device_proc_addr: None,
+            pfn_get_physical_device_proc_addr: None,
+        };
+        let result = unsafe { negotiate(&mut interface) };
+        let version = interface.loader_layer_interface_version;
+        check.has_device_proc_addr = interface.pfn_get_device_proc_addr.is_some()
+            || symbol::<GetDeviceProcAddr>(&library, layer.get_device_proc_addr_name()).is_some();
+        if result != VK_SUCCESS || version < MIN_SUPPORTED_LOADER_LAYER_INTERFACE_VERSION {
+            check.error = Some(format!(
+                "{} failed with {} and interface version {}",
+                layer.negotiate_function_name(),
+                result,
+                version
+            ));
+        } else {
+            check.interface_version = Some(version);
+        }
+        return check;
+    }
+
+    check.has_device_proc_addr =
+        symbol::<GetDeviceProcAddr>(&library, layer.get_device_proc_addr_name()).is_some();
+    if symbol::<GetInstanceProcAddr>(&library, layer.get_instance_proc_addr_name()).is_none() {
+        check.error = Some(format!(
+            "exports neither {} nor {}",
+            layer.negotiate_function_name(),
+            layer.get_instance_proc_addr_name()
+        ));
+    } else {
+        check.notes.push(format!(
+            "doesn't export {}, the loader falls back to {}",
+            layer.negotiate_function_name(),
+            layer.get_instance_proc_addr_name()
+        ));
+    }
+    check
+}
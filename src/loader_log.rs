@@ -0,0 +1,321 @@
+//! Capturing the loader's own diagnostic messages from within the process.
+//!
+//! Normally the messages produced by the loader only end up on `stderr` (when `VK_LOADER_DEBUG` asks for them) or in a
+//! debug-utils messenger once an instance exists. Anything that goes wrong while scanning manifests or inside
+//! `vkCreateInstance` is therefore easy to lose. The loader built by this crate exports an additional hook,
+//! [`vulkan_loader_sys_set_log_callback`], which sees every message before any filtering takes place. [`install`] wires
+//! that hook up to the [`log`] crate, using the loader's category bits to pick the target, and can optionally keep a
+//! copy of every message around for later display.
+//!
+//! The verbosity of the loader's `stderr` output can likewise be changed at runtime with [`set_debug_level`], rather
+//! than only through `VK_LOADER_DEBUG` before the library is loaded.
+//!
+//! A loader taken from `VULKAN_LOADER_SYS_LIB_DIR` usually lacks the hook. build.rs then leaves out everything that
+//! calls it, and only [`messenger_create_info`] remains to capture messages.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_void, CStr},
+    os::raw::c_char,
+    ptr::{null, null_mut},
+    sync::Mutex,
+};
+
+use crate::*;
+
+// Mirrors `enum vulkan_loader_debug_flags` in loader/log.h.
+pub const VULKAN_LOADER_INFO_BIT: u32 = 0x01;
+pub const VULKAN_LOADER_WARN_BIT: u32 = 0x02;
+pub const VULKAN_LOADER_PERF_BIT: u32 = 0x04;
+pub const VULKAN_LOADER_ERROR_BIT: u32 = 0x08;
+pub const VULKAN_LOADER_DEBUG_BIT: u32 = 0x10;
+pub const VULKAN_LOADER_LAYER_BIT: u32 = 0x20;
+pub const VULKAN_LOADER_DRIVER_BIT: u32 = 0x40;
+pub const VULKAN_LOADER_VALIDATION_BIT: u32 = 0x80;
+pub const VULKAN_LOADER_FATAL_ERROR_BIT: u32 = 0x100;
+
+/// Every message category the loader knows about.
+pub const VULKAN_LOADER_ALL_BITS: u32 = 0x1FF;
+
+pub type PFN_vulkan_loader_sys_log_callback = Option<
+    unsafe extern "C" fn(
+        msg_type: u32,
+        msg_code: i32,
+        message: *const c_char,
+        user_data: *mut c_void,
+    ),
+>;
+
+#[cfg(loader_log_hook)]
+extern "C" {
+    /// Registers `callback` to receive every loader message whose type intersects `mask`. Passing `None` removes the
+    /// current callback. Only one callback can be registered at a time.
+    pub fn vulkan_loader_sys_set_log_callback(
+        mask: u32,
+        callback: PFN_vulkan_loader_sys_log_callback,
+        user_data: *mut c_void,
+    );
+
+    /// Replaces the set of message categories the loader writes to `stderr`, as if `VK_LOADER_DEBUG` had been set.
+    pub fn vulkan_loader_sys_set_debug_level(debug_level: u32);
+
+    /// Returns the set of message categories the loader currently writes to `stderr`.
+    pub fn vulkan_loader_sys_get_debug_level() -> u32;
+}
+
+/// Changes which message categories the loader writes to `stderr` while the process is running. This takes effect for
+/// every message logged from then on, but an active loader settings file overrides it whenever the loader re-reads it.
+#[cfg(loader_log_hook)]
+pub fn set_debug_level(flags: u32) {
+    unsafe { vulkan_loader_sys_set_debug_level(flags) }
+}
+
+/// Returns the message categories the loader currently writes to `stderr`.
+#[cfg(loader_log_hook)]
+pub fn debug_level() -> u32 {
+    unsafe { vulkan_loader_sys_get_debug_level() }
+}
+
+/// Parses a comma separated filter list with the same rules the loader applies to `VK_LOADER_DEBUG`, e.g.
+/// `"error,warn,driver"`. Unknown entries are ignored.
+pub fn parse_debug_level(filters: &str) -> u32 {
+    filters
+        .split(',')
+        .filter(|filter| !filter.is_empty())
+        .fold(0, |flags, filter| {
+            // The loader compares with strncmp against the length of the entry, so prefixes match as well.
+            let matches = |name: &str| name.starts_with(filter);
+            if matches("all") {
+                !0
+            } else if matches("warn") {
+                flags | VULKAN_LOADER_WARN_BIT
+            } else if matches("info") {
+                flags | VULKAN_LOADER_INFO_BIT
+            } else if matches("perf") {
+                flags | VULKAN_LOADER_PERF_BIT
+            } else if matches("error") {
+                flags | VULKAN_LOADER_ERROR_BIT
+            } else if matches("debug") {
+                flags | VULKAN_LOADER_DEBUG_BIT
+            } else if matches("layer") {
+                flags | VULKAN_LOADER_LAYER_BIT
+            } else if matches("driver") || matches("implem") || matches("icd") {
+                flags | VULKAN_LOADER_DRIVER_BIT
+            } else {
+                flags
+            }
+        })
+}
+
+/// A single message emitted by the loader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoaderMessage {
+    /// The `VULKAN_LOADER_*_BIT` flags the message was logged with.
+    pub flags: u32,
+    pub code: i32,
+    pub message: String,
+}
+
+impl LoaderMessage {
+    /// The [`log`] target the message is routed to, derived from its category bits.
+    pub fn target(&self) -> &'static str {
+        if self.flags & VULKAN_LOADER_LAYER_BIT != 0 {
+            "vulkan_loader::layer"
+        } else if self.flags & VULKAN_LOADER_DRIVER_BIT != 0 {
+            "vulkan_loader::driver"
+        } else if self.flags & VULKAN_LOADER_VALIDATION_BIT != 0 {
+            "vulkan_loader::validation"
+        } else if self.flags & VULKAN_LOADER_PERF_BIT != 0 {
+            "vulkan_loader::perf"
+        } else {
+            "vulkan_loader"
+        }
+    }
+
+    /// The [`log::Level`] of the message, see [`level`].
+    pub fn level(&self) -> log::Level {
+        level(self.flags)
+    }
+}
+
+/// The [`log::Level`] matching a set of `VULKAN_LOADER_*_BIT` flags. Messages that only carry the layer or driver bit
+/// are treated as info, the same way the loader does when forwarding them to debug-utils.
+pub fn level(flags: u32) -> log::Level {
+    if flags & (VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_FATAL_ERROR_BIT) != 0 {
+        log::Level::Error
+    } else if flags & VULKAN_LOADER_WARN_BIT != 0 {
+        log::Level::Warn
+    } else if flags & VULKAN_LOADER_INFO_BIT != 0 {
+        log::Level::Info
+    } else if flags & VULKAN_LOADER_DEBUG_BIT != 0 {
+        log::Level::Debug
+    } else {
+        log::Level::Info
+    }
+}
+
+struct Capture {
+    buffer: Option<Vec<LoaderMessage>>,
+}
+
+static CAPTURE: Mutex<Capture> = Mutex::new(Capture { buffer: None });
+
+thread_local! {
+    // The last message the global callback forwarded on this thread, with the address of the loader's buffer it was in.
+    // loader_log hands the same buffer to the global callback first and to the debug-utils messengers right after,
+    // which is how `messenger_callback` recognises a message it would otherwise report twice.
+    static FORWARDED: RefCell<Option<(usize, String)>> = const { RefCell::new(None) };
+}
+
+fn dispatch(message: LoaderMessage) {
+    log::log!(target: message.target(), message.level(), "{}", message.message);
+
+    let mut capture = CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(buffer) = capture.buffer.as_mut() {
+        buffer.push(message);
+    }
+}
+
+#[cfg(loader_log_hook)]
+unsafe extern "C" fn global_callback(
+    msg_type: u32,
+    msg_code: i32,
+    message: *const c_char,
+    _user_data: *mut c_void,
+) {
+    if message.is_null() {
+        return;
+    }
+
+    let message_address = message as usize;
+    let message = LoaderMessage {
+        flags: msg_type,
+        code: msg_code,
+        message: CStr::from_ptr(message).to_string_lossy().into_owned(),
+    };
+    FORWARDED.with(|forwarded| {
+        *forwarded.borrow_mut() = Some((message_address, message.message.clone()));
+    });
+
+    // Unwinding into the loader is undefined behaviour, so whatever the logger does has to stay on this side.
+    let _ = std::panic::catch_unwind(|| dispatch(message));
+}
+
+/// Starts forwarding every loader message whose type intersects `mask` to the [`log`] crate. When `buffered` is set,
+/// the messages are also kept until they are retrieved with [`take_buffered`].
+///
+/// This is process wide and replaces any previous installation. The loader holds a lock while the callback runs, so the
+/// logger must not call into the loader itself.
+#[cfg(loader_log_hook)]
+pub fn install(mask: u32, buffered: bool) {
+    {
+        let mut capture = CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+        capture.buffer = if buffered {
+            Some(capture.buffer.take().unwrap_or_default())
+        } else {
+            None
+        };
+    }
+
+    unsafe { vulkan_loader_sys_set_log_callback(mask, Some(global_callback), null_mut()) };
+}
+
+/// Stops forwarding loader messages. Anything still buffered stays available through [`take_buffered`].
+#[cfg(loader_log_hook)]
+pub fn uninstall() {
+    unsafe { vulkan_loader_sys_set_log_callback(0, None, null_mut()) };
+
+    FORWARDED.with(|forwarded| forwarded.borrow_mut().take());
+}
+
+/// Returns and clears the messages buffered since the last call.
+#[cfg(loader_log_hook)]
+pub fn take_buffered() -> Vec<LoaderMessage> {
+    let mut capture = CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+    match capture.buffer.as_mut() {
+        Some(buffer) => std::mem::take(buffer),
+        None => Vec::new(),
+    }
+}
+
+unsafe extern "system" fn messenger_callback(
+    message_severity: VkDebugUtilsMessageSeverityFlagBitsEXT,
+    message_types: VkDebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const VkDebugUtilsMessengerCallbackDataEXT,
+    _user_data: *mut c_void,
+) -> VkBool32 {
+    if callback_data.is_null()
+        || (*callback_data).pMessage.is_null()
+        || (*callback_data).pMessageIdName.is_null()
+    {
+        return VK_FALSE;
+    }
+
+    // Only pick up the loader's own messages; anything coming from layers is none of our business here.
+    if CStr::from_ptr((*callback_data).pMessageIdName).to_bytes() != b"Loader Message" {
+        return VK_FALSE;
+    }
+
+    // Skip the message if the global callback just forwarded it, i.e. when its categories intersect the installed mask.
+    let message_address = (*callback_data).pMessage as usize;
+    let message = CStr::from_ptr((*callback_data).pMessage).to_string_lossy();
+    let forwarded = FORWARDED.with(|forwarded| {
+        let mut forwarded = forwarded.borrow_mut();
+        match &*forwarded {
+            Some((address, text)) if *address == message_address && *text == message => {
+                *forwarded = None;
+                true
+            }
+            _ => false,
+        }
+    });
+    if forwarded {
+        return VK_FALSE;
+    }
+
+    // The debug-utils path loses the layer and driver bits, so reconstruct what can be reconstructed.
+    let mut flags = match message_severity {
+        VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT => VULKAN_LOADER_ERROR_BIT,
+        VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT => VULKAN_LOADER_WARN_BIT,
+        VK_DEBUG_UTILS_MESSAGE_SEVERITY_INFO_BIT_EXT => VULKAN_LOADER_INFO_BIT,
+        _ => VULKAN_LOADER_DEBUG_BIT,
+    };
+    if message_types & VK_DEBUG_UTILS_MESSAGE_TYPE_PERFORMANCE_BIT_EXT != 0 {
+        flags |= VULKAN_LOADER_PERF_BIT;
+    }
+    if message_types & VK_DEBUG_UTILS_MESSAGE_TYPE_VALIDATION_BIT_EXT != 0 {
+        flags |= VULKAN_LOADER_VALIDATION_BIT;
+    }
+
+    let message = LoaderMessage {
+        flags,
+        code: (*callback_data).messageIdNumber,
+        message: message.into_owned(),
+    };
+
+    let _ = std::panic::catch_unwind(|| dispatch(message));
+
+    VK_FALSE
+}
+
+/// Returns a messenger create info that routes the loader's messages the same way [`install`] does. Chain it into the
+/// `pNext` of a `VkInstanceCreateInfo` to capture messages emitted during `vkCreateInstance` and `vkDestroyInstance`.
+///
+/// Loader messages that the global callback already picked up, because their categories intersect the mask passed to
+/// [`install`], are not reported a second time. Everything else the messenger sees is.
+pub fn messenger_create_info() -> VkDebugUtilsMessengerCreateInfoEXT {
+    VkDebugUtilsMessengerCreateInfoEXT {
+        sType: VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
+        pNext: null(),
+        flags: 0,
+        messageSeverity: VK_DEBUG_UTILS_MESSAGE_SEVERITY_VERBOSE_BIT_EXT
+            | VK_DEBUG_UTILS_MESSAGE_SEVERITY_INFO_BIT_EXT
+            | VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT
+            | VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT,
+        messageType: VK_DEBUG_UTILS_MESSAGE_TYPE_GENERAL_BIT_EXT
+            | VK_DEBUG_UTILS_MESSAGE_TYPE_VALIDATION_BIT_EXT
+            | VK_DEBUG_UTILS_MESSAGE_TYPE_PERFORMANCE_BIT_EXT,
+        pfnUserCallback: Some(messenger_callback),
+        pUserData: null_mut(),
+    }
+}
@@ -0,0 +1,1166 @@
+//! Reading driver and layer manifests the way the loader does.
+//!
+//! [`DriverManifest::parse`] and [`LayerManifest::parse`] apply the same checks as `loader_parse_icd_manifest` and
+//! `loader_read_layer_json` in loader/loader.c, and record what the loader would have logged as [`Diagnostic`]s. A
+//! diagnostic with [`Severity::Error`] means the loader skips the driver or layer.
+//!
+//! Available with the `tools` feature.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+
+/// `VK_MAX_EXTENSION_NAME_SIZE` and `VK_MAX_DESCRIPTION_SIZE`, including the terminator.
+const MAX_STRING_SIZE: usize = 256;
+
+/// The name of the layer that `vkconfig` uses to override the layer configuration.
+pub const OVERRIDE_LAYER_NAME: &str = "VK_LAYER_LUNARG_override";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Informational, the loader carries on as usual.
+    Info,
+    /// The loader warns about it, but still uses the driver or layer.
+    Warning,
+    /// The loader skips the driver or layer.
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity.as_str(), self.message)
+    }
+}
+
+fn push(diagnostics: &mut Vec<Diagnostic>, severity: Severity, message: impl Into<String>) {
+    diagnostics.push(Diagnostic {
+        severity,
+        message: message.into(),
+    });
+}
+
+fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+/// A `major.minor.patch` manifest file format version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct FileFormatVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl FileFormatVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> FileFormatVersion {
+        FileFormatVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    fn from_api_version(version: u32) -> FileFormatVersion {
+        FileFormatVersion::new(
+            (version >> 22) & 0x7F,
+            (version >> 12) & 0x3FF,
+            version & 0xFFF,
+        )
+    }
+}
+
+impl fmt::Display for FileFormatVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+// atoi: leading digits, anything after them is ignored.
+fn atoi(string: &str) -> u32 {
+    let string = string.trim_start();
+    let digits = string
+        .char_indices()
+        .take_while(|(_, c)| c.is_ascii_digit())
+        .count();
+    string[..digits].parse().unwrap_or(0)
+}
+
+/// Parses a version string into a `VK_MAKE_API_VERSION` value, exactly like `loader_parse_version_string`: three parts
+/// are `major.minor.patch`, four parts are `variant.major.minor.patch`.
+pub fn parse_version_string(version: &str) -> u32 {
+    let parts = version
+        .split(['.', '"', '\n', '\r'])
+        .filter(|part| !part.is_empty())
+        .map(|part| atoi(part) & 0xFFFF)
+        .collect::<Vec<_>>();
+
+    let (variant, major, minor, patch) = match parts[..] {
+        [] => (0, 0, 0, 0),
+        [major] => (0, major, 0, 0),
+        [major, minor] => (0, major, minor, 0),
+        [major, minor, patch] => (0, major, minor, patch),
+        [variant, major, minor, patch, ..] => (variant, major, minor, patch),
+    };
+
+    crate::VK_MAKE_API_VERSION(variant, major, minor, patch)
+}
+
+/// Formats a `VK_MAKE_API_VERSION` value, leaving out the variant when it is zero.
+pub fn version_to_string(version: u32) -> String {
+    let variant = version >> 29;
+    let rest = FileFormatVersion::from_api_version(version);
+    if variant == 0 {
+        rest.to_string()
+    } else {
+        format!("{}.{}", variant, rest)
+    }
+}
+
+/// The library a manifest's `library_path` refers to, resolved like `combine_manifest_directory_and_library_path`:
+/// absolute paths are used as they are, paths containing a directory separator are relative to the manifest, and bare
+/// file names are left to the system library search.
+pub fn resolve_library_path(manifest_path: &Path, library_path: &str) -> PathBuf {
+    let library = Path::new(library_path);
+    if library.is_absolute() || !library_path.contains(std::path::MAIN_SEPARATOR) {
+        return library.into();
+    }
+
+    manifest_path
+        .parent()
+        .map_or_else(|| library.into(), |directory| directory.join(library))
+}
+
+/// Whether `library_arch` names a different pointer width than the current process.
+fn library_arch_mismatch(library_arch: &str) -> bool {
+    (library_arch.starts_with("32") && cfg!(not(target_pointer_width = "32")))
+        || (library_arch.starts_with("64") && cfg!(not(target_pointer_width = "64")))
+}
+
+fn read_json(path: &Path, diagnostics: &mut Vec<Diagnostic>) -> Option<Value> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(error) => {
+            push(
+                diagnostics,
+                Severity::Error,
+                format!("Failed to read {}: {}", path.display(), error),
+            );
+            return None;
+        }
+    };
+
+    match serde_json::from_slice(&contents) {
+        Ok(json) => Some(json),
+        Err(error) => {
+            push(
+                diagnostics,
+                Severity::Error,
+                format!("{} is not valid JSON: {}", path.display(), error),
+            );
+            None
+        }
+    }
+}
+
+// The loader reads strings with cJSON_Print, so numbers and such are accepted as long as the field is there.
+fn string_value(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A driver manifest, normally found in an `icd.d` folder.
+#[derive(Debug, Clone)]
+pub struct DriverManifest {
+    pub path: PathBuf,
+    pub file_format_version: Option<FileFormatVersion>,
+    /// `library_path` as written in the manifest.
+    pub library_path: Option<String>,
+    pub api_version: Option<u32>,
+    pub is_portability_driver: bool,
+    pub library_arch: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl DriverManifest {
+    /// Reads and checks the driver manifest at `path`. Problems reading the file end up in the diagnostics.
+    pub fn load(path: impl AsRef<Path>) -> DriverManifest {
+        let path = path.as_ref();
+        let mut diagnostics = Vec::new();
+        match read_json(path, &mut diagnostics) {
+            Some(json) => DriverManifest::parse(path, &json),
+            None => DriverManifest {
+                path: path.into(),
+                file_format_version: None,
+                library_path: None,
+                api_version: None,
+                is_portability_driver: false,
+                library_arch: None,
+                diagnostics,
+            },
+        }
+    }
+
+    /// Checks an already parsed driver manifest. `path` is only used for messages and to resolve relative library
+    /// paths.
+    pub fn parse(path: impl AsRef<Path>, json: &Value) -> DriverManifest {
+        let path = path.as_ref();
+        let file = path.display();
+        let mut manifest = DriverManifest {
+            path: path.into(),
+            file_format_version: None,
+            library_path: None,
+            api_version: None,
+            is_portability_driver: false,
+            library_arch: None,
+            diagnostics: Vec::new(),
+        };
+        let diagnostics = &mut manifest.diagnostics;
+
+        let Some(file_format_version) = json.get("file_format_version") else {
+            push(
+                diagnostics,
+                Severity::Error,
+                format!(
+                    "ICD JSON {} does not have a 'file_format_version' field.",
+                    file
+                ),
+            );
+            return manifest;
+        };
+        let version = FileFormatVersion::from_api_version(parse_version_string(&string_value(
+            file_format_version,
+        )));
+        manifest.file_format_version = Some(version);
+        // The loader knows file format versions 1.0.0 and 1.0.1.
+        if version >= FileFormatVersion::new(1, 0, 2) {
+            push(
+                diagnostics,
+                Severity::Info,
+                format!(
+                    "{} has unknown icd manifest file version {}. May cause errors.",
+                    file, version
+                ),
+            );
+        }
+
+        let Some(icd) = json.get("ICD") else {
+            push(
+                diagnostics,
+                Severity::Error,
+                format!("Can not find 'ICD' object in ICD JSON file {}.", file),
+            );
+            return manifest;
+        };
+
+        match icd.get("library_path").map(string_value) {
+            None => {
+                push(
+                    diagnostics,
+                    Severity::Error,
+                    format!(
+                        "Failed to find 'library_path' object in ICD JSON file {}.",
+                        file
+                    ),
+                );
+                return manifest;
+            }
+            Some(library_path) if library_path.is_empty() => {
+                push(
+                    diagnostics,
+                    Severity::Error,
+                    format!("ICD JSON {} 'library_path' field is empty.", file),
+                );
+                return manifest;
+            }
+            Some(library_path) => manifest.library_path = Some(library_path),
+        }
+
+        let Some(api_version) = icd.get("api_version") else {
+            push(
+                diagnostics,
+                Severity::Error,
+                format!("ICD JSON {} does not have an 'api_version' field.", file),
+            );
+            return manifest;
+        };
+        let api_version = parse_version_string(&string_value(api_version));
+        manifest.api_version = Some(api_version);
+        if api_version >> 29 != 0 {
+            push(
+                diagnostics,
+                Severity::Error,
+                format!("Driver's ICD JSON {} 'api_version' field contains a non-zero variant value of {}.", file, api_version >> 29),
+            );
+            return manifest;
+        }
+
+        manifest.is_portability_driver =
+            icd.get("is_portability_driver") == Some(&Value::Bool(true));
+
+        if let Some(library_arch) = icd.get("library_arch").map(string_value) {
+            if library_arch_mismatch(&library_arch) {
+                push(
+                    diagnostics,
+                    Severity::Error,
+                    "Driver library architecture doesn't match the current running architecture, skipping this driver",
+                );
+            }
+            manifest.library_arch = Some(library_arch);
+        }
+
+        manifest
+    }
+
+    /// Whether the loader would go on to load the driver. Portability drivers additionally need the application to
+    /// enable portability enumeration.
+    pub fn is_usable(&self) -> bool {
+        !has_errors(&self.diagnostics)
+    }
+
+    pub fn resolved_library_path(&self) -> Option<PathBuf> {
+        self.library_path
+            .as_deref()
+            .map(|library_path| resolve_library_path(&self.path, library_path))
+    }
+}
+
+/// An `enable_environment` or `disable_environment` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvironmentVariable {
+    pub name: String,
+    pub value: String,
+}
+
+/// The `functions` object of a layer manifest, renaming the layer's interface functions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayerFunctions {
+    pub negotiate_loader_layer_interface_version: Option<String>,
+    pub get_instance_proc_addr: Option<String>,
+    pub get_device_proc_addr: Option<String>,
+}
+
+/// An entry of `instance_extensions` or `device_extensions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionEntry {
+    pub name: String,
+    pub spec_version: u32,
+}
+
+/// A single layer described by a layer manifest.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub is_implicit: bool,
+    /// `library_path` as written in the manifest. Meta layers have none.
+    pub library_path: Option<String>,
+    pub component_layers: Vec<String>,
+    pub api_version: u32,
+    pub implementation_version: u32,
+    pub description: String,
+    pub functions: LayerFunctions,
+    pub instance_extensions: Vec<ExtensionEntry>,
+    pub device_extensions: Vec<ExtensionEntry>,
+    pub enable_environment: Option<EnvironmentVariable>,
+    pub disable_environment: Option<EnvironmentVariable>,
+    pub override_paths: Vec<String>,
+    pub blacklisted_layers: Vec<String>,
+    pub app_keys: Vec<String>,
+    pub library_arch: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Layer {
+    fn empty(is_implicit: bool) -> Layer {
+        Layer {
+            name: String::new(),
+            is_implicit,
+            library_path: None,
+            component_layers: Vec::new(),
+            api_version: 0,
+            implementation_version: 0,
+            description: String::new(),
+            functions: LayerFunctions::default(),
+            instance_extensions: Vec::new(),
+            device_extensions: Vec::new(),
+            enable_environment: None,
+            disable_environment: None,
+            override_paths: Vec::new(),
+            blacklisted_layers: Vec::new(),
+            app_keys: Vec::new(),
+            library_arch: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn is_meta_layer(&self) -> bool {
+        self.library_path.is_none() && !self.component_layers.is_empty()
+    }
+
+    pub fn is_override_layer(&self) -> bool {
+        self.name == OVERRIDE_LAYER_NAME
+    }
+
+    /// Whether the loader keeps the layer after reading its manifest.
+    pub fn is_usable(&self) -> bool {
+        !has_errors(&self.diagnostics)
+    }
+
+    /// The name of the function the loader looks up to negotiate the interface version.
+    pub fn negotiate_function_name(&self) -> &str {
+        self.functions
+            .negotiate_loader_layer_interface_version
+            .as_deref()
+            .unwrap_or("vkNegotiateLoaderLayerInterfaceVersion")
+    }
+
+    /// The name of the function the loader falls back to when the layer does not negotiate.
+    pub fn get_instance_proc_addr_name(&self) -> &str {
+        self.functions
+            .get_instance_proc_addr
+            .as_deref()
+            .unwrap_or("vkGetInstanceProcAddr")
+    }
+
+    /// The name of the function the loader looks up when negotiation didn't provide a `vkGetDeviceProcAddr`.
+    pub fn get_device_proc_addr_name(&self) -> &str {
+        self.functions
+            .get_device_proc_addr
+            .as_deref()
+            .unwrap_or("vkGetDeviceProcAddr")
+    }
+}
+
+fn string_array(node: &Value, key: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<Vec<String>> {
+    let value = node.get(key)?;
+    match value.as_array() {
+        Some(array) => Some(array.iter().map(string_value).collect()),
+        None => {
+            push(
+                diagnostics,
+                Severity::Warning,
+                format!("'{}' is not an array and is ignored.", key),
+            );
+            Some(Vec::new())
+        }
+    }
+}
+
+fn environment_variable(object: &Value) -> Option<EnvironmentVariable> {
+    // The loader only looks at the first member of the object.
+    let (name, value) = object.as_object()?.iter().next()?;
+    Some(EnvironmentVariable {
+        name: name.clone(),
+        value: value.as_str()?.into(),
+    })
+}
+
+fn extensions(node: &Value, key: &str, require_name: bool) -> Vec<ExtensionEntry> {
+    node.get(key)
+        .and_then(Value::as_array)
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(|extension| {
+                    let name = extension.get("name").map(string_value);
+                    if name.is_none() && require_name {
+                        return None;
+                    }
+                    Some(ExtensionEntry {
+                        name: name.unwrap_or_default(),
+                        spec_version: extension
+                            .get("spec_version")
+                            .map_or(0, |version| atoi(&string_value(version))),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_layer(node: &Value, version: FileFormatVersion, is_implicit: bool, file: &str) -> Layer {
+    let mut layer = Layer::empty(is_implicit);
+    let diagnostics = &mut layer.diagnostics;
+
+    let Some(name) = node.get("name").map(string_value) else {
+        push(diagnostics, Severity::Error, format!("Layer located at {} didn't find required layer value \"name\" in manifest JSON file.", file));
+        return layer;
+    };
+    if name.len() >= MAX_STRING_SIZE {
+        push(
+            diagnostics,
+            Severity::Warning,
+            format!(
+                "Layer name is longer than {} bytes and gets truncated.",
+                MAX_STRING_SIZE - 1
+            ),
+        );
+    }
+    if !name.starts_with("VK_LAYER_") {
+        push(
+            diagnostics,
+            Severity::Warning,
+            format!(
+                "Layer name {} does not conform to naming standard (Policy #LLP_LAYER_3)",
+                name
+            ),
+        );
+    }
+    let is_override = name == OVERRIDE_LAYER_NAME;
+    layer.name = name;
+
+    let Some(layer_type) = node.get("type").map(string_value) else {
+        push(diagnostics, Severity::Error, format!("Layer located at {} didn't find required layer value \"type\" in manifest JSON file.", file));
+        return layer;
+    };
+    match layer_type.as_str() {
+        "INSTANCE" | "GLOBAL" => {}
+        "DEVICE" => {
+            push(
+                diagnostics,
+                Severity::Error,
+                "Device layers are deprecated. Skipping this layer",
+            );
+            return layer;
+        }
+        other => {
+            push(
+                diagnostics,
+                Severity::Error,
+                format!(
+                    "Unknown layer type \"{}\", expected \"INSTANCE\" or \"GLOBAL\".",
+                    other
+                ),
+            );
+            return layer;
+        }
+    }
+
+    let Some(api_version) = node.get("api_version").map(string_value) else {
+        push(diagnostics, Severity::Error, format!("Layer located at {} didn't find required layer value \"api_version\" in manifest JSON file.", file));
+        return layer;
+    };
+    layer.api_version = parse_version_string(&api_version);
+    if layer.api_version >> 29 != 0 {
+        push(
+            diagnostics,
+            Severity::Error,
+            format!("Layer \"{}\" has an 'api_version' field which contains a non-zero variant value of {}.", layer.name, layer.api_version >> 29),
+        );
+        return layer;
+    }
+
+    let Some(implementation_version) = node.get("implementation_version").map(string_value) else {
+        push(diagnostics, Severity::Error, format!("Layer located at {} didn't find required layer value \"implementation_version\" in manifest JSON file.", file));
+        return layer;
+    };
+    layer.implementation_version = atoi(&implementation_version);
+
+    let Some(description) = node.get("description").map(string_value) else {
+        push(diagnostics, Severity::Error, format!("Layer located at {} didn't find required layer value \"description\" in manifest JSON file.", file));
+        return layer;
+    };
+    layer.description = description;
+
+    if let Some(library_path) = node.get("library_path") {
+        if node.get("component_layers").is_some() {
+            push(
+                diagnostics,
+                Severity::Error,
+                "Indicating meta-layer-specific component_layers, but also defining layer library path.  Both are not compatible, so skipping this layer",
+            );
+            return layer;
+        }
+        layer.library_path = Some(string_value(library_path));
+    } else {
+        if version < FileFormatVersion::new(1, 1, 0) {
+            push(diagnostics, Severity::Warning, "Indicating meta-layer-specific component_layers, but using older JSON file version.");
+        }
+        match string_array(node, "component_layers", diagnostics) {
+            Some(component_layers) => layer.component_layers = component_layers,
+            None => {
+                push(
+                    diagnostics,
+                    Severity::Error,
+                    "Layer missing both library_path and component_layers fields.  One or the other MUST be defined.  Skipping this layer",
+                );
+                return layer;
+            }
+        }
+    }
+
+    if is_override {
+        layer.blacklisted_layers =
+            string_array(node, "blacklisted_layers", diagnostics).unwrap_or_default();
+    }
+
+    layer.override_paths = string_array(node, "override_paths", diagnostics).unwrap_or_default();
+    if !layer.override_paths.is_empty() && version < FileFormatVersion::new(1, 1, 0) {
+        push(
+            diagnostics,
+            Severity::Warning,
+            "Indicating meta-layer-specific override paths, but using older JSON file version.",
+        );
+    }
+
+    if is_implicit {
+        match node.get("disable_environment") {
+            None => {
+                push(diagnostics, Severity::Error, "Didn't find required layer object disable_environment in manifest JSON file, skipping this layer");
+                return layer;
+            }
+            Some(disable_environment) => match environment_variable(disable_environment) {
+                Some(variable) => layer.disable_environment = Some(variable),
+                None => {
+                    push(
+                        diagnostics,
+                        Severity::Error,
+                        "Didn't find required layer child value disable_environment in manifest JSON file, skipping this layer (Policy #LLP_LAYER_9)",
+                    );
+                    return layer;
+                }
+            },
+        }
+    }
+
+    if let Some(functions) = node.get("functions") {
+        let function = |key: &str| functions.get(key).map(string_value);
+        if version >= FileFormatVersion::new(1, 1, 0) {
+            layer.functions.negotiate_loader_layer_interface_version =
+                function("vkNegotiateLoaderLayerInterfaceVersion");
+        }
+        layer.functions.get_instance_proc_addr = function("vkGetInstanceProcAddr");
+        layer.functions.get_device_proc_addr = function("vkGetDeviceProcAddr");
+
+        let deprecated = version >= FileFormatVersion::new(1, 1, 0);
+        for (tag, present) in [
+            (
+                "vkGetInstanceProcAddr",
+                layer.functions.get_instance_proc_addr.is_some(),
+            ),
+            (
+                "vkGetDeviceProcAddr",
+                layer.functions.get_device_proc_addr.is_some(),
+            ),
+        ] {
+            if deprecated && present {
+                push(
+                    diagnostics,
+                    Severity::Info,
+                    format!("Layer \"{}\" using deprecated '{}' tag which was deprecated starting with JSON file version 1.1.0.", layer.name, tag),
+                );
+            }
+        }
+    }
+
+    layer.instance_extensions = extensions(node, "instance_extensions", true);
+    layer.device_extensions = extensions(node, "device_extensions", false);
+
+    if is_implicit {
+        layer.enable_environment = node
+            .get("enable_environment")
+            .and_then(environment_variable);
+    }
+
+    if node.get("pre_instance_functions").is_some() {
+        if version < FileFormatVersion::new(1, 1, 2) {
+            push(
+                diagnostics,
+                Severity::Warning,
+                "Found pre_instance_functions section. This section is only valid in manifest version 1.1.2 or later. The section will be ignored",
+            );
+        } else if !is_implicit {
+            push(
+                diagnostics,
+                Severity::Warning,
+                "Found pre_instance_functions section in explicit layer. This section is only valid in implicit layers. The section will be ignored",
+            );
+        }
+    }
+
+    if let Some(app_keys) = string_array(node, "app_keys", diagnostics) {
+        if !is_override {
+            push(
+                diagnostics,
+                Severity::Warning,
+                format!("Layer {} contains app_keys, but any app_keys can only be provided by the override metalayer. These will be ignored.", layer.name),
+            );
+        }
+        layer.app_keys = app_keys;
+    }
+
+    if let Some(library_arch) = node.get("library_arch").map(string_value) {
+        if library_arch_mismatch(&library_arch) {
+            push(
+                diagnostics,
+                Severity::Error,
+                "Layer library architecture doesn't match the current running architecture, skipping this layer",
+            );
+        }
+        layer.library_arch = Some(library_arch);
+    }
+
+    layer
+}
+
+/// The layer file format versions the loader knows: 1.0.0 - 1.0.1, 1.1.0 - 1.1.2 and 1.2.0 - 1.2.1.
+pub fn is_known_layer_file_format_version(version: FileFormatVersion) -> bool {
+    matches!(
+        (version.major, version.minor, version.patch),
+        (1, 0, 0..=1) | (1, 1, 0..=2) | (1, 2, 0..=1)
+    )
+}
+
+/// A layer manifest, which describes one or more layers.
+#[derive(Debug, Clone)]
+pub struct LayerManifest {
+    pub path: PathBuf,
+    pub is_implicit: bool,
+    pub file_format_version: Option<FileFormatVersion>,
+    pub layers: Vec<Layer>,
+    /// Problems with the file as a whole. Problems with a single layer are recorded on that layer.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LayerManifest {
+    /// Reads and checks the layer manifest at `path`. Problems reading the file end up in the diagnostics.
+    pub fn load(path: impl AsRef<Path>, is_implicit: bool) -> LayerManifest {
+        let path = path.as_ref();
+        let mut diagnostics = Vec::new();
+        match read_json(path, &mut diagnostics) {
+            Some(json) => LayerManifest::parse(path, &json, is_implicit),
+            None => LayerManifest {
+                path: path.into(),
+                is_implicit,
+                file_format_version: None,
+                layers: Vec::new(),
+                diagnostics,
+            },
+        }
+    }
+
+    /// Checks an already parsed layer manifest. `path` is only used for messages and to resolve relative library paths.
+    pub fn parse(path: impl AsRef<Path>, json: &Value, is_implicit: bool) -> LayerManifest {
+        let path = path.as_ref();
+        let file = path.display().to_string();
+        let mut manifest = LayerManifest {
+            path: path.into(),
+            is_implicit,
+            file_format_version: None,
+            layers: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+
+        let Some(object) = json.as_object() else {
+            push(
+                &mut manifest.diagnostics,
+                Severity::Error,
+                format!("The top level of {} is not an object.", file),
+            );
+            return manifest;
+        };
+
+        let Some(file_format_version) = object.get("file_format_version") else {
+            push(
+                &mut manifest.diagnostics,
+                Severity::Error,
+                format!("{} does not have a 'file_format_version' field.", file),
+            );
+            return manifest;
+        };
+        let version = FileFormatVersion::from_api_version(parse_version_string(&string_value(
+            file_format_version,
+        )));
+        manifest.file_format_version = Some(version);
+        if !is_known_layer_file_format_version(version) {
+            push(
+                &mut manifest.diagnostics,
+                Severity::Info,
+                format!(
+                    "{} has unknown layer manifest file version {}.  May cause errors.",
+                    file, version
+                ),
+            );
+        }
+
+        manifest.layers =
+            LayerManifest::layer_nodes(object, version, &file, &mut manifest.diagnostics)
+                .into_iter()
+                .map(|node| read_layer(node, version, is_implicit, &file))
+                .collect();
+
+        manifest
+    }
+
+    fn layer_nodes<'a>(
+        object: &'a Map<String, Value>,
+        version: FileFormatVersion,
+        file: &str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Vec<&'a Value> {
+        if let Some(layers) = object.get("layers") {
+            if version < FileFormatVersion::new(1, 0, 1) {
+                push(
+                    diagnostics,
+                    Severity::Warning,
+                    format!("'layers' tag not supported until file version 1.0.1, but {} is reporting version {}", file, version),
+                );
+            }
+            return match layers.as_array() {
+                Some(layers) => layers.iter().collect(),
+                None => {
+                    push(
+                        diagnostics,
+                        Severity::Error,
+                        format!("'layers' in {} is not an array.", file),
+                    );
+                    Vec::new()
+                }
+            };
+        }
+
+        match object.get("layer") {
+            Some(layer) => vec![layer],
+            None => {
+                push(diagnostics, Severity::Error, format!("Can not find 'layer' object in manifest JSON file {}.  Skipping this file.", file));
+                Vec::new()
+            }
+        }
+    }
+
+    /// Whether the file itself is readable. Individual layers may still be skipped.
+    pub fn is_usable(&self) -> bool {
+        !has_errors(&self.diagnostics)
+    }
+}
+
+impl Layer {
+    /// The library the layer's `library_path` refers to, relative to the manifest at `manifest_path`.
+    pub fn resolved_library_path(&self, manifest_path: &Path) -> Option<PathBuf> {
+        self.library_path
+            .as_deref()
+            .map(|library_path| resolve_library_path(manifest_path, library_path))
+    }
+}
+
+/// The JSON type a manifest field is expected to have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    String,
+    /// A `major.minor.patch` string.
+    Version,
+    Bool,
+    Object,
+    Array,
+    /// An array of strings.
+    Strings,
+}
+
+impl FieldKind {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            FieldKind::String => value.is_string(),
+            FieldKind::Version => value.as_str().is_some_and(|version| {
+                let parts = version.split('.').collect::<Vec<_>>();
+                parts.len() == 3
+                    && parts
+                        .iter()
+                        .all(|part| !part.is_empty() && part.bytes().all(|c| c.is_ascii_digit()))
+            }),
+            FieldKind::Bool => value.is_boolean(),
+            FieldKind::Object => value.is_object(),
+            FieldKind::Array => value.is_array(),
+            FieldKind::Strings => value
+                .as_array()
+                .is_some_and(|array| array.iter().all(Value::is_string)),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            FieldKind::String => "a string",
+            FieldKind::Version => "a \"major.minor.patch\" string",
+            FieldKind::Bool => "a boolean",
+            FieldKind::Object => "an object",
+            FieldKind::Array => "an array",
+            FieldKind::Strings => "an array of strings",
+        }
+    }
+}
+
+/// A field of the manifest schema and the file format version that introduced it.
+struct SchemaField {
+    name: &'static str,
+    kind: FieldKind,
+    since: FileFormatVersion,
+}
+
+const fn field(name: &'static str, kind: FieldKind, since: (u32, u32, u32)) -> SchemaField {
+    SchemaField {
+        name,
+        kind,
+        since: FileFormatVersion::new(since.0, since.1, since.2),
+    }
+}
+
+const DRIVER_FIELDS: &[SchemaField] = &[
+    field("file_format_version", FieldKind::Version, (1, 0, 0)),
+    field("ICD", FieldKind::Object, (1, 0, 0)),
+];
+
+const DRIVER_ICD_FIELDS: &[SchemaField] = &[
+    field("library_path", FieldKind::String, (1, 0, 0)),
+    field("api_version", FieldKind::Version, (1, 0, 0)),
+    field("is_portability_driver", FieldKind::Bool, (1, 0, 1)),
+    field("library_arch", FieldKind::String, (1, 0, 1)),
+];
+
+const LAYER_FIELDS: &[SchemaField] = &[
+    field("file_format_version", FieldKind::Version, (1, 0, 0)),
+    field("layer", FieldKind::Object, (1, 0, 0)),
+    // 1.0.1, but the loader already warns about that.
+    field("layers", FieldKind::Array, (1, 0, 0)),
+];
+
+// From docs/LoaderLayerInterface.md. The fields added in 1.2.0 are read by vkconfig rather than by the loader. Fields
+// whose version the loader already complains about, such as `component_layers`, are listed as 1.0.0 so they are not
+// reported twice.
+const LAYER_LAYER_FIELDS: &[SchemaField] = &[
+    field("name", FieldKind::String, (1, 0, 0)),
+    field("type", FieldKind::String, (1, 0, 0)),
+    field("library_path", FieldKind::String, (1, 0, 0)),
+    field("api_version", FieldKind::Version, (1, 0, 0)),
+    field("implementation_version", FieldKind::String, (1, 0, 0)),
+    field("description", FieldKind::String, (1, 0, 0)),
+    field("functions", FieldKind::Object, (1, 0, 0)),
+    field("instance_extensions", FieldKind::Array, (1, 0, 0)),
+    field("device_extensions", FieldKind::Array, (1, 0, 0)),
+    field("enable_environment", FieldKind::Object, (1, 0, 0)),
+    field("disable_environment", FieldKind::Object, (1, 0, 0)),
+    field("component_layers", FieldKind::Strings, (1, 0, 0)),
+    field("override_paths", FieldKind::Strings, (1, 0, 0)),
+    field("blacklisted_layers", FieldKind::Strings, (1, 1, 1)),
+    field("app_keys", FieldKind::Strings, (1, 1, 1)),
+    field("pre_instance_functions", FieldKind::Object, (1, 0, 0)),
+    field("introduction", FieldKind::String, (1, 2, 0)),
+    field("url", FieldKind::String, (1, 2, 0)),
+    field("platforms", FieldKind::Strings, (1, 2, 0)),
+    field("status", FieldKind::String, (1, 2, 0)),
+    field("settings", FieldKind::Array, (1, 2, 0)),
+    field("presets", FieldKind::Array, (1, 2, 0)),
+    field("library_arch", FieldKind::String, (1, 2, 1)),
+];
+
+fn check_fields(
+    object: &Map<String, Value>,
+    schema: &[SchemaField],
+    version: FileFormatVersion,
+    context: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (name, value) in object {
+        let Some(field) = schema.iter().find(|field| field.name == name) else {
+            push(
+                diagnostics,
+                Severity::Warning,
+                format!("{}: unknown field '{}' is ignored.", context, name),
+            );
+            continue;
+        };
+        if version < field.since {
+            push(
+                diagnostics,
+                Severity::Warning,
+                format!(
+                    "{}: '{}' needs file_format_version {} or later, but the file declares {}.",
+                    context, name, field.since, version
+                ),
+            );
+        }
+        if !field.kind.matches(value) {
+            push(
+                diagnostics,
+                Severity::Warning,
+                format!(
+                    "{}: '{}' should be {}, found {}.",
+                    context,
+                    name,
+                    field.kind.describe(),
+                    value
+                ),
+            );
+        }
+    }
+}
+
+fn check_library_arch(
+    object: &Map<String, Value>,
+    context: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if let Some(library_arch) = object.get("library_arch") {
+        if library_arch != "32" && library_arch != "64" {
+            push(
+                diagnostics,
+                Severity::Warning,
+                format!(
+                    "{}: 'library_arch' should be \"32\" or \"64\", found {}.",
+                    context, library_arch
+                ),
+            );
+        }
+    }
+}
+
+fn schema_version(object: &Map<String, Value>) -> FileFormatVersion {
+    object
+        .get("file_format_version")
+        .map_or(FileFormatVersion::default(), |version| {
+            FileFormatVersion::from_api_version(parse_version_string(&string_value(version)))
+        })
+}
+
+/// Checks a driver manifest against the schema of its `file_format_version`: unknown fields, fields that the declared
+/// version does not have yet and fields of the wrong JSON type. The loader silently ignores all of these, so unlike
+/// the diagnostics of [`DriverManifest::parse`] they are always warnings.
+pub fn check_driver_schema(json: &Value) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(object) = json.as_object() else {
+        return diagnostics;
+    };
+    let version = schema_version(object);
+
+    check_fields(
+        object,
+        DRIVER_FIELDS,
+        version,
+        "top level",
+        &mut diagnostics,
+    );
+    if let Some(icd) = object.get("ICD").and_then(Value::as_object) {
+        check_fields(icd, DRIVER_ICD_FIELDS, version, "ICD", &mut diagnostics);
+        check_library_arch(icd, "ICD", &mut diagnostics);
+    }
+
+    diagnostics
+}
+
+/// Checks a layer manifest against the schema of its `file_format_version`, like [`check_driver_schema`]. Also points
+/// out fields that only mean something for the other kind of layer, such as `disable_environment` in an explicit layer.
+pub fn check_layer_schema(json: &Value, is_implicit: bool) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(object) = json.as_object() else {
+        return diagnostics;
+    };
+    let version = schema_version(object);
+
+    check_fields(object, LAYER_FIELDS, version, "top level", &mut diagnostics);
+    if object.contains_key("layer") && object.contains_key("layers") {
+        push(
+            &mut diagnostics,
+            Severity::Warning,
+            "top level: both 'layer' and 'layers' are present, 'layer' is ignored.",
+        );
+    }
+
+    let layers = match (object.get("layers"), object.get("layer")) {
+        (Some(Value::Array(layers)), _) => layers.iter().collect(),
+        (None, Some(layer)) => vec![layer],
+        _ => Vec::new(),
+    };
+    for (index, layer) in layers.into_iter().enumerate() {
+        let Some(layer) = layer.as_object() else {
+            continue;
+        };
+        let context = match layer.get("name") {
+            Some(name) => format!("layer {}", string_value(name)),
+            None => format!("layer #{}", index),
+        };
+        check_fields(
+            layer,
+            LAYER_LAYER_FIELDS,
+            version,
+            &context,
+            &mut diagnostics,
+        );
+        check_library_arch(layer, &context, &mut diagnostics);
+
+        if !is_implicit {
+            for name in [
+                "enable_environment",
+                "disable_environment",
+                "pre_instance_functions",
+            ] {
+                if layer.contains_key(name) {
+                    push(
+                        &mut diagnostics,
+                        Severity::Warning,
+                        format!("{}: '{}' is only used by implicit layers.", context, name),
+                    );
+                }
+            }
+        }
+        let is_meta_layer = layer.contains_key("component_layers");
+        if !is_meta_layer && layer.contains_key("override_paths") {
+            push(
+                &mut diagnostics,
+                Severity::Warning,
+                format!("{}: 'override_paths' is only used by meta-layers.", context),
+            );
+        }
+        let is_override = layer.get("name").and_then(Value::as_str) == Some(OVERRIDE_LAYER_NAME);
+        // Stray app_keys are already reported by read_layer.
+        if !is_override && layer.contains_key("blacklisted_layers") {
+            push(
+                &mut diagnostics,
+                Severity::Warning,
+                format!(
+                    "{}: 'blacklisted_layers' is only used by {}.",
+                    context, OVERRIDE_LAYER_NAME
+                ),
+            );
+        }
+        for key in ["instance_extensions", "device_extensions"] {
+            let entries = layer
+                .get(key)
+                .and_then(Value::as_array)
+                .map_or(&[][..], Vec::as_slice);
+            for entry in entries {
+                if entry.get("name").is_none() || entry.get("spec_version").is_none() {
+                    push(
+                        &mut diagnostics,
+                        Severity::Warning,
+                        format!(
+                            "{}: every entry of '{}' needs a 'name' and a 'spec_version'.",
+                            context, key
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
@@ -0,0 +1,30 @@
+//! Where the linked loader searches for driver and layer manifests on Linux and the BSDs, as it was configured when
+//! this crate was built.
+//!
+//! Each value comes from the CMake cache variable of the same name, which the matching `VULKAN_LOADER_SYS_*`
+//! environment variable overrides at build time. For a loader taken from `VULKAN_LOADER_SYS_LIB_DIR`, the same
+//! variables describe how that loader was built.
+
+/// Searched in place of `XDG_CONFIG_DIRS` when that is unset or empty, as a colon separated list.
+pub const FALLBACK_CONFIG_DIRS: &str = include!(concat!(
+    env!("OUT_DIR"),
+    "/search_paths/fallback_config_dirs.rs"
+));
+
+/// Searched in place of `XDG_DATA_DIRS` when that is unset or empty, as a colon separated list.
+pub const FALLBACK_DATA_DIRS: &str = include!(concat!(
+    env!("OUT_DIR"),
+    "/search_paths/fallback_data_dirs.rs"
+));
+
+/// The system configuration directories, in the order they are searched. Either `SYSCONFDIR`, or the install prefix's
+/// `etc` directory followed by `/etc`.
+pub const SYSCONF_DIRS: &[&str] =
+    &include!(concat!(env!("OUT_DIR"), "/search_paths/sysconf_dirs.rs"));
+
+/// Whether the loader was built with `LOADER_USE_UNSAFE_FILE_SEARCH`, and so keeps using the environment variables that
+/// add search paths when the process runs with elevated privileges.
+pub const UNSAFE_FILE_SEARCH: bool = include!(concat!(
+    env!("OUT_DIR"),
+    "/search_paths/unsafe_file_search.rs"
+));
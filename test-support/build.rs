@@ -0,0 +1,8 @@
+// vulkan-loader-sys passes on whether its loader has the log hook that `FrameworkSettings::log_filter` relies on.
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(loader_log_hook)");
+    println!("cargo:rerun-if-env-changed=DEP_VULKAN_LOG_HOOK");
+    if std::env::var_os("DEP_VULKAN_LOG_HOOK").is_some() {
+        println!("cargo:rustc-cfg=loader_log_hook");
+    }
+}
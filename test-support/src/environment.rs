@@ -0,0 +1,589 @@
+//! [`FrameworkEnvironment`], the Rust counterpart of the C++ test framework's class of the same name.
+
+use std::{
+    env,
+    ffi::{CStr, OsStr, OsString},
+    fs,
+    os::raw::c_char,
+    path::{Path, PathBuf},
+    process,
+    ptr::{null, null_mut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use vulkan_loader_sys::{loader_log, *};
+
+use crate::manifest::{IcdManifest, LayerManifest};
+
+// Every variable the loader consults while looking for drivers and layers, or while deciding which of them to use.
+// All of them are cleared or overridden for as long as an environment exists.
+const LOADER_ENVIRONMENT_VARIABLES: &[&str] = &[
+    "VK_DRIVER_FILES",
+    "VK_ICD_FILENAMES",
+    "VK_ADD_DRIVER_FILES",
+    "VK_LAYER_PATH",
+    "VK_ADD_LAYER_PATH",
+    "VK_INSTANCE_LAYERS",
+    "VK_LOADER_LAYERS_ENABLE",
+    "VK_LOADER_LAYERS_DISABLE",
+    "VK_LOADER_LAYERS_ALLOW",
+    "VK_LOADER_DRIVERS_SELECT",
+    "VK_LOADER_DRIVERS_DISABLE",
+    "VK_LOADER_DEVICE_SELECT",
+    "VK_LOADER_DISABLE_SELECT",
+    "VK_LOADER_DISABLE_INST_EXT_FILTER",
+    "HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_CONFIG_DIRS",
+    "XDG_DATA_HOME",
+    "XDG_DATA_DIRS",
+];
+
+// The process environment is shared by every test in a binary, so only one environment can exist at a time.
+static ENVIRONMENT_LOCK: Mutex<()> = Mutex::new(());
+
+static NEXT_DIRECTORY: AtomicUsize = AtomicUsize::new(0);
+
+/// Options for a [`FrameworkEnvironment`].
+#[derive(Debug, Clone, Default)]
+pub struct FrameworkSettings {
+    /// A `VK_LOADER_DEBUG` style filter applied to the loader's own output for the lifetime of the environment, such as
+    /// `"all"` or `"error,warn"`. Left alone when `None`. Needs a loader with the log hook, which one taken from
+    /// `VULKAN_LOADER_SYS_LIB_DIR` usually lacks.
+    pub log_filter: Option<String>,
+    /// Keeps the temporary directory around after the environment is dropped, which helps when debugging a test.
+    pub keep_directory: bool,
+}
+
+impl FrameworkSettings {
+    pub fn log_filter(mut self, log_filter: impl Into<String>) -> FrameworkSettings {
+        self.log_filter = Some(log_filter.into());
+        self
+    }
+
+    pub fn keep_directory(mut self, keep_directory: bool) -> FrameworkSettings {
+        self.keep_directory = keep_directory;
+        self
+    }
+}
+
+/// Where a manifest is placed, which decides how the loader finds it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ManifestDiscoveryType {
+    /// The regular location: `VK_DRIVER_FILES` for drivers, `VK_LAYER_PATH` for explicit layers and the XDG data home
+    /// for implicit layers.
+    #[default]
+    Generic,
+    /// A folder listed in `VK_ADD_DRIVER_FILES` or `VK_ADD_LAYER_PATH`.
+    AddEnvVar,
+    /// A folder the loader does not search, for drivers that are handed over through direct driver loading.
+    None,
+}
+
+/// A driver to add to a [`FrameworkEnvironment`].
+#[derive(Debug, Clone)]
+pub struct TestIcdDetails {
+    pub manifest: IcdManifest,
+    pub json_name: String,
+    pub discovery_type: ManifestDiscoveryType,
+    /// Writes the manifest exactly as given instead of copying the library into the environment first.
+    pub is_fake: bool,
+}
+
+impl TestIcdDetails {
+    pub fn new(library_path: impl AsRef<Path>) -> TestIcdDetails {
+        TestIcdDetails::from_manifest(IcdManifest::new(library_path))
+    }
+
+    pub fn from_manifest(manifest: IcdManifest) -> TestIcdDetails {
+        TestIcdDetails {
+            manifest,
+            json_name: "test_icd".into(),
+            discovery_type: ManifestDiscoveryType::Generic,
+            is_fake: false,
+        }
+    }
+
+    pub fn api_version(mut self, api_version: u32) -> TestIcdDetails {
+        self.manifest.api_version = api_version;
+        self
+    }
+
+    pub fn json_name(mut self, json_name: impl Into<String>) -> TestIcdDetails {
+        self.json_name = json_name.into();
+        self
+    }
+
+    pub fn discovery_type(mut self, discovery_type: ManifestDiscoveryType) -> TestIcdDetails {
+        self.discovery_type = discovery_type;
+        self
+    }
+
+    pub fn is_fake(mut self, is_fake: bool) -> TestIcdDetails {
+        self.is_fake = is_fake;
+        self
+    }
+}
+
+/// A layer to add to a [`FrameworkEnvironment`].
+#[derive(Debug, Clone)]
+pub struct TestLayerDetails {
+    pub manifest: LayerManifest,
+    pub json_name: String,
+    pub discovery_type: ManifestDiscoveryType,
+    /// Writes the manifest exactly as given instead of copying the library into the environment first.
+    pub is_fake: bool,
+}
+
+impl TestLayerDetails {
+    pub fn new(manifest: LayerManifest, json_name: impl Into<String>) -> TestLayerDetails {
+        TestLayerDetails {
+            manifest,
+            json_name: json_name.into(),
+            discovery_type: ManifestDiscoveryType::Generic,
+            is_fake: false,
+        }
+    }
+
+    pub fn discovery_type(mut self, discovery_type: ManifestDiscoveryType) -> TestLayerDetails {
+        self.discovery_type = discovery_type;
+        self
+    }
+
+    pub fn is_fake(mut self, is_fake: bool) -> TestLayerDetails {
+        self.is_fake = is_fake;
+        self
+    }
+}
+
+/// A driver or layer that was added to an environment.
+#[derive(Debug, Clone)]
+pub struct InstalledManifest {
+    /// Where the manifest was written.
+    pub manifest_path: PathBuf,
+    /// The library the manifest points at, after it was copied into the environment.
+    pub library_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Folder {
+    Drivers,
+    AddDrivers,
+    ExplicitLayers,
+    AddExplicitLayers,
+    ImplicitLayers,
+    Null,
+    Home,
+    ConfigHome,
+    ConfigDirs,
+    DataHome,
+    DataDirs,
+    SysConf,
+}
+
+impl Folder {
+    fn relative_path(self) -> &'static str {
+        match self {
+            Folder::Drivers => "drivers",
+            Folder::AddDrivers => "add_drivers",
+            Folder::ExplicitLayers => "explicit_layers",
+            Folder::AddExplicitLayers => "add_explicit_layers",
+            Folder::ImplicitLayers => "data_home/vulkan/implicit_layer.d",
+            Folder::Null => "null",
+            Folder::Home => "home",
+            Folder::ConfigHome => "config_home",
+            Folder::ConfigDirs => "config_dirs",
+            Folder::DataHome => "data_home",
+            Folder::DataDirs => "data_dirs",
+            Folder::SysConf => "sysconf",
+        }
+    }
+}
+
+/// A hermetic set of drivers and layers for the loader to find.
+///
+/// The environment owns a temporary directory holding the manifests and copies of the libraries they refer to, and
+/// points the loader's search paths at it: `VK_DRIVER_FILES` and `VK_LAYER_PATH` select its driver and explicit layer
+/// folders, while `HOME` and the `XDG_*` variables move the per-user implicit layer folders inside it. Every other
+/// variable that changes driver or layer selection is cleared. The previous values come back when the environment is
+/// dropped.
+///
+/// The C++ framework intercepts the loader's file system calls with a shim library to hide the implicit layers
+/// installed system wide under `/etc/vulkan`. Instead, when `vulkan-loader-sys` builds the loader with its
+/// `loader-tests` feature, the loader's `SYSCONFDIR` is a directory of its own that the environment links to an empty
+/// folder inside it. A loader built with `VULKAN_LOADER_SYS_SYSCONFDIR` or without the feature still finds the layers
+/// under `/etc/vulkan`.
+///
+/// As the environment is process wide, creating a second `FrameworkEnvironment` blocks until the first one is gone.
+/// Environment variables that matter to a test, such as a layer's `enable_environment`, should be set through
+/// [`FrameworkEnvironment::set_env`] so that they are restored as well.
+pub struct FrameworkEnvironment {
+    root: PathBuf,
+    settings: FrameworkSettings,
+    icds: Vec<InstalledManifest>,
+    layers: Vec<InstalledManifest>,
+    // The value each variable had before the environment touched it.
+    saved_variables: Vec<(OsString, Option<OsString>)>,
+    saved_debug_level: Option<u32>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl FrameworkEnvironment {
+    pub fn new() -> FrameworkEnvironment {
+        FrameworkEnvironment::with_settings(FrameworkSettings::default())
+    }
+
+    pub fn with_settings(settings: FrameworkSettings) -> FrameworkEnvironment {
+        let lock = ENVIRONMENT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let root = env::temp_dir().join(format!(
+            "vulkan-loader-sys-{}-{}",
+            process::id(),
+            NEXT_DIRECTORY.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut environment = FrameworkEnvironment {
+            root,
+            settings,
+            icds: Vec::new(),
+            layers: Vec::new(),
+            saved_variables: Vec::new(),
+            saved_debug_level: None,
+            _lock: lock,
+        };
+
+        // A directory left behind by an earlier process with the same id would leak its manifests into this one.
+        let _ = fs::remove_dir_all(&environment.root);
+        for folder in [
+            Folder::Drivers,
+            Folder::AddDrivers,
+            Folder::ExplicitLayers,
+            Folder::AddExplicitLayers,
+            Folder::ImplicitLayers,
+            Folder::Null,
+            Folder::Home,
+            Folder::ConfigHome,
+            Folder::ConfigDirs,
+            Folder::DataHome,
+            Folder::DataDirs,
+            Folder::SysConf,
+        ] {
+            fs::create_dir_all(environment.folder(folder)).unwrap_or_else(|error| {
+                panic!(
+                    "Failed to create {}: {}",
+                    environment.folder(folder).display(),
+                    error
+                )
+            });
+        }
+
+        for variable in LOADER_ENVIRONMENT_VARIABLES {
+            environment.remove_env(variable);
+        }
+        environment.set_env("VK_DRIVER_FILES", environment.folder(Folder::Drivers));
+        environment.set_env("VK_LAYER_PATH", environment.folder(Folder::ExplicitLayers));
+        environment.set_env("HOME", environment.folder(Folder::Home));
+        environment.set_env("XDG_CONFIG_HOME", environment.folder(Folder::ConfigHome));
+        environment.set_env("XDG_CONFIG_DIRS", environment.folder(Folder::ConfigDirs));
+        environment.set_env("XDG_DATA_HOME", environment.folder(Folder::DataHome));
+        environment.set_env("XDG_DATA_DIRS", environment.folder(Folder::DataDirs));
+        environment.link_sysconfdir();
+
+        if let Some(log_filter) = &environment.settings.log_filter {
+            let debug_level = loader_log::parse_debug_level(log_filter);
+            environment.saved_debug_level = Some(replace_debug_level(debug_level));
+        }
+
+        environment
+    }
+
+    fn folder(&self, folder: Folder) -> PathBuf {
+        self.root.join(folder.relative_path())
+    }
+
+    fn link_sysconfdir(&self) {
+        #[cfg(unix)]
+        if let Some(sysconfdir) = vulkan_loader_sys::LOADER_TEST_SYSCONFDIR {
+            // Whatever an earlier environment, possibly of a process that crashed, left behind.
+            let _ = fs::remove_file(sysconfdir);
+            let _ = fs::remove_dir_all(sysconfdir);
+            let folder = self.folder(Folder::SysConf);
+            if let Err(error) = std::os::unix::fs::symlink(&folder, sysconfdir) {
+                panic!(
+                    "Failed to link {} to {}: {}",
+                    sysconfdir,
+                    folder.display(),
+                    error
+                );
+            }
+        }
+    }
+
+    /// The temporary directory everything lives in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Sets an environment variable until the environment is dropped.
+    pub fn set_env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) {
+        self.save_variable(key.as_ref());
+        env::set_var(key, value);
+    }
+
+    /// Removes an environment variable until the environment is dropped.
+    pub fn remove_env(&mut self, key: impl AsRef<OsStr>) {
+        self.save_variable(key.as_ref());
+        env::remove_var(key);
+    }
+
+    fn save_variable(&mut self, key: &OsStr) {
+        if !self.saved_variables.iter().any(|(saved, _)| saved == key) {
+            self.saved_variables.push((key.into(), env::var_os(key)));
+        }
+    }
+
+    fn add_search_path(&mut self, variable: &str, folder: Folder) {
+        let path = self.folder(folder);
+        if env::var_os(variable).as_deref() != Some(path.as_os_str()) {
+            self.set_env(variable, path);
+        }
+    }
+
+    // Gives each library its own file so that two drivers or layers using the same binary are loaded twice.
+    fn copy_library(&self, library: &Path, folder: Folder, index: usize) -> PathBuf {
+        let mut name = library.file_stem().unwrap_or_default().to_os_string();
+        name.push(format!("_{}", index));
+        if let Some(extension) = library.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+
+        let destination = self.folder(folder).join(name);
+        fs::copy(library, &destination).unwrap_or_else(|error| {
+            panic!(
+                "Failed to copy {} into the test environment: {}",
+                library.display(),
+                error
+            )
+        });
+        destination
+    }
+
+    fn write_manifest(&self, folder: Folder, name: &str, contents: &str) -> PathBuf {
+        let path = self.folder(folder).join(format!("{}.json", name));
+        fs::write(&path, contents)
+            .unwrap_or_else(|error| panic!("Failed to write {}: {}", path.display(), error));
+        path
+    }
+
+    /// Adds a driver and returns where its manifest ended up.
+    pub fn add_icd(&mut self, mut details: TestIcdDetails) -> &InstalledManifest {
+        let index = self.icds.len();
+        let folder = match details.discovery_type {
+            ManifestDiscoveryType::Generic => Folder::Drivers,
+            ManifestDiscoveryType::AddEnvVar => {
+                self.add_search_path("VK_ADD_DRIVER_FILES", Folder::AddDrivers);
+                Folder::AddDrivers
+            }
+            ManifestDiscoveryType::None => Folder::Null,
+        };
+
+        let library_path = if details.is_fake {
+            None
+        } else {
+            let library_path = self.copy_library(&details.manifest.library_path, folder, index);
+            details.manifest.library_path = library_path.clone();
+            Some(library_path)
+        };
+
+        let manifest_path = self.write_manifest(
+            folder,
+            &format!("{}_{}", details.json_name, index),
+            &details.manifest.to_json(),
+        );
+
+        self.icds.push(InstalledManifest {
+            manifest_path,
+            library_path,
+        });
+        self.icds.last().unwrap()
+    }
+
+    /// Adds an implicit layer. Implicit layers are always on unless their manifest says otherwise through
+    /// `enable_environment` or `disable_environment`.
+    pub fn add_implicit_layer(&mut self, details: TestLayerDetails) -> &InstalledManifest {
+        self.add_layer(details, Folder::ImplicitLayers)
+    }
+
+    /// Adds an explicit layer, which is only used when the application or `VK_INSTANCE_LAYERS` asks for it.
+    pub fn add_explicit_layer(&mut self, details: TestLayerDetails) -> &InstalledManifest {
+        let folder = match details.discovery_type {
+            ManifestDiscoveryType::Generic => Folder::ExplicitLayers,
+            ManifestDiscoveryType::AddEnvVar => {
+                self.add_search_path("VK_ADD_LAYER_PATH", Folder::AddExplicitLayers);
+                Folder::AddExplicitLayers
+            }
+            ManifestDiscoveryType::None => Folder::Null,
+        };
+        self.add_layer(details, folder)
+    }
+
+    fn add_layer(&mut self, mut details: TestLayerDetails, folder: Folder) -> &InstalledManifest {
+        let index = self.layers.len();
+        let folder = if details.discovery_type == ManifestDiscoveryType::None {
+            Folder::Null
+        } else {
+            folder
+        };
+
+        let library_path = match (&details.manifest.library_path, details.is_fake) {
+            (Some(library_path), false) => {
+                let library_path = self.copy_library(library_path, folder, index);
+                details.manifest.library_path = Some(library_path.clone());
+                Some(library_path)
+            }
+            _ => None,
+        };
+
+        let manifest_path =
+            self.write_manifest(folder, &details.json_name, &details.manifest.to_json());
+
+        self.layers.push(InstalledManifest {
+            manifest_path,
+            library_path,
+        });
+        self.layers.last().unwrap()
+    }
+
+    pub fn icds(&self) -> &[InstalledManifest] {
+        &self.icds
+    }
+
+    pub fn layers(&self) -> &[InstalledManifest] {
+        &self.layers
+    }
+
+    /// Creates an instance with an application info requesting Vulkan 1.0 and no layers or extensions.
+    pub fn create_instance(&self) -> Result<Instance, VkResult> {
+        self.create_instance_with(&[], &[])
+    }
+
+    /// Creates an instance with the given layers and extensions enabled.
+    pub fn create_instance_with(
+        &self,
+        layers: &[&CStr],
+        extensions: &[&CStr],
+    ) -> Result<Instance, VkResult> {
+        let layers = layers.iter().map(|name| name.as_ptr()).collect::<Vec<_>>();
+        let extensions = extensions
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect::<Vec<_>>();
+
+        let application_info = VkApplicationInfo {
+            sType: VK_STRUCTURE_TYPE_APPLICATION_INFO,
+            pNext: null(),
+            pApplicationName: c"vulkan-loader-sys-test-support".as_ptr(),
+            applicationVersion: 0,
+            pEngineName: null(),
+            engineVersion: 0,
+            apiVersion: VK_MAKE_API_VERSION(0, 1, 0, 0),
+        };
+
+        let create_info = VkInstanceCreateInfo {
+            sType: VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+            pNext: null(),
+            flags: 0,
+            pApplicationInfo: &application_info,
+            enabledLayerCount: layers.len().try_into().unwrap(),
+            ppEnabledLayerNames: layers.as_ptr(),
+            enabledExtensionCount: extensions.len().try_into().unwrap(),
+            ppEnabledExtensionNames: extensions.as_ptr(),
+        };
+
+        unsafe { Instance::new(&create_info) }
+    }
+
+    /// The names of the layers `vkEnumerateInstanceLayerProperties` reports.
+    pub fn instance_layers(&self) -> Result<Vec<String>, VkResult> {
+        let properties = unsafe {
+            loop {
+                let mut count = 0;
+                let result = vkEnumerateInstanceLayerProperties(&mut count, null_mut());
+                if result != VK_SUCCESS {
+                    return Err(result);
+                }
+
+                let mut properties = Vec::with_capacity(count as usize);
+                let result =
+                    vkEnumerateInstanceLayerProperties(&mut count, properties.as_mut_ptr());
+                // VK_INCOMPLETE means layers were added between the two calls, so ask again.
+                match result {
+                    VK_SUCCESS => {
+                        properties.set_len(count as usize);
+                        break properties;
+                    }
+                    VK_INCOMPLETE => continue,
+                    _ => return Err(result),
+                }
+            }
+        };
+
+        Ok(properties
+            .iter()
+            .map(|properties: &VkLayerProperties| unsafe {
+                CStr::from_ptr(properties.layerName.as_ptr() as *const c_char)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect())
+    }
+}
+
+impl Default for FrameworkEnvironment {
+    fn default() -> FrameworkEnvironment {
+        FrameworkEnvironment::new()
+    }
+}
+
+// Sets the loader's debug level and returns the previous one.
+#[cfg(loader_log_hook)]
+fn replace_debug_level(debug_level: u32) -> u32 {
+    let previous = loader_log::debug_level();
+    loader_log::set_debug_level(debug_level);
+    previous
+}
+
+#[cfg(not(loader_log_hook))]
+fn replace_debug_level(_debug_level: u32) -> u32 {
+    panic!(
+        "FrameworkSettings::log_filter needs a loader with the log hook, which the one in \
+         VULKAN_LOADER_SYS_LIB_DIR lacks"
+    );
+}
+
+impl Drop for FrameworkEnvironment {
+    fn drop(&mut self) {
+        for (key, value) in self.saved_variables.drain(..).rev() {
+            match value {
+                Some(value) => env::set_var(key, value),
+                None => env::remove_var(key),
+            }
+        }
+
+        if let Some(debug_level) = self.saved_debug_level {
+            replace_debug_level(debug_level);
+        }
+
+        if let Some(sysconfdir) = vulkan_loader_sys::LOADER_TEST_SYSCONFDIR {
+            let _ = fs::remove_file(sysconfdir);
+        }
+
+        if !self.settings.keep_directory {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Test support for code built on `vulkan-loader-sys`, modelled on the `FrameworkEnvironment` of the loader's C++ test
+//! framework in `tests/framework`.
+//!
+//! A test declares the drivers and layers it wants, and [`FrameworkEnvironment`] writes their manifests into a fresh
+//! temporary directory and points the loader at it before any instance is created:
+//!
+//! ```no_run
+//! use vulkan_loader_sys_test_support::*;
+//!
+//! let mut env = FrameworkEnvironment::new();
+//! let icd = test_icd_path("test_icd_version_2").unwrap();
+//! env.add_icd(TestIcdDetails::new(&icd));
+//! env.add_icd(TestIcdDetails::new(&icd));
+//! env.add_implicit_layer(TestLayerDetails::new(
+//!     LayerManifest::new("VK_LAYER_implicit_test")
+//!         .library_path(test_layer_path("test_layer_export_version_2").unwrap())
+//!         .enable_environment("ENABLE_ME")
+//!         .disable_environment("DISABLE_ME"),
+//!     "implicit_test_layer",
+//! ));
+//! env.set_env("ENABLE_ME", "1");
+//!
+//! let instance = env.create_instance().unwrap();
+//! ```
+//!
+//! The test drivers and layers come from the loader's own test framework and are only built when `vulkan-loader-sys`
+//! has its `loader-tests` feature enabled. Any other driver or layer library works just as well.
+
+use std::path::PathBuf;
+
+pub mod environment;
+pub mod manifest;
+
+pub use environment::{
+    FrameworkEnvironment, FrameworkSettings, InstalledManifest, ManifestDiscoveryType,
+    TestIcdDetails, TestLayerDetails,
+};
+pub use manifest::{IcdManifest, LayerManifest};
+
+fn test_library_path(folder: &str, name: &str) -> Option<PathBuf> {
+    let directory = PathBuf::from(vulkan_loader_sys::LOADER_TEST_BINARY_DIR?);
+    let path = directory
+        .join("framework")
+        .join(folder)
+        .join(format!("lib{}.so", name));
+    path.exists().then_some(path)
+}
+
+/// The path of one of the test framework's drivers, such as `test_icd_version_2`, if it was built.
+pub fn test_icd_path(name: &str) -> Option<PathBuf> {
+    test_library_path("icd", name)
+}
+
+/// The path of one of the test framework's layers, such as `test_layer_export_version_2`, if it was built.
+pub fn test_layer_path(name: &str) -> Option<PathBuf> {
+    test_library_path("layer", name)
+}
@@ -0,0 +1,282 @@
+//! Driver and layer manifests, written the same way `tests/framework/json_writer.h` writes them for the C++ framework.
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// Formats a `VK_MAKE_API_VERSION` value as the loader expects it in manifests, for example `1.3.0`.
+pub fn version_to_string(version: u32) -> String {
+    let major = (version >> 22) & 0x7F;
+    let minor = (version >> 12) & 0x3FF;
+    let patch = version & 0xFFF;
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len() + 2);
+    escaped.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Just enough of a JSON writer to produce readable manifests.
+struct JsonWriter {
+    output: String,
+    // Whether the innermost open object already has a member, which decides if the next one needs a comma.
+    has_members: Vec<bool>,
+}
+
+impl JsonWriter {
+    fn new() -> JsonWriter {
+        JsonWriter {
+            output: String::new(),
+            has_members: Vec::new(),
+        }
+    }
+
+    fn start_member(&mut self, key: &str) {
+        if let Some(has_members) = self.has_members.last_mut() {
+            if *has_members {
+                self.output.push(',');
+            }
+            *has_members = true;
+        }
+        self.output.push('\n');
+        self.indent();
+        write!(self.output, "{}: ", escape(key)).unwrap();
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.has_members.len() {
+            self.output.push_str("    ");
+        }
+    }
+
+    fn start_object(&mut self) {
+        self.output.push('{');
+        self.has_members.push(false);
+    }
+
+    fn start_keyed_object(&mut self, key: &str) {
+        self.start_member(key);
+        self.start_object();
+    }
+
+    fn end_object(&mut self) {
+        self.has_members.pop();
+        self.output.push('\n');
+        self.indent();
+        self.output.push('}');
+    }
+
+    fn add_string(&mut self, key: &str, value: &str) {
+        self.start_member(key);
+        self.output.push_str(&escape(value));
+    }
+
+    fn add_bool(&mut self, key: &str, value: bool) {
+        self.start_member(key);
+        self.output.push_str(if value { "true" } else { "false" });
+    }
+
+    fn add_strings(&mut self, key: &str, values: &[String]) {
+        self.start_member(key);
+        let values = values.iter().map(|value| escape(value)).collect::<Vec<_>>();
+        write!(self.output, "[{}]", values.join(", ")).unwrap();
+    }
+
+    fn finish(mut self) -> String {
+        self.output.push('\n');
+        self.output
+    }
+}
+
+/// The contents of a driver manifest.
+#[derive(Debug, Clone)]
+pub struct IcdManifest {
+    pub file_format_version: String,
+    pub library_path: PathBuf,
+    pub api_version: u32,
+    pub is_portability_driver: bool,
+    pub library_arch: Option<String>,
+}
+
+impl IcdManifest {
+    pub fn new(library_path: impl AsRef<Path>) -> IcdManifest {
+        IcdManifest {
+            file_format_version: "1.0.0".into(),
+            library_path: library_path.as_ref().into(),
+            api_version: vulkan_loader_sys::VK_MAKE_API_VERSION(0, 1, 0, 0),
+            is_portability_driver: false,
+            library_arch: None,
+        }
+    }
+
+    pub fn api_version(mut self, api_version: u32) -> IcdManifest {
+        self.api_version = api_version;
+        self
+    }
+
+    pub fn is_portability_driver(mut self, is_portability_driver: bool) -> IcdManifest {
+        self.is_portability_driver = is_portability_driver;
+        self
+    }
+
+    pub fn library_arch(mut self, library_arch: impl Into<String>) -> IcdManifest {
+        self.library_arch = Some(library_arch.into());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut writer = JsonWriter::new();
+        writer.start_object();
+        writer.add_string("file_format_version", &self.file_format_version);
+        writer.start_keyed_object("ICD");
+        writer.add_string("library_path", &self.library_path.to_string_lossy());
+        writer.add_string("api_version", &version_to_string(self.api_version));
+        writer.add_bool("is_portability_driver", self.is_portability_driver);
+        if let Some(library_arch) = &self.library_arch {
+            writer.add_string("library_arch", library_arch);
+        }
+        writer.end_object();
+        writer.end_object();
+        writer.finish()
+    }
+}
+
+/// The contents of a layer manifest describing a single layer.
+#[derive(Debug, Clone)]
+pub struct LayerManifest {
+    pub file_format_version: String,
+    pub name: String,
+    pub library_path: Option<PathBuf>,
+    pub api_version: u32,
+    pub implementation_version: u32,
+    pub description: String,
+    pub instance_extensions: Vec<String>,
+    pub enable_environment: Option<String>,
+    pub disable_environment: Option<String>,
+    /// Turns the manifest into a meta layer made of these layers.
+    pub component_layers: Vec<String>,
+}
+
+impl LayerManifest {
+    pub fn new(name: impl Into<String>) -> LayerManifest {
+        LayerManifest {
+            file_format_version: "1.1.2".into(),
+            name: name.into(),
+            library_path: None,
+            api_version: vulkan_loader_sys::VK_MAKE_API_VERSION(0, 1, 0, 0),
+            implementation_version: 0,
+            description: String::new(),
+            instance_extensions: Vec::new(),
+            enable_environment: None,
+            disable_environment: None,
+            component_layers: Vec::new(),
+        }
+    }
+
+    pub fn library_path(mut self, library_path: impl AsRef<Path>) -> LayerManifest {
+        self.library_path = Some(library_path.as_ref().into());
+        self
+    }
+
+    pub fn api_version(mut self, api_version: u32) -> LayerManifest {
+        self.api_version = api_version;
+        self
+    }
+
+    pub fn implementation_version(mut self, implementation_version: u32) -> LayerManifest {
+        self.implementation_version = implementation_version;
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> LayerManifest {
+        self.description = description.into();
+        self
+    }
+
+    pub fn instance_extension(mut self, extension: impl Into<String>) -> LayerManifest {
+        self.instance_extensions.push(extension.into());
+        self
+    }
+
+    /// The layer is only enabled while this environment variable is set to `1`.
+    pub fn enable_environment(mut self, variable: impl Into<String>) -> LayerManifest {
+        self.enable_environment = Some(variable.into());
+        self
+    }
+
+    /// The layer is disabled while this environment variable is set to `1`. The loader ignores implicit layers that do
+    /// not have one.
+    pub fn disable_environment(mut self, variable: impl Into<String>) -> LayerManifest {
+        self.disable_environment = Some(variable.into());
+        self
+    }
+
+    pub fn component_layer(mut self, layer: impl Into<String>) -> LayerManifest {
+        self.component_layers.push(layer.into());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut writer = JsonWriter::new();
+        writer.start_object();
+        writer.add_string("file_format_version", &self.file_format_version);
+        writer.start_keyed_object("layer");
+        writer.add_string("name", &self.name);
+        writer.add_string("type", "GLOBAL");
+        if let Some(library_path) = &self.library_path {
+            writer.add_string("library_path", &library_path.to_string_lossy());
+        }
+        writer.add_string("api_version", &version_to_string(self.api_version));
+        writer.add_string(
+            "implementation_version",
+            &self.implementation_version.to_string(),
+        );
+        writer.add_string("description", &self.description);
+        if !self.component_layers.is_empty() {
+            writer.add_strings("component_layers", &self.component_layers);
+        }
+        if !self.instance_extensions.is_empty() {
+            writer.start_member("instance_extensions");
+            writer.output.push('[');
+            for (index, extension) in self.instance_extensions.iter().enumerate() {
+                if index > 0 {
+                    writer.output.push_str(", ");
+                }
+                write!(
+                    writer.output,
+                    "{{\"name\": {}, \"spec_version\": \"1\"}}",
+                    escape(extension)
+                )
+                .unwrap();
+            }
+            writer.output.push(']');
+        }
+        if let Some(variable) = &self.enable_environment {
+            writer.start_keyed_object("enable_environment");
+            writer.add_string(variable, "1");
+            writer.end_object();
+        }
+        if let Some(variable) = &self.disable_environment {
+            writer.start_keyed_object("disable_environment");
+            writer.add_string(variable, "1");
+            writer.end_object();
+        }
+        writer.end_object();
+        writer.end_object();
+        writer.finish()
+    }
+}
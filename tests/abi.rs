@@ -0,0 +1,140 @@
+//! Checks the generated bindings against the C compiler's view of vulkan.h.
+//!
+//! bindgen's own layout tests are turned off in build.rs. Instead, with the `abi-tests` feature enabled, build.rs
+//! compiles a C probe recording the size and alignment of every Vk and StdVideo struct and union, together with the
+//! offset and size of each of their fields but the bitfields, and generates the same list from the Rust side. Running
+//! this for another target, for example
+//! `cargo test --features abi-tests --test abi --target aarch64-unknown-linux-gnu`, checks the bindings for that
+//! target.
+//!
+//! build.rs also generates the bindings for a few other targets, listed in `COMPARED_TARGETS` in build.rs or in
+//! `VULKAN_LOADER_SYS_COMPARE_TARGETS`, and `bindings_across_targets` checks that they only differ where vulkan.h
+//! itself depends on the pointer size.
+
+#![cfg(feature = "abi-tests")]
+
+use std::{ffi::CStr, os::raw::c_char};
+
+use vulkan_loader_sys::*;
+
+include!(concat!(env!("OUT_DIR"), "/abi_tests.rs"));
+include!(concat!(env!("OUT_DIR"), "/bindings_comparison.rs"));
+
+#[repr(C)]
+struct AbiProbeType {
+    name: *const c_char,
+    size: usize,
+    align: usize,
+}
+
+#[repr(C)]
+struct AbiProbeField {
+    type_name: *const c_char,
+    name: *const c_char,
+    offset: usize,
+    size: usize,
+}
+
+extern "C" {
+    static vulkan_loader_sys_abi_types: [AbiProbeType; ABI_TYPE_COUNT];
+    static vulkan_loader_sys_abi_fields: [AbiProbeField; ABI_FIELD_COUNT];
+}
+
+fn field_size<T, U>(_: fn(&T) -> &U) -> usize {
+    std::mem::size_of::<U>()
+}
+
+fn string(string: *const c_char) -> &'static str {
+    unsafe { CStr::from_ptr(string) }.to_str().unwrap()
+}
+
+#[test]
+fn type_layouts() {
+    let c_types = unsafe { &vulkan_loader_sys_abi_types }
+        .iter()
+        .map(|probe| (string(probe.name), probe.size, probe.align))
+        .collect::<Vec<_>>();
+
+    let mismatches = rust_types()
+        .into_iter()
+        .zip(c_types)
+        .filter(|(rust, c)| rust != c)
+        .map(|(rust, c)| format!("{:?} in Rust, {:?} in C", rust, c))
+        .collect::<Vec<_>>();
+
+    assert!(
+        mismatches.is_empty(),
+        "{} of {} types have a different size or alignment (name, size, alignment):\n{}",
+        mismatches.len(),
+        ABI_TYPE_COUNT,
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn field_layouts() {
+    let c_fields = unsafe { &vulkan_loader_sys_abi_fields }
+        .iter()
+        .map(|probe| {
+            (
+                string(probe.type_name),
+                string(probe.name),
+                probe.offset,
+                probe.size,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mismatches = rust_fields()
+        .into_iter()
+        .zip(c_fields)
+        .filter(|(rust, c)| rust != c)
+        .map(|(rust, c)| format!("{:?} in Rust, {:?} in C", rust, c))
+        .collect::<Vec<_>>();
+
+    assert!(
+        mismatches.is_empty(),
+        "{} of {} fields have a different offset or size (type, field, offset, size):\n{}",
+        mismatches.len(),
+        ABI_FIELD_COUNT,
+        mismatches.join("\n")
+    );
+}
+
+// vulkan.h defines non-dispatchable handles as pointers to an opaque struct on 64-bit targets and as uint64_t on the
+// others, with VK_USE_64_BIT_PTR_DEFINES and VK_NULL_HANDLE following suit.
+fn differs_by_pointer_size(item: &str, one: &str, other: &str) -> bool {
+    let (kind, name) = item.split_once(' ').unwrap();
+    match kind {
+        "type" => {
+            let pointer = format!("pub type {0} = * mut {0}_T ;", name);
+            let integer = format!("pub type {} = u64 ;", name);
+            (one == pointer && other == integer) || (one == integer && other == pointer)
+        }
+        "struct" => name.ends_with("_T") && (one.is_empty() || other.is_empty()),
+        "const" => name == "VK_USE_64_BIT_PTR_DEFINES" || name == "VK_NULL_HANDLE",
+        _ => false,
+    }
+}
+
+#[test]
+fn bindings_across_targets() {
+    let unexpected = BINDING_DIFFERENCES
+        .iter()
+        .filter(|(_, item, ours, theirs)| !differs_by_pointer_size(item, ours, theirs))
+        .map(|(target, item, ours, theirs)| {
+            format!(
+                "{} on {}:\n  ours:   {}\n  theirs: {}",
+                item, target, ours, theirs
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert!(
+        unexpected.is_empty(),
+        "the bindings differ from the ones for {:?} in {} items:\n{}",
+        COMPARED_TARGETS,
+        unexpected.len(),
+        unexpected.join("\n")
+    );
+}
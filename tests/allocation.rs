@@ -0,0 +1,330 @@
+//! The bookkeeping `GlobalAllocator` and `TrackingAllocator` do on top of the allocator they wrap.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    ffi::c_void,
+    mem::{align_of, size_of},
+    ptr::null_mut,
+    slice,
+    sync::Mutex,
+};
+
+use vulkan_loader_sys::{allocation::*, *};
+
+// What GlobalAllocator stores in front of every allocation: the size and the alignment.
+const HEADER_SIZE: usize = 2 * size_of::<usize>();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Call {
+    Alloc(Layout),
+    Realloc(Layout, usize),
+    Dealloc(Layout),
+}
+
+/// The system allocator, remembering every call made to it along with the address it returned or was passed.
+#[derive(Default)]
+struct Recording(Mutex<Vec<(Call, usize)>>);
+
+impl Recording {
+    fn take(&self) -> Vec<(Call, usize)> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+unsafe impl GlobalAlloc for Recording {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let base = System.alloc(layout);
+        self.0
+            .lock()
+            .unwrap()
+            .push((Call::Alloc(layout), base as usize));
+        base
+    }
+
+    unsafe fn realloc(&self, base: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_base = System.realloc(base, layout, new_size);
+        self.0
+            .lock()
+            .unwrap()
+            .push((Call::Realloc(layout, new_size), new_base as usize));
+        new_base
+    }
+
+    unsafe fn dealloc(&self, base: *mut u8, layout: Layout) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((Call::Dealloc(layout), base as usize));
+        System.dealloc(base, layout)
+    }
+}
+
+fn fill(memory: *mut c_void, size: usize) {
+    let memory = unsafe { slice::from_raw_parts_mut(memory as *mut u8, size) };
+    for (i, byte) in memory.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+}
+
+fn is_filled(memory: *mut c_void, size: usize) -> bool {
+    let memory = unsafe { slice::from_raw_parts(memory as *const u8, size) };
+    memory.iter().enumerate().all(|(i, byte)| *byte == i as u8)
+}
+
+#[test]
+fn global_allocator_layout() {
+    let allocator = GlobalAllocator(Recording::default());
+
+    for alignment in [1, 2, 4, 8, 16, 32, 64, 256, 4096] {
+        for size in [1, 3, 24, 100, 4096] {
+            let memory = allocator.allocate(size, alignment, VK_SYSTEM_ALLOCATION_SCOPE_OBJECT);
+            assert!(!memory.is_null());
+            assert_eq!(memory as usize % alignment, 0);
+            fill(memory, size);
+
+            let calls = allocator.0.take();
+            let (layout, base) = match calls[..] {
+                [(Call::Alloc(layout), base)] => (layout, base),
+                _ => panic!("unexpected calls {:?}", calls),
+            };
+            // The header comes first, padded to the alignment, so the user visible part is still aligned.
+            let offset = memory as usize - base;
+            let expected_alignment = alignment.max(align_of::<usize>());
+            assert_eq!(layout.align(), expected_alignment);
+            assert_eq!(
+                offset,
+                HEADER_SIZE.div_ceil(expected_alignment) * expected_alignment
+            );
+            assert_eq!(layout.size(), offset + size);
+
+            unsafe { allocator.free(memory) };
+            assert_eq!(allocator.0.take(), [(Call::Dealloc(layout), base)]);
+        }
+    }
+}
+
+#[test]
+fn global_allocator_rejects_what_it_cannot_allocate() {
+    let allocator = GlobalAllocator(Recording::default());
+
+    assert!(allocator
+        .allocate(0, 8, VK_SYSTEM_ALLOCATION_SCOPE_COMMAND)
+        .is_null());
+    // The header doesn't fit on top of the size.
+    assert!(allocator
+        .allocate(usize::MAX, 8, VK_SYSTEM_ALLOCATION_SCOPE_COMMAND)
+        .is_null());
+    // Not a power of two.
+    assert!(allocator
+        .allocate(16, 24, VK_SYSTEM_ALLOCATION_SCOPE_COMMAND)
+        .is_null());
+    assert!(allocator.0.take().is_empty());
+
+    unsafe { allocator.free(null_mut()) };
+    assert!(allocator.0.take().is_empty());
+}
+
+#[test]
+fn global_allocator_reallocate() {
+    let allocator = GlobalAllocator(Recording::default());
+    let scope = VK_SYSTEM_ALLOCATION_SCOPE_OBJECT;
+
+    // A null original allocates.
+    let memory = unsafe { allocator.reallocate(null_mut(), 40, 64, scope) };
+    assert_eq!(memory as usize % 64, 0);
+    fill(memory, 40);
+    let layout = match allocator.0.take()[..] {
+        [(Call::Alloc(layout), _)] => layout,
+        ref calls => panic!("unexpected calls {:?}", calls),
+    };
+    assert_eq!(layout, Layout::from_size_align(64 + 40, 64).unwrap());
+
+    // Growing keeps the contents and the alignment, and hands the underlying allocator the layout it allocated with.
+    let grown = unsafe { allocator.reallocate(memory, 4000, 64, scope) };
+    assert_eq!(grown as usize % 64, 0);
+    assert!(is_filled(grown, 40));
+    fill(grown, 4000);
+    let grown_base = match allocator.0.take()[..] {
+        [(Call::Realloc(old_layout, new_size), new_base)] => {
+            assert_eq!(old_layout, layout);
+            assert_eq!(new_size, 64 + 4000);
+            new_base
+        }
+        ref calls => panic!("unexpected calls {:?}", calls),
+    };
+    assert_eq!(grown as usize - grown_base, 64);
+
+    // Shrinking remembers the new size, which freeing then relies on.
+    let shrunk = unsafe { allocator.reallocate(grown, 10, 64, scope) };
+    assert!(is_filled(shrunk, 10));
+    let shrunk_base = match allocator.0.take()[..] {
+        [(Call::Realloc(old_layout, new_size), new_base)] => {
+            assert_eq!(old_layout, Layout::from_size_align(64 + 4000, 64).unwrap());
+            assert_eq!(new_size, 64 + 10);
+            new_base
+        }
+        ref calls => panic!("unexpected calls {:?}", calls),
+    };
+
+    // A size of zero frees.
+    assert!(unsafe { allocator.reallocate(shrunk, 0, 64, scope) }.is_null());
+    assert_eq!(
+        allocator.0.take(),
+        [(
+            Call::Dealloc(Layout::from_size_align(64 + 10, 64).unwrap()),
+            shrunk_base
+        )]
+    );
+}
+
+#[test]
+fn tracking_allocator_counts() {
+    let allocator = TrackingAllocator::default();
+
+    let command = allocator.allocate(100, 8, VK_SYSTEM_ALLOCATION_SCOPE_COMMAND);
+    let object = allocator.allocate(200, 16, VK_SYSTEM_ALLOCATION_SCOPE_OBJECT);
+    let instance = allocator.allocate(300, 8, VK_SYSTEM_ALLOCATION_SCOPE_INSTANCE);
+    // Failed allocations are counted, but don't leave anything live.
+    assert!(allocator
+        .allocate(0, 8, VK_SYSTEM_ALLOCATION_SCOPE_CACHE)
+        .is_null());
+
+    let statistics = allocator.statistics();
+    let command_statistics = statistics.scope(VK_SYSTEM_ALLOCATION_SCOPE_COMMAND);
+    assert_eq!(command_statistics.allocations, 1);
+    assert_eq!(command_statistics.live_allocations, 1);
+    assert_eq!(command_statistics.live_bytes, 100);
+    assert_eq!(command_statistics.peak_bytes, 100);
+    assert_eq!(
+        statistics
+            .scope(VK_SYSTEM_ALLOCATION_SCOPE_OBJECT)
+            .live_bytes,
+        200
+    );
+    assert_eq!(
+        statistics
+            .scope(VK_SYSTEM_ALLOCATION_SCOPE_INSTANCE)
+            .live_bytes,
+        300
+    );
+    let cache_statistics = statistics.scope(VK_SYSTEM_ALLOCATION_SCOPE_CACHE);
+    assert_eq!(cache_statistics.allocations, 1);
+    assert_eq!(cache_statistics.live_allocations, 0);
+
+    // The new size is accounted to the scope of the reallocation, the old one to the scope it was allocated in.
+    let command =
+        unsafe { allocator.reallocate(command, 40, 8, VK_SYSTEM_ALLOCATION_SCOPE_DEVICE) };
+    let object =
+        unsafe { allocator.reallocate(object, 1000, 16, VK_SYSTEM_ALLOCATION_SCOPE_OBJECT) };
+    let statistics = allocator.statistics();
+    let command_statistics = statistics.scope(VK_SYSTEM_ALLOCATION_SCOPE_COMMAND);
+    assert_eq!(command_statistics.live_allocations, 0);
+    assert_eq!(command_statistics.live_bytes, 0);
+    assert_eq!(command_statistics.peak_bytes, 100);
+    assert_eq!(command_statistics.frees, 0);
+    let device_statistics = statistics.scope(VK_SYSTEM_ALLOCATION_SCOPE_DEVICE);
+    assert_eq!(device_statistics.reallocations, 1);
+    assert_eq!(device_statistics.live_bytes, 40);
+    let object_statistics = statistics.scope(VK_SYSTEM_ALLOCATION_SCOPE_OBJECT);
+    assert_eq!(object_statistics.reallocations, 1);
+    assert_eq!(object_statistics.live_allocations, 1);
+    assert_eq!(object_statistics.live_bytes, 1000);
+    assert_eq!(object_statistics.peak_bytes, 1000);
+
+    unsafe {
+        allocator.free(command);
+        allocator.free(object);
+        allocator.free(instance);
+        allocator.free(null_mut());
+    }
+    let statistics = allocator.statistics();
+    for scope in SYSTEM_ALLOCATION_SCOPES {
+        assert_eq!(statistics.scope(scope).live_allocations, 0);
+        assert_eq!(statistics.scope(scope).live_bytes, 0);
+    }
+    assert_eq!(statistics.scope(VK_SYSTEM_ALLOCATION_SCOPE_DEVICE).frees, 1);
+    assert_eq!(statistics.scope(VK_SYSTEM_ALLOCATION_SCOPE_OBJECT).frees, 1);
+    assert_eq!(
+        statistics.scope(VK_SYSTEM_ALLOCATION_SCOPE_INSTANCE).frees,
+        1
+    );
+    assert_eq!(statistics.unknown_frees, 0);
+    assert!(allocator.leaks().is_empty());
+
+    allocator.internal_allocation(
+        64,
+        VK_INTERNAL_ALLOCATION_TYPE_EXECUTABLE,
+        VK_SYSTEM_ALLOCATION_SCOPE_DEVICE,
+    );
+    allocator.internal_free(
+        64,
+        VK_INTERNAL_ALLOCATION_TYPE_EXECUTABLE,
+        VK_SYSTEM_ALLOCATION_SCOPE_DEVICE,
+    );
+    let statistics = allocator.statistics();
+    assert_eq!(
+        statistics
+            .scope(VK_SYSTEM_ALLOCATION_SCOPE_DEVICE)
+            .internal_allocations,
+        1
+    );
+    assert_eq!(
+        statistics
+            .scope(VK_SYSTEM_ALLOCATION_SCOPE_DEVICE)
+            .internal_frees,
+        1
+    );
+}
+
+#[test]
+fn tracking_allocator_unknown_scopes_and_frees() {
+    let allocator = TrackingAllocator::default();
+
+    let memory = allocator.allocate(16, 8, 42);
+    let statistics = allocator.statistics();
+    assert_eq!(statistics.scope(42).allocations, 1);
+    assert_eq!(statistics.unknown_scope.live_bytes, 16);
+    assert!(SYSTEM_ALLOCATION_SCOPES
+        .iter()
+        .all(|scope| statistics.scope(*scope).allocations == 0));
+    unsafe { allocator.free(memory) };
+    assert_eq!(allocator.statistics().unknown_scope.frees, 1);
+
+    // Memory from elsewhere is still handed to the wrapped allocator, but counted.
+    let elsewhere = GlobalAllocator(System).allocate(16, 8, VK_SYSTEM_ALLOCATION_SCOPE_OBJECT);
+    unsafe { allocator.free(elsewhere) };
+    assert_eq!(allocator.statistics().unknown_frees, 1);
+}
+
+#[test]
+fn tracking_allocator_leaks() {
+    let allocator = TrackingAllocator::default();
+
+    let first = allocator.allocate(10, 8, VK_SYSTEM_ALLOCATION_SCOPE_OBJECT);
+    let second = allocator.allocate(20, 64, VK_SYSTEM_ALLOCATION_SCOPE_DEVICE);
+    let third = allocator.allocate(30, 8, VK_SYSTEM_ALLOCATION_SCOPE_INSTANCE);
+    unsafe { allocator.free(second) };
+
+    let mut expected = vec![
+        LiveAllocation {
+            address: first as usize,
+            size: 10,
+            alignment: 8,
+            scope: VK_SYSTEM_ALLOCATION_SCOPE_OBJECT,
+        },
+        LiveAllocation {
+            address: third as usize,
+            size: 30,
+            alignment: 8,
+            scope: VK_SYSTEM_ALLOCATION_SCOPE_INSTANCE,
+        },
+    ];
+    expected.sort_by_key(|allocation| allocation.address);
+    assert_eq!(allocator.leaks(), expected);
+
+    unsafe {
+        allocator.free(first);
+        allocator.free(third);
+    }
+    assert!(allocator.leaks().is_empty());
+}
@@ -0,0 +1,120 @@
+//! Checks what build.rs tells cargo to rerun it for: every file the loader is built from, and nothing that cargo would
+//! otherwise rebuild the loader for, such as the Rust sources, the documentation or files that don't exist.
+
+#![cfg(not(feature = "docs-only"))]
+
+use std::{fs, path::Path};
+
+include!(concat!(env!("OUT_DIR"), "/build_inputs.rs"));
+
+// Scripts the loader's build runs or reads. The others are only used by hand.
+const BUILD_SCRIPTS: &[&str] = &[
+    "scripts/CMakeLists.txt",
+    "scripts/known_good.json",
+    "scripts/update_deps.py",
+    "scripts/parse_asm_values.py",
+];
+
+fn files_below(dir: &Path, files: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files_below(&path, files);
+        } else {
+            files.push(
+                path.strip_prefix(env!("CARGO_MANIFEST_DIR"))
+                    .unwrap()
+                    .display()
+                    .to_string(),
+            );
+        }
+    }
+}
+
+fn is_tracked(file: &str) -> bool {
+    BUILD_INPUT_FILES.contains(&file)
+}
+
+fn built_from_source() -> bool {
+    option_env!("VULKAN_LOADER_SYS_LIB_DIR").is_none()
+}
+
+#[test]
+fn loader_sources_are_tracked() {
+    if !built_from_source() {
+        return;
+    }
+
+    let mut files = Vec::new();
+    files_below(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("loader"),
+        &mut files,
+    );
+    assert!(files
+        .iter()
+        .any(|file| file == "loader/generated/vk_loader_extensions.c"));
+
+    let untracked = files
+        .iter()
+        .filter(|file| !file.ends_with(".md") && !is_tracked(file))
+        .collect::<Vec<_>>();
+    assert!(untracked.is_empty(), "not tracked: {:?}", untracked);
+
+    assert!(is_tracked("CMakeLists.txt"));
+    for script in BUILD_SCRIPTS {
+        assert!(is_tracked(script), "{} is not tracked", script);
+    }
+}
+
+#[test]
+fn only_existing_files_are_tracked() {
+    for file in BUILD_INPUT_FILES {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(file);
+        assert!(path.is_file(), "{} is not a file", file);
+    }
+}
+
+#[test]
+fn nothing_else_is_tracked() {
+    for file in BUILD_INPUT_FILES
+        .iter()
+        .filter(|file| Path::new(file).is_relative())
+    {
+        let loader_source = *file == "CMakeLists.txt"
+            || (file.starts_with("loader/") && !file.ends_with(".md"))
+            || BUILD_SCRIPTS.contains(file);
+        let loader_test_source = cfg!(feature = "loader-tests")
+            && file.starts_with("tests/")
+            && !file.ends_with(".rs")
+            && !file.ends_with(".md");
+        assert!(loader_source || loader_test_source, "{} is tracked", file);
+        assert!(!file.contains("__pycache__"), "{} is tracked", file);
+    }
+}
+
+#[test]
+fn configuration_variables_are_tracked() {
+    for var in [
+        "DOCS_RS",
+        "VULKAN_LOADER_SYS_SYSROOT",
+        "VULKAN_LOADER_SYS_LIB_DIR",
+        "VULKAN_LOADER_SYS_INCLUDE_DIR",
+        "VULKAN_LOADER_SYS_CACHE_DIR",
+        "VULKAN_LOADER_SYS_COMPARE_TARGETS",
+        "CMAKE_TOOLCHAIN_FILE",
+        "LIBCLANG_PATH",
+    ] {
+        assert!(
+            BUILD_INPUT_ENV_VARS.contains(&var),
+            "{} is not tracked",
+            var
+        );
+    }
+
+    // Only the target's own variants.
+    let sysroot_vars = BUILD_INPUT_ENV_VARS
+        .iter()
+        .filter(|var| var.starts_with("VULKAN_LOADER_SYS_SYSROOT_"))
+        .count();
+    assert_eq!(sysroot_vars, 2);
+}
@@ -0,0 +1,217 @@
+//! Decoding of the data debug-utils messengers are called with, and messengers backed by closures.
+
+use std::{ffi::CStr, ptr::null};
+
+use vulkan_loader_sys::{debug_utils::*, *};
+
+fn label(name: &'static CStr, color: [f32; 4]) -> VkDebugUtilsLabelEXT {
+    VkDebugUtilsLabelEXT {
+        sType: VK_STRUCTURE_TYPE_DEBUG_UTILS_LABEL_EXT,
+        pNext: null(),
+        pLabelName: name.as_ptr(),
+        color,
+    }
+}
+
+fn object(
+    object_type: VkObjectType,
+    handle: u64,
+    name: Option<&'static CStr>,
+) -> VkDebugUtilsObjectNameInfoEXT {
+    VkDebugUtilsObjectNameInfoEXT {
+        sType: VK_STRUCTURE_TYPE_DEBUG_UTILS_OBJECT_NAME_INFO_EXT,
+        pNext: null(),
+        objectType: object_type,
+        objectHandle: handle,
+        pObjectName: name.map_or(null(), CStr::as_ptr),
+    }
+}
+
+#[test]
+fn from_raw() {
+    let queue_labels = [label(c"present", [1.0, 0.0, 0.0, 1.0])];
+    let cmd_buf_labels = [
+        label(c"shadow pass", [0.0, 0.5, 0.0, 1.0]),
+        label(c"main pass", [0.0, 0.0, 1.0, 0.5]),
+    ];
+    let objects = [
+        object(VK_OBJECT_TYPE_BUFFER, 0x1234, Some(c"vertices")),
+        object(VK_OBJECT_TYPE_IMAGE, 0x5678, None),
+    ];
+    let data = VkDebugUtilsMessengerCallbackDataEXT {
+        sType: VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CALLBACK_DATA_EXT,
+        pNext: null(),
+        flags: 0,
+        pMessageIdName: c"VUID-test".as_ptr(),
+        messageIdNumber: -42,
+        pMessage: c"Something went wrong".as_ptr(),
+        queueLabelCount: queue_labels.len() as u32,
+        pQueueLabels: queue_labels.as_ptr(),
+        cmdBufLabelCount: cmd_buf_labels.len() as u32,
+        pCmdBufLabels: cmd_buf_labels.as_ptr(),
+        objectCount: objects.len() as u32,
+        pObjects: objects.as_ptr(),
+    };
+
+    let message = unsafe {
+        DebugUtilsMessage::from_raw(
+            VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT,
+            VK_DEBUG_UTILS_MESSAGE_TYPE_VALIDATION_BIT_EXT,
+            &data,
+        )
+    };
+    assert_eq!(
+        message,
+        DebugUtilsMessage {
+            severity: VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT,
+            types: VK_DEBUG_UTILS_MESSAGE_TYPE_VALIDATION_BIT_EXT,
+            message_id_name: Some("VUID-test".to_owned()),
+            message_id_number: -42,
+            message: "Something went wrong".to_owned(),
+            queue_labels: vec![DebugUtilsLabel {
+                name: "present".to_owned(),
+                color: [1.0, 0.0, 0.0, 1.0],
+            }],
+            cmd_buf_labels: vec![
+                DebugUtilsLabel {
+                    name: "shadow pass".to_owned(),
+                    color: [0.0, 0.5, 0.0, 1.0],
+                },
+                DebugUtilsLabel {
+                    name: "main pass".to_owned(),
+                    color: [0.0, 0.0, 1.0, 0.5],
+                },
+            ],
+            objects: vec![
+                DebugUtilsObject {
+                    object_type: VK_OBJECT_TYPE_BUFFER,
+                    handle: 0x1234,
+                    name: Some("vertices".to_owned()),
+                },
+                DebugUtilsObject {
+                    object_type: VK_OBJECT_TYPE_IMAGE,
+                    handle: 0x5678,
+                    name: None,
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn from_raw_without_optional_data() {
+    // Counts with null arrays and null strings are tolerated rather than dereferenced.
+    let data = VkDebugUtilsMessengerCallbackDataEXT {
+        sType: VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CALLBACK_DATA_EXT,
+        pNext: null(),
+        flags: 0,
+        pMessageIdName: null(),
+        messageIdNumber: 0,
+        pMessage: null(),
+        queueLabelCount: 1,
+        pQueueLabels: null(),
+        cmdBufLabelCount: 0,
+        pCmdBufLabels: null(),
+        objectCount: 3,
+        pObjects: null(),
+    };
+
+    let message = unsafe {
+        DebugUtilsMessage::from_raw(
+            VK_DEBUG_UTILS_MESSAGE_SEVERITY_INFO_BIT_EXT,
+            VK_DEBUG_UTILS_MESSAGE_TYPE_GENERAL_BIT_EXT,
+            &data,
+        )
+    };
+    assert_eq!(message.message_id_name, None);
+    assert_eq!(message.message, "");
+    assert!(message.queue_labels.is_empty());
+    assert!(message.cmd_buf_labels.is_empty());
+    assert!(message.objects.is_empty());
+}
+
+#[cfg(all(feature = "loader-tests", target_os = "linux"))]
+mod messengers {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use vulkan_loader_sys_test_support::*;
+
+    const DEBUG_UTILS: &CStr = c"VK_EXT_debug_utils";
+
+    fn submit(instance: &Instance, message: &CStr) {
+        let submit: PFN_vkSubmitDebugUtilsMessageEXT =
+            unsafe { std::mem::transmute(instance.get_proc_addr(c"vkSubmitDebugUtilsMessageEXT")) };
+        let objects = [object(VK_OBJECT_TYPE_INSTANCE, 1, Some(c"instance"))];
+        let data = VkDebugUtilsMessengerCallbackDataEXT {
+            sType: VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CALLBACK_DATA_EXT,
+            pNext: null(),
+            flags: 0,
+            pMessageIdName: null(),
+            messageIdNumber: 7,
+            pMessage: message.as_ptr(),
+            queueLabelCount: 0,
+            pQueueLabels: null(),
+            cmdBufLabelCount: 0,
+            pCmdBufLabels: null(),
+            objectCount: objects.len() as u32,
+            pObjects: objects.as_ptr(),
+        };
+        unsafe {
+            submit.unwrap()(
+                instance.handle(),
+                VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT,
+                VK_DEBUG_UTILS_MESSAGE_TYPE_GENERAL_BIT_EXT,
+                &data,
+            )
+        };
+    }
+
+    #[test]
+    fn panics_are_contained() {
+        let mut env = FrameworkEnvironment::new();
+        env.add_icd(TestIcdDetails::new(
+            test_icd_path("test_icd_version_2").unwrap(),
+        ));
+        let mut instance = env.create_instance_with(&[], &[DEBUG_UTILS]).unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        instance
+            .create_debug_utils_messenger(
+                VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT,
+                VK_DEBUG_UTILS_MESSAGE_TYPE_GENERAL_BIT_EXT,
+                |message| panic!("{}", message.message),
+            )
+            .unwrap();
+        let sink = received.clone();
+        instance
+            .create_debug_utils_messenger(
+                VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT,
+                VK_DEBUG_UTILS_MESSAGE_TYPE_GENERAL_BIT_EXT,
+                move |message| sink.lock().unwrap().push(message.clone()),
+            )
+            .unwrap();
+
+        // The panicking messenger neither unwinds into the loader nor stops the other messenger from being called, and
+        // it keeps being called for later messages.
+        submit(&instance, c"first");
+        submit(&instance, c"second");
+
+        let received = received.lock().unwrap();
+        let messages = received
+            .iter()
+            .map(|message| message.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["first", "second"]);
+        assert_eq!(received[0].message_id_number, 7);
+        assert_eq!(
+            received[0].objects,
+            [DebugUtilsObject {
+                object_type: VK_OBJECT_TYPE_INSTANCE,
+                handle: 1,
+                name: Some("instance".to_owned()),
+            }]
+        );
+    }
+}
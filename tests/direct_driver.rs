@@ -0,0 +1,219 @@
+//! Chaining a `DirectDriverList` into instance creation, and instances created from it.
+
+use std::{
+    ffi::{c_void, CStr},
+    os::raw::c_char,
+    ptr::null,
+    slice,
+};
+
+use vulkan_loader_sys::{direct_driver::*, *};
+
+type GetInstanceProcAddr =
+    unsafe extern "system" fn(VkInstance, *const c_char) -> PFN_vkVoidFunction;
+
+unsafe extern "system" fn first_driver(
+    _instance: VkInstance,
+    _name: *const c_char,
+) -> PFN_vkVoidFunction {
+    None
+}
+
+unsafe extern "system" fn second_driver(
+    _instance: VkInstance,
+    _name: *const c_char,
+) -> PFN_vkVoidFunction {
+    None
+}
+
+fn driver_list(mode: DirectDriverLoadingMode) -> DirectDriverList {
+    let mut list = DirectDriverList::new(mode);
+    unsafe {
+        list.push(DirectDriver::from_fn(Some(first_driver)))
+            .push(DirectDriver::from_fn(Some(second_driver)));
+    }
+    list
+}
+
+fn create_info(extensions: &[*const c_char], p_next: *const c_void) -> VkInstanceCreateInfo {
+    VkInstanceCreateInfo {
+        sType: VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+        pNext: p_next,
+        flags: 0,
+        pApplicationInfo: null(),
+        enabledLayerCount: 0,
+        ppEnabledLayerNames: null(),
+        enabledExtensionCount: extensions.len() as u32,
+        ppEnabledExtensionNames: if extensions.is_empty() {
+            null()
+        } else {
+            extensions.as_ptr()
+        },
+    }
+}
+
+unsafe fn extension_names(create_info: &VkInstanceCreateInfo) -> Vec<&CStr> {
+    slice::from_raw_parts(
+        create_info.ppEnabledExtensionNames,
+        create_info.enabledExtensionCount as usize,
+    )
+    .iter()
+    .map(|name| CStr::from_ptr(*name))
+    .collect()
+}
+
+fn direct_driver_loading() -> &'static CStr {
+    CStr::from_bytes_with_nul(VK_LUNARG_DIRECT_DRIVER_LOADING_EXTENSION_NAME).unwrap()
+}
+
+#[test]
+fn chain() {
+    let messenger = loader_log::messenger_create_info();
+    let surface = c"VK_KHR_surface";
+    let original = create_info(
+        &[surface.as_ptr()],
+        &messenger as *const VkDebugUtilsMessengerCreateInfoEXT as *const c_void,
+    );
+
+    unsafe {
+        driver_list(DirectDriverLoadingMode::Inclusive).chain(&original, |chained| {
+            assert_eq!(chained.sType, VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO);
+            assert_eq!(chained.flags, original.flags);
+            assert_eq!(chained.pApplicationInfo, original.pApplicationInfo);
+
+            // The list comes first, followed by whatever was chained before.
+            let list = &*(chained.pNext as *const VkDirectDriverLoadingListLUNARG);
+            assert_eq!(
+                list.sType,
+                VK_STRUCTURE_TYPE_DIRECT_DRIVER_LOADING_LIST_LUNARG
+            );
+            assert_eq!(list.pNext as *const c_void, original.pNext);
+            assert_eq!(list.mode, VK_DIRECT_DRIVER_LOADING_MODE_INCLUSIVE_LUNARG);
+
+            let drivers = slice::from_raw_parts(list.pDrivers, list.driverCount as usize);
+            assert_eq!(drivers.len(), 2);
+            for (driver, expected) in drivers
+                .iter()
+                .zip([first_driver as GetInstanceProcAddr, second_driver])
+            {
+                assert_eq!(
+                    driver.sType,
+                    VK_STRUCTURE_TYPE_DIRECT_DRIVER_LOADING_INFO_LUNARG
+                );
+                assert!(driver.pNext.is_null());
+                assert_eq!(
+                    driver
+                        .pfnGetInstanceProcAddr
+                        .map(|function| function as usize),
+                    Some(expected as usize)
+                );
+            }
+
+            assert_eq!(extension_names(chained), [surface, direct_driver_loading()]);
+        });
+    }
+
+    // The original create info is left alone.
+    assert_eq!(original.enabledExtensionCount, 1);
+    assert_eq!(
+        original.pNext,
+        &messenger as *const VkDebugUtilsMessengerCreateInfoEXT as *const c_void
+    );
+}
+
+#[test]
+fn extension_is_added_once() {
+    let list = driver_list(DirectDriverLoadingMode::Exclusive);
+
+    // Without any extensions.
+    let original = create_info(&[], null());
+    unsafe {
+        list.chain(&original, |chained| {
+            let list = &*(chained.pNext as *const VkDirectDriverLoadingListLUNARG);
+            assert!(list.pNext.is_null());
+            assert_eq!(list.mode, VK_DIRECT_DRIVER_LOADING_MODE_EXCLUSIVE_LUNARG);
+            assert_eq!(extension_names(chained), [direct_driver_loading()]);
+        });
+    }
+
+    // Already enabled by the caller.
+    let extensions = [
+        c"VK_EXT_debug_utils".as_ptr(),
+        direct_driver_loading().as_ptr(),
+    ];
+    let original = create_info(&extensions, null());
+    unsafe {
+        list.chain(&original, |chained| {
+            assert_eq!(chained.enabledExtensionCount, 2);
+            assert_eq!(
+                extension_names(chained),
+                [c"VK_EXT_debug_utils", direct_driver_loading()]
+            );
+        });
+    }
+}
+
+#[test]
+fn empty_list() {
+    let list = DirectDriverList::default();
+    assert_eq!(list.mode, DirectDriverLoadingMode::Exclusive);
+
+    let original = create_info(&[], null());
+    unsafe {
+        list.chain(&original, |chained| {
+            let list = &*(chained.pNext as *const VkDirectDriverLoadingListLUNARG);
+            assert_eq!(list.driverCount, 0);
+            assert_eq!(extension_names(chained), [direct_driver_loading()]);
+        });
+    }
+}
+
+#[cfg(all(feature = "loader-tests", target_os = "linux", loader_log_hook))]
+mod instances {
+    use super::*;
+
+    use vulkan_loader_sys_test_support::*;
+
+    // Creates an instance with the test driver as the only direct driver, and returns whether the loader went looking
+    // for driver manifests while doing so.
+    fn create_instance(mode: DirectDriverLoadingMode) -> bool {
+        let mut list = DirectDriverList::new(mode);
+        list.push(
+            unsafe { DirectDriver::open(test_icd_path("test_icd_version_7").unwrap()) }.unwrap(),
+        );
+
+        loader_log::install(loader_log::VULKAN_LOADER_DRIVER_BIT, true);
+        loader_log::take_buffered();
+        let instance = unsafe { list.create_instance(&create_info(&[], null())) };
+        loader_log::uninstall();
+
+        assert!(instance.is_ok(), "{:?}", instance.err());
+        drop(instance);
+
+        loader_log::take_buffered()
+            .iter()
+            .any(|message| message.message.starts_with("Found ICD manifest file"))
+    }
+
+    #[test]
+    fn exclusive_mode() {
+        let mut env = FrameworkEnvironment::new();
+        env.add_icd(TestIcdDetails::new(
+            test_icd_path("test_icd_version_2").unwrap(),
+        ));
+
+        assert!(!create_instance(DirectDriverLoadingMode::Exclusive));
+        assert!(create_instance(DirectDriverLoadingMode::Inclusive));
+    }
+
+    #[test]
+    fn exclusive_mode_without_manifests() {
+        let env = FrameworkEnvironment::new();
+        assert_eq!(
+            env.create_instance().err(),
+            Some(VK_ERROR_INCOMPATIBLE_DRIVER)
+        );
+
+        assert!(!create_instance(DirectDriverLoadingMode::Exclusive));
+    }
+}
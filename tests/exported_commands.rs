@@ -0,0 +1,72 @@
+//! Cross-checks the commands declared as extern functions against the dynamic symbol table of the libvulkan the crate
+//! links to. On musl the loader is a static archive without one.
+
+#![cfg(all(target_os = "linux", not(target_env = "musl")))]
+
+use std::{collections::BTreeSet, process::Command};
+
+use vulkan_loader_sys::EXPORTED_COMMANDS;
+
+// The commands of the window systems the loader is built with but the features leave out of the bindings, which are
+// exported without being declared. build.rs works them out from its PLATFORM_EXPORTS.
+fn undeclared_platform_commands() -> Vec<&'static str> {
+    env!("VULKAN_LOADER_SYS_UNDECLARED_PLATFORM_COMMANDS")
+        .split(',')
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+fn dynamic_symbols() -> BTreeSet<String> {
+    let library = concat!(env!("VULKAN_LOADER_SYS_LINKED_LIB_DIR"), "/libvulkan.so");
+    let output = Command::new("nm")
+        .args(["--dynamic", "--defined-only", library])
+        .output()
+        .expect("failed to run nm");
+    assert!(output.status.success(), "nm failed on {}", library);
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(2))
+        .filter(|symbol| symbol.starts_with("vk"))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn declared_commands_are_exported() {
+    let exported = dynamic_symbols();
+
+    let missing = EXPORTED_COMMANDS
+        .iter()
+        .filter(|command| !exported.contains(**command))
+        .collect::<Vec<_>>();
+    assert!(
+        missing.is_empty(),
+        "declared but not exported: {:?}",
+        missing
+    );
+}
+
+#[test]
+fn exported_commands_are_declared() {
+    let platform_commands = undeclared_platform_commands();
+    let undeclared = dynamic_symbols()
+        .into_iter()
+        .filter(|symbol| !EXPORTED_COMMANDS.contains(&symbol.as_str()))
+        .filter(|symbol| !platform_commands.contains(&symbol.as_str()))
+        .collect::<Vec<_>>();
+    assert!(
+        undeclared.is_empty(),
+        "exported but not declared: {:?}",
+        undeclared
+    );
+}
+
+#[test]
+fn extension_commands_are_not_declared() {
+    // Only reachable through vkGetInstanceProcAddr, as the loader doesn't export them.
+    assert!(!EXPORTED_COMMANDS.contains(&"vkCreateDebugUtilsMessengerEXT"));
+    assert!(EXPORTED_COMMANDS.contains(&"vkCreateInstance"));
+    assert!(EXPORTED_COMMANDS.contains(&"vkGetInstanceProcAddr"));
+}
@@ -0,0 +1,72 @@
+//! Instances created against drivers and layers set up by the test-support crate's `FrameworkEnvironment`.
+
+#![cfg(target_os = "linux")]
+
+use vulkan_loader_sys::*;
+use vulkan_loader_sys_test_support::*;
+
+#[test]
+fn no_drivers() {
+    let env = FrameworkEnvironment::new();
+
+    assert_eq!(
+        env.create_instance().err(),
+        Some(VK_ERROR_INCOMPATIBLE_DRIVER)
+    );
+}
+
+#[test]
+fn system_layers_are_hidden() {
+    let env = FrameworkEnvironment::new();
+
+    if let Some(sysconfdir) = LOADER_TEST_SYSCONFDIR {
+        assert_eq!(search_paths::SYSCONF_DIRS, [sysconfdir]);
+        assert!(std::fs::canonicalize(sysconfdir)
+            .unwrap()
+            .starts_with(std::fs::canonicalize(env.root()).unwrap()));
+    }
+    if cfg!(feature = "loader-tests") {
+        assert!(env.instance_layers().unwrap().is_empty());
+    }
+}
+
+#[cfg(all(feature = "loader-tests", loader_log_hook))]
+#[test]
+fn two_drivers_and_an_implicit_layer() {
+    let mut env = FrameworkEnvironment::new();
+
+    let icd = test_icd_path("test_icd_version_2").unwrap();
+    env.add_icd(TestIcdDetails::new(&icd));
+    env.add_icd(TestIcdDetails::new(&icd));
+    env.add_implicit_layer(TestLayerDetails::new(
+        LayerManifest::new("VK_LAYER_implicit_test")
+            .library_path(test_layer_path("test_layer_export_version_2").unwrap())
+            .enable_environment("ENABLE_IMPLICIT_TEST_LAYER")
+            .disable_environment("DISABLE_IMPLICIT_TEST_LAYER"),
+        "implicit_test_layer",
+    ));
+
+    assert_eq!(env.icds().len(), 2);
+    assert_ne!(env.icds()[0].library_path, env.icds()[1].library_path);
+    assert_eq!(env.instance_layers().unwrap(), ["VK_LAYER_implicit_test"]);
+
+    let inserted_layers = |env: &FrameworkEnvironment| {
+        loader_log::install(loader_log::VULKAN_LOADER_LAYER_BIT, true);
+        loader_log::take_buffered();
+        drop(env.create_instance().unwrap());
+        loader_log::uninstall();
+
+        loader_log::take_buffered()
+            .into_iter()
+            .filter(|message| message.message.starts_with("Insert instance layer"))
+            .count()
+    };
+
+    assert_eq!(inserted_layers(&env), 0);
+
+    env.set_env("ENABLE_IMPLICIT_TEST_LAYER", "1");
+    assert_eq!(inserted_layers(&env), 1);
+
+    env.set_env("DISABLE_IMPLICIT_TEST_LAYER", "1");
+    assert_eq!(inserted_layers(&env), 0);
+}
@@ -0,0 +1,56 @@
+//! Headless surfaces created through the loader's `vkCreateHeadlessSurfaceEXT` terminator.
+
+#![cfg(all(
+    feature = "headless-surface",
+    feature = "loader-tests",
+    target_os = "linux"
+))]
+
+use vulkan_loader_sys::{headless_surface::HEADLESS_SURFACE_EXTENSIONS, *};
+use vulkan_loader_sys_test_support::*;
+
+fn environment() -> FrameworkEnvironment {
+    let mut env = FrameworkEnvironment::new();
+    env.add_icd(TestIcdDetails::new(
+        test_icd_path("test_icd_version_7").unwrap(),
+    ));
+    // The test driver doesn't report any instance extensions, so a layer advertises them instead. The loader creates
+    // headless surfaces itself and only calls into drivers that implement the extension, which this one doesn't.
+    env.add_implicit_layer(TestLayerDetails::new(
+        LayerManifest::new("VK_LAYER_headless_surface")
+            .library_path(test_layer_path("test_layer_export_version_2").unwrap())
+            .instance_extension("VK_KHR_surface")
+            .instance_extension("VK_EXT_headless_surface"),
+        "headless_surface_layer",
+    ));
+    env
+}
+
+#[test]
+fn surface_is_created_by_the_loader() {
+    let env = environment();
+
+    let mut instance = env
+        .create_instance_with(&[], &HEADLESS_SURFACE_EXTENSIONS)
+        .unwrap();
+    let first = instance.create_headless_surface().unwrap();
+    let second = instance.create_headless_surface().unwrap();
+    assert!(!first.is_null());
+    assert_ne!(first, second);
+
+    // Dropping the instance destroys both surfaces first.
+    drop(instance);
+}
+
+#[test]
+fn extension_has_to_be_enabled() {
+    let env = environment();
+
+    let mut instance = env
+        .create_instance_with(&[], &HEADLESS_SURFACE_EXTENSIONS[..1])
+        .unwrap();
+    assert_eq!(
+        instance.create_headless_surface(),
+        Err(VK_ERROR_EXTENSION_NOT_PRESENT)
+    );
+}
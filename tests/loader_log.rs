@@ -0,0 +1,235 @@
+//! Routing of the loader's messages through the global hook and through debug-utils messengers.
+
+use vulkan_loader_sys::loader_log::*;
+
+fn message(flags: u32) -> LoaderMessage {
+    LoaderMessage {
+        flags,
+        code: 0,
+        message: String::new(),
+    }
+}
+
+#[test]
+fn targets() {
+    assert_eq!(message(VULKAN_LOADER_INFO_BIT).target(), "vulkan_loader");
+    assert_eq!(
+        message(VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_LAYER_BIT).target(),
+        "vulkan_loader::layer"
+    );
+    assert_eq!(
+        message(VULKAN_LOADER_WARN_BIT | VULKAN_LOADER_DRIVER_BIT).target(),
+        "vulkan_loader::driver"
+    );
+    assert_eq!(
+        message(VULKAN_LOADER_WARN_BIT | VULKAN_LOADER_VALIDATION_BIT).target(),
+        "vulkan_loader::validation"
+    );
+    assert_eq!(
+        message(VULKAN_LOADER_PERF_BIT).target(),
+        "vulkan_loader::perf"
+    );
+    // The layer bit takes precedence over everything else.
+    assert_eq!(
+        message(VULKAN_LOADER_ALL_BITS).target(),
+        "vulkan_loader::layer"
+    );
+}
+
+#[test]
+fn levels() {
+    assert_eq!(level(VULKAN_LOADER_ERROR_BIT), log::Level::Error);
+    assert_eq!(level(VULKAN_LOADER_FATAL_ERROR_BIT), log::Level::Error);
+    assert_eq!(
+        level(VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_WARN_BIT),
+        log::Level::Error
+    );
+    assert_eq!(level(VULKAN_LOADER_WARN_BIT), log::Level::Warn);
+    assert_eq!(
+        level(VULKAN_LOADER_WARN_BIT | VULKAN_LOADER_INFO_BIT),
+        log::Level::Warn
+    );
+    assert_eq!(level(VULKAN_LOADER_INFO_BIT), log::Level::Info);
+    assert_eq!(level(VULKAN_LOADER_DEBUG_BIT), log::Level::Debug);
+    assert_eq!(level(VULKAN_LOADER_LAYER_BIT), log::Level::Info);
+    assert_eq!(level(VULKAN_LOADER_DRIVER_BIT), log::Level::Info);
+    assert_eq!(level(0), log::Level::Info);
+    assert_eq!(
+        message(VULKAN_LOADER_PERF_BIT | VULKAN_LOADER_DEBUG_BIT).level(),
+        log::Level::Debug
+    );
+}
+
+#[test]
+fn parse_debug_levels() {
+    assert_eq!(
+        parse_debug_level("error,warn,driver"),
+        VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_WARN_BIT | VULKAN_LOADER_DRIVER_BIT
+    );
+    assert_eq!(parse_debug_level(""), 0);
+    assert_eq!(
+        parse_debug_level("info,,perf,"),
+        VULKAN_LOADER_INFO_BIT | VULKAN_LOADER_PERF_BIT
+    );
+    assert_eq!(
+        parse_debug_level("layer,debug"),
+        VULKAN_LOADER_LAYER_BIT | VULKAN_LOADER_DEBUG_BIT
+    );
+    // Aliases and prefixes of the names, as the loader compares them with strncmp.
+    assert_eq!(parse_debug_level("implem"), VULKAN_LOADER_DRIVER_BIT);
+    assert_eq!(parse_debug_level("icd"), VULKAN_LOADER_DRIVER_BIT);
+    assert_eq!(
+        parse_debug_level("err,w"),
+        VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_WARN_BIT
+    );
+    // Unknown entries are ignored.
+    assert_eq!(parse_debug_level("errors,verbose"), 0);
+    assert_eq!(parse_debug_level("warn,nothing"), VULKAN_LOADER_WARN_BIT);
+    // "all" turns on everything, whatever else is listed.
+    assert_eq!(parse_debug_level("all"), !0);
+    assert_eq!(parse_debug_level("error,all,warn"), !0);
+}
+
+#[cfg(all(target_os = "linux", loader_log_hook))]
+mod hooks {
+    use super::*;
+
+    use std::ptr::{null, null_mut};
+
+    use vulkan_loader_sys::*;
+    use vulkan_loader_sys_test_support::*;
+
+    const NO_DRIVERS: &str = "vkCreateInstance: Found no drivers!";
+
+    // Creates an instance without any drivers, which makes the loader log NO_DRIVERS as an error and driver message,
+    // with the messenger from `messenger_create_info` chained in.
+    fn create_instance_with_messenger(_env: &FrameworkEnvironment) -> VkResult {
+        let messenger_create_info = messenger_create_info();
+        let create_info = VkInstanceCreateInfo {
+            sType: VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+            pNext: &messenger_create_info as *const _ as *const _,
+            flags: 0,
+            pApplicationInfo: null(),
+            enabledLayerCount: 0,
+            ppEnabledLayerNames: null(),
+            enabledExtensionCount: 0,
+            ppEnabledExtensionNames: null(),
+        };
+
+        let mut instance = null_mut();
+        unsafe { vkCreateInstance(&create_info, null(), &mut instance) }
+    }
+
+    fn no_drivers_messages(messages: &[LoaderMessage]) -> Vec<&LoaderMessage> {
+        messages
+            .iter()
+            .filter(|message| message.message == NO_DRIVERS)
+            .collect()
+    }
+
+    #[test]
+    fn set_and_get_debug_level() {
+        let env = FrameworkEnvironment::new();
+
+        let original = debug_level();
+        for flags in [
+            0,
+            VULKAN_LOADER_ERROR_BIT,
+            VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_WARN_BIT | VULKAN_LOADER_DRIVER_BIT,
+            VULKAN_LOADER_ALL_BITS,
+        ] {
+            set_debug_level(flags);
+            assert_eq!(debug_level(), flags);
+        }
+
+        // FrameworkSettings::log_filter sets the level for the lifetime of the environment and restores it afterwards.
+        set_debug_level(original);
+        drop(env);
+        let env = FrameworkEnvironment::with_settings(
+            FrameworkSettings::default().log_filter("error,warn,driver"),
+        );
+        assert_eq!(
+            debug_level(),
+            VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_WARN_BIT | VULKAN_LOADER_DRIVER_BIT
+        );
+        drop(env);
+        assert_eq!(debug_level(), original);
+    }
+
+    #[test]
+    fn install_and_take_buffered() {
+        let env = FrameworkEnvironment::new();
+
+        install(VULKAN_LOADER_DRIVER_BIT, true);
+        take_buffered();
+        assert_eq!(
+            env.create_instance().err(),
+            Some(VK_ERROR_INCOMPATIBLE_DRIVER)
+        );
+        uninstall();
+
+        let messages = take_buffered();
+        let no_drivers = no_drivers_messages(&messages);
+        assert_eq!(no_drivers.len(), 1);
+        assert_eq!(
+            no_drivers[0].flags,
+            VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_DRIVER_BIT
+        );
+        assert_eq!(no_drivers[0].target(), "vulkan_loader::driver");
+        assert_eq!(no_drivers[0].level(), log::Level::Error);
+        assert!(messages
+            .iter()
+            .all(|message| message.flags & VULKAN_LOADER_DRIVER_BIT != 0));
+
+        // Taking the messages clears the buffer, and nothing is recorded once uninstalled.
+        assert!(take_buffered().is_empty());
+        assert_eq!(
+            env.create_instance().err(),
+            Some(VK_ERROR_INCOMPATIBLE_DRIVER)
+        );
+        assert!(take_buffered().is_empty());
+
+        // Without buffering, the messages only go to the logger.
+        install(VULKAN_LOADER_ALL_BITS, false);
+        assert_eq!(
+            env.create_instance().err(),
+            Some(VK_ERROR_INCOMPATIBLE_DRIVER)
+        );
+        uninstall();
+        assert!(take_buffered().is_empty());
+    }
+
+    #[test]
+    fn messenger_reports_what_the_hook_does_not() {
+        let env = FrameworkEnvironment::new();
+
+        // Only the messenger sees the message.
+        take_buffered();
+        install(VULKAN_LOADER_LAYER_BIT, true);
+        assert_eq!(
+            create_instance_with_messenger(&env),
+            VK_ERROR_INCOMPATIBLE_DRIVER
+        );
+        uninstall();
+        let messages = take_buffered();
+        let no_drivers = no_drivers_messages(&messages);
+        assert_eq!(no_drivers.len(), 1);
+        // Debug-utils doesn't carry the driver bit.
+        assert_eq!(no_drivers[0].flags, VULKAN_LOADER_ERROR_BIT);
+
+        // Both see it, but it is only reported once.
+        install(VULKAN_LOADER_DRIVER_BIT, true);
+        assert_eq!(
+            create_instance_with_messenger(&env),
+            VK_ERROR_INCOMPATIBLE_DRIVER
+        );
+        uninstall();
+        let messages = take_buffered();
+        let no_drivers = no_drivers_messages(&messages);
+        assert_eq!(no_drivers.len(), 1);
+        assert_eq!(
+            no_drivers[0].flags,
+            VULKAN_LOADER_ERROR_BIT | VULKAN_LOADER_DRIVER_BIT
+        );
+    }
+}
@@ -0,0 +1,39 @@
+//! Runs the loader's own googletest suite, which is built by build.rs when the `loader-tests` feature is enabled.
+//!
+//! `cargo test --features loader-tests` therefore checks that the loader this crate ships passes the same tests as the
+//! upstream one.
+
+#![cfg(all(feature = "loader-tests", target_os = "linux"))]
+
+use std::{path::Path, process::Command, sync::Mutex};
+
+// The suites share the test framework's fake file system in the build directory, so they must not run at the same time.
+static SUITE_LOCK: Mutex<()> = Mutex::new(());
+
+fn run_suite(name: &str) {
+    // build.rs ignores the feature for musl targets and prebuilt loaders, and says so in a warning.
+    let Some(directory) = vulkan_loader_sys::LOADER_TEST_BINARY_DIR else {
+        eprintln!("The loader's tests were not built, skipping {}", name);
+        return;
+    };
+    let directory = Path::new(directory);
+
+    let _guard = SUITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let status = Command::new(directory.join(name))
+        .arg("--gtest_brief=1")
+        .current_dir(directory)
+        .status()
+        .unwrap_or_else(|error| panic!("Failed to run {}: {}", name, error));
+
+    assert!(status.success(), "{} failed with {}", name, status);
+}
+
+#[test]
+fn regression_suite() {
+    run_suite("test_regression");
+}
+
+#[test]
+fn threading_suite() {
+    run_suite("test_threading");
+}
@@ -0,0 +1,36 @@
+//! The video codec std types the `video` feature adds to the bindings.
+
+#![cfg(feature = "video")]
+
+use vulkan_loader_sys::*;
+
+#[test]
+fn flags_have_bitfield_accessors() {
+    let mut flags: StdVideoH264SpsFlags = unsafe { std::mem::zeroed() };
+    flags.set_frame_mbs_only_flag(1);
+    flags.set_vui_parameters_present_flag(1);
+    assert_eq!(flags.frame_mbs_only_flag(), 1);
+    assert_eq!(flags.vui_parameters_present_flag(), 1);
+    assert_eq!(flags.direct_8x8_inference_flag(), 0);
+
+    flags.set_frame_mbs_only_flag(0);
+    assert_eq!(flags.frame_mbs_only_flag(), 0);
+    assert_eq!(flags.vui_parameters_present_flag(), 1);
+}
+
+#[test]
+fn std_headers_are_complete() {
+    // Constants and enums that no Vulkan struct refers to are only there with the video feature.
+    assert_eq!(STD_VIDEO_H264_CPB_CNT_LIST_SIZE, 32);
+    let _: StdVideoH264SliceType = STD_VIDEO_H264_SLICE_TYPE_I;
+}
+
+#[test]
+fn decode_structs_point_at_std_types() {
+    let parameters: VkVideoDecodeH264SessionParametersAddInfoKHR = unsafe { std::mem::zeroed() };
+    let _: *const StdVideoH264SequenceParameterSet = parameters.pStdSPSs;
+    let _: *const StdVideoH264PictureParameterSet = parameters.pStdPPSs;
+
+    let picture: VkVideoDecodeH265PictureInfoKHR = unsafe { std::mem::zeroed() };
+    let _: *const StdVideoDecodeH265PictureInfo = picture.pStdPictureInfo;
+}
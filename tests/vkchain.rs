@@ -0,0 +1,157 @@
+//! Runs the vkchain binary against layers installed in a `FrameworkEnvironment`, and checks its chains against the ones
+//! the loader logs.
+
+#![cfg(all(feature = "tools", target_os = "linux"))]
+
+use std::process::{Command, Output};
+
+use serde_json::Value;
+use vulkan_loader_sys_test_support::*;
+
+fn vkchain(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_vkchain"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[cfg(feature = "loader-tests")]
+fn names(layers: &Value) -> Vec<&str> {
+    layers
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|layer| layer.get("name").unwrap_or(layer).as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn missing_layers_are_skipped() {
+    let mut env = FrameworkEnvironment::new();
+    env.add_explicit_layer(TestLayerDetails::new(
+        LayerManifest::new("VK_LAYER_meta").component_layer("VK_LAYER_not_installed"),
+        "meta_layer",
+    ));
+
+    let output = vkchain(&[
+        "--json",
+        "--layer",
+        "VK_LAYER_meta",
+        "--layer",
+        "VK_LAYER_missing",
+    ]);
+    // The loader would fail vkCreateInstance with VK_ERROR_LAYER_NOT_PRESENT.
+    assert!(!output.status.success());
+
+    let chain: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(chain["instance"].as_array().unwrap().is_empty());
+    assert_eq!(chain["skipped"][0]["name"], "VK_LAYER_meta");
+    assert_eq!(
+        chain["skipped"][0]["reason"],
+        "meta layer with missing component layers: VK_LAYER_not_installed"
+    );
+    assert_eq!(chain["skipped"][1]["name"], "VK_LAYER_missing");
+
+    let output = vkchain(&["--dot"]);
+    assert!(output.status.success());
+    let dot = String::from_utf8(output.stdout).unwrap();
+    assert!(dot.starts_with("digraph call_chain {"));
+    assert!(dot.contains("instance_loader -> instance_end;"));
+}
+
+#[cfg(feature = "loader-tests")]
+fn install_layers(env: &mut FrameworkEnvironment) {
+    let layer = test_layer_path("test_layer_export_version_2").unwrap();
+    env.add_implicit_layer(TestLayerDetails::new(
+        LayerManifest::new("VK_LAYER_implicit")
+            .library_path(&layer)
+            .disable_environment("DISABLE_IMPLICIT"),
+        "implicit_layer",
+    ));
+    for name in ["VK_LAYER_a", "VK_LAYER_b", "VK_LAYER_c"] {
+        env.add_explicit_layer(TestLayerDetails::new(
+            LayerManifest::new(name).library_path(&layer),
+            name,
+        ));
+    }
+    env.add_explicit_layer(TestLayerDetails::new(
+        LayerManifest::new("VK_LAYER_meta")
+            .component_layer("VK_LAYER_c")
+            .component_layer("VK_LAYER_a"),
+        "meta_layer",
+    ));
+}
+
+#[cfg(feature = "loader-tests")]
+#[test]
+fn layers_are_ordered_like_the_loader_orders_them() {
+    let mut env = FrameworkEnvironment::new();
+    install_layers(&mut env);
+    env.set_env("VK_LOADER_LAYERS_ENABLE", "VK_LAYER_b");
+
+    let output = vkchain(&[
+        "--json",
+        "--layer",
+        "VK_LAYER_meta",
+        "--layer",
+        "VK_LAYER_a",
+    ]);
+    assert!(output.status.success());
+    let chain: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    // Implicit layers first, then VK_LOADER_LAYERS_ENABLE, then the application's layers with the meta layer expanded.
+    let expected = [
+        "VK_LAYER_implicit",
+        "VK_LAYER_b",
+        "VK_LAYER_c",
+        "VK_LAYER_a",
+    ];
+    assert_eq!(names(&chain["instance"]), expected);
+    assert_eq!(names(&chain["device"]), expected);
+    assert_eq!(chain["instance"][2]["meta_layer"], "VK_LAYER_meta");
+    assert_eq!(chain["instance"][0]["implicit"], true);
+
+    env.set_env("DISABLE_IMPLICIT", "1");
+    let output = vkchain(&["--layer", "VK_LAYER_a"]);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(!text.contains("VK_LAYER_implicit"));
+    assert!(text.contains("Enabled: forced on by VK_LOADER_LAYERS_ENABLE"));
+}
+
+#[cfg(feature = "loader-tests")]
+#[test]
+fn chain_matches_the_loader_log() {
+    use vulkan_loader_sys::debug_log::{self, Event};
+
+    let mut env = FrameworkEnvironment::new();
+    env.add_icd(TestIcdDetails::new(
+        test_icd_path("test_icd_version_7").unwrap(),
+    ));
+    install_layers(&mut env);
+    env.set_env("VK_INSTANCE_LAYERS", "VK_LAYER_b:VK_LAYER_meta");
+
+    let vkinfo = Command::new(env!("CARGO_BIN_EXE_vkinfo"))
+        .arg("--json")
+        .env("VK_LOADER_DEBUG", "layer")
+        .output()
+        .unwrap();
+    let logged = debug_log::parse(&String::from_utf8_lossy(&vkinfo.stderr))
+        .into_iter()
+        .find_map(|record| match record.event {
+            Event::CallChain {
+                is_device: false,
+                layers,
+            } => Some(layers),
+            _ => None,
+        })
+        .unwrap();
+
+    let chain: Value = serde_json::from_slice(&vkchain(&["--json"]).stdout).unwrap();
+    assert_eq!(
+        names(&chain["instance"]),
+        logged
+            .iter()
+            .map(|layer| layer.name.as_str())
+            .collect::<Vec<_>>()
+    );
+}
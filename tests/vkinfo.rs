@@ -0,0 +1,65 @@
+//! Runs the vkinfo binary against environments set up by the test-support crate's `FrameworkEnvironment`.
+
+#![cfg(all(feature = "tools", target_os = "linux"))]
+
+use std::process::{Command, Output};
+
+use vulkan_loader_sys_test_support::*;
+
+fn vkinfo(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_vkinfo"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn no_drivers() {
+    let _env = FrameworkEnvironment::new();
+
+    let output = vkinfo(&["--json"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("VK_ERROR_INCOMPATIBLE_DRIVER"));
+}
+
+#[cfg(feature = "loader-tests")]
+#[test]
+fn json_and_diffable_output() {
+    let mut env = FrameworkEnvironment::new();
+    env.add_icd(TestIcdDetails::new(
+        test_icd_path("test_icd_version_2").unwrap(),
+    ));
+
+    let output = vkinfo(&["--json"]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(report["instance"]["api_version"]
+        .as_str()
+        .unwrap()
+        .starts_with("1."));
+    assert!(report["instance"]["extensions"].is_object());
+    assert!(report["physical_devices"].is_array());
+
+    let output = vkinfo(&["--diffable"]);
+    assert!(output.status.success());
+    let lines = String::from_utf8(output.stdout).unwrap();
+    assert!(lines
+        .lines()
+        .any(|line| line.starts_with("instance.api_version = ")));
+    assert!(lines.lines().is_sorted());
+}
+
+#[cfg(feature = "loader-tests")]
+#[test]
+fn direct_driver() {
+    // No manifests at all, the driver is only reachable through --driver.
+    let _env = FrameworkEnvironment::new();
+    let icd = test_icd_path("test_icd_version_7").unwrap();
+
+    let output = vkinfo(&["--json", "--driver", icd.to_str().unwrap()]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
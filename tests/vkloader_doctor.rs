@@ -0,0 +1,273 @@
+//! Runs the vkloader-doctor binary against drivers and layers installed in a `FrameworkEnvironment`, and checks that
+//! its report applies the same filters and `enable_environment` rules as the loader.
+
+#![cfg(all(feature = "tools", target_os = "linux"))]
+
+use std::{path::Path, process::Command};
+
+use serde_json::Value;
+use vulkan_loader_sys_test_support::*;
+
+// Drivers and layers whose library doesn't exist. Their verdicts only depend on the manifests and the environment as
+// long as they are turned off before the doctor gets to the library.
+const MISSING_LIBRARY: &str = "/nonexistent/libvulkan_loader_sys_missing.so";
+
+fn vkloader_doctor() -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_vkloader-doctor"))
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.code().is_some(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn fake_icd(env: &mut FrameworkEnvironment, json_name: &str) {
+    env.add_icd(
+        TestIcdDetails::new(MISSING_LIBRARY)
+            .json_name(json_name)
+            .is_fake(true),
+    );
+}
+
+fn fake_layer(name: &str) -> LayerManifest {
+    LayerManifest::new(name).library_path(MISSING_LIBRARY)
+}
+
+fn entry<'a>(entries: &'a Value, key: &str, value: &str) -> &'a Value {
+    entries
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry[key] == value)
+        .unwrap_or_else(|| panic!("no {} {} in {:#}", key, value, entries))
+}
+
+fn driver<'a>(report: &'a Value, manifest: &Path) -> &'a Value {
+    entry(&report["drivers"], "manifest", manifest.to_str().unwrap())
+}
+
+fn layer<'a>(report: &'a Value, name: &str) -> &'a Value {
+    entry(&report["layers"], "name", name)
+}
+
+fn verdict(entry: &Value) -> (bool, &str) {
+    (
+        entry["verdict"]["used"].as_bool().unwrap(),
+        entry["verdict"]["reason"].as_str().unwrap(),
+    )
+}
+
+#[test]
+fn report_lists_the_environment() {
+    let mut env = FrameworkEnvironment::new();
+    fake_icd(&mut env, "first_icd");
+    fake_icd(&mut env, "second_icd");
+    env.add_implicit_layer(
+        TestLayerDetails::new(
+            fake_layer("VK_LAYER_doctor_implicit").disable_environment("DISABLE_DOCTOR_IMPLICIT"),
+            "doctor_implicit",
+        )
+        .is_fake(true),
+    );
+    env.add_explicit_layer(
+        TestLayerDetails::new(fake_layer("VK_LAYER_doctor_explicit"), "doctor_explicit")
+            .is_fake(true),
+    );
+
+    let report = vkloader_doctor();
+
+    assert_eq!(
+        report["environment"]["VK_DRIVER_FILES"],
+        env.root().join("drivers").to_str().unwrap()
+    );
+    assert_eq!(
+        report["search_paths"]["drivers"]["overridden_by"],
+        "VK_DRIVER_FILES"
+    );
+
+    assert_eq!(report["drivers"].as_array().unwrap().len(), 2);
+    for icd in env.icds() {
+        let driver = driver(&report, &icd.manifest_path);
+        assert_eq!(driver["library_path"], MISSING_LIBRARY);
+        let (used, reason) = verdict(driver);
+        assert!(!used);
+        assert!(reason.starts_with("library failed to load"), "{}", reason);
+    }
+
+    // Nothing but the environment's own layers, which rules out the ones installed on the machine.
+    assert_eq!(report["layers"].as_array().unwrap().len(), 2);
+    let implicit = layer(&report, "VK_LAYER_doctor_implicit");
+    assert_eq!(implicit["type"], "implicit");
+    assert_eq!(
+        implicit["manifest"],
+        env.layers()[0].manifest_path.to_str().unwrap()
+    );
+    let explicit = layer(&report, "VK_LAYER_doctor_explicit");
+    assert_eq!(explicit["type"], "explicit");
+    assert_eq!(
+        verdict(explicit),
+        (false, "only if the application enables it")
+    );
+}
+
+#[test]
+fn filters_are_applied() {
+    let mut env = FrameworkEnvironment::new();
+    fake_icd(&mut env, "selected_icd");
+    fake_icd(&mut env, "disabled_icd");
+    fake_icd(&mut env, "other_icd");
+    env.add_implicit_layer(
+        TestLayerDetails::new(
+            fake_layer("VK_LAYER_doctor_implicit").disable_environment("DISABLE_DOCTOR_IMPLICIT"),
+            "doctor_implicit",
+        )
+        .is_fake(true),
+    );
+    env.add_explicit_layer(
+        TestLayerDetails::new(fake_layer("VK_LAYER_doctor_explicit"), "doctor_explicit")
+            .is_fake(true),
+    );
+    env.set_env("VK_LOADER_DRIVERS_SELECT", "selected_*");
+    env.set_env("VK_LOADER_DRIVERS_DISABLE", "disabled_*");
+    env.set_env("VK_LOADER_LAYERS_DISABLE", "~all~");
+
+    let report = vkloader_doctor();
+    assert_eq!(
+        report["environment"]["VK_LOADER_DRIVERS_SELECT"],
+        "selected_*"
+    );
+
+    // The selected driver gets as far as loading its library.
+    let (used, reason) = verdict(driver(&report, &env.icds()[0].manifest_path));
+    assert!(!used);
+    assert!(reason.starts_with("library failed to load"), "{}", reason);
+    assert_eq!(
+        verdict(driver(&report, &env.icds()[1].manifest_path)),
+        (false, "disabled by VK_LOADER_DRIVERS_DISABLE")
+    );
+    assert_eq!(
+        verdict(driver(&report, &env.icds()[2].manifest_path)),
+        (false, "not selected by VK_LOADER_DRIVERS_SELECT")
+    );
+
+    assert_eq!(
+        verdict(layer(&report, "VK_LAYER_doctor_implicit")),
+        (false, "forced off by VK_LOADER_LAYERS_DISABLE")
+    );
+    assert_eq!(
+        verdict(layer(&report, "VK_LAYER_doctor_explicit")),
+        (
+            false,
+            "disabled by VK_LOADER_LAYERS_DISABLE, even when the application enables it"
+        )
+    );
+
+    // VK_LOADER_LAYERS_ALLOW exempts a layer from the disable filter, and VK_INSTANCE_LAYERS turns on an explicit one.
+    env.set_env("VK_LOADER_LAYERS_ALLOW", "VK_LAYER_doctor_implicit");
+    env.set_env("VK_INSTANCE_LAYERS", "VK_LAYER_doctor_explicit");
+    let report = vkloader_doctor();
+    for name in ["VK_LAYER_doctor_implicit", "VK_LAYER_doctor_explicit"] {
+        let (used, reason) = verdict(layer(&report, name));
+        assert!(!used);
+        assert!(reason.starts_with("library failed to load"), "{}", reason);
+    }
+}
+
+#[test]
+fn enable_environment_is_applied() {
+    let mut env = FrameworkEnvironment::new();
+    env.add_implicit_layer(
+        TestLayerDetails::new(
+            fake_layer("VK_LAYER_doctor_implicit")
+                .enable_environment("ENABLE_DOCTOR_IMPLICIT")
+                .disable_environment("DISABLE_DOCTOR_IMPLICIT"),
+            "doctor_implicit",
+        )
+        .is_fake(true),
+    );
+
+    let report = vkloader_doctor();
+    assert_eq!(
+        verdict(layer(&report, "VK_LAYER_doctor_implicit")),
+        (
+            false,
+            "enable_environment ENABLE_DOCTOR_IMPLICIT=1 is not set"
+        )
+    );
+
+    env.set_env("ENABLE_DOCTOR_IMPLICIT", "1");
+    env.set_env("DISABLE_DOCTOR_IMPLICIT", "1");
+    let report = vkloader_doctor();
+    assert_eq!(
+        verdict(layer(&report, "VK_LAYER_doctor_implicit")),
+        (false, "disable_environment DISABLE_DOCTOR_IMPLICIT is set")
+    );
+
+    // VK_LOADER_LAYERS_ENABLE stands in for a missing enable_environment, but not for a disable_environment.
+    env.remove_env("ENABLE_DOCTOR_IMPLICIT");
+    env.set_env("VK_LOADER_LAYERS_ENABLE", "VK_LAYER_doctor_*");
+    let report = vkloader_doctor();
+    assert_eq!(
+        verdict(layer(&report, "VK_LAYER_doctor_implicit")),
+        (false, "disable_environment DISABLE_DOCTOR_IMPLICIT is set")
+    );
+}
+
+// How many layers the loader inserts into an instance's call chain.
+#[cfg(all(feature = "loader-tests", loader_log_hook))]
+fn inserted_layers(env: &FrameworkEnvironment) -> usize {
+    use vulkan_loader_sys::loader_log;
+
+    loader_log::install(loader_log::VULKAN_LOADER_LAYER_BIT, true);
+    loader_log::take_buffered();
+    drop(env.create_instance().unwrap());
+    loader_log::uninstall();
+
+    loader_log::take_buffered()
+        .into_iter()
+        .filter(|message| message.message.starts_with("Insert instance layer"))
+        .count()
+}
+
+#[cfg(all(feature = "loader-tests", loader_log_hook))]
+#[test]
+fn layers_turned_on_by_the_environment_are_used() {
+    let mut env = FrameworkEnvironment::new();
+    env.add_icd(TestIcdDetails::new(
+        test_icd_path("test_icd_version_2").unwrap(),
+    ));
+    env.add_implicit_layer(TestLayerDetails::new(
+        LayerManifest::new("VK_LAYER_doctor_implicit")
+            .library_path(test_layer_path("test_layer_export_version_2").unwrap())
+            .enable_environment("ENABLE_DOCTOR_IMPLICIT")
+            .disable_environment("DISABLE_DOCTOR_IMPLICIT"),
+        "doctor_implicit",
+    ));
+
+    let report = vkloader_doctor();
+    assert_eq!(
+        verdict(driver(&report, &env.icds()[0].manifest_path)),
+        (true, "found on the search path")
+    );
+    assert_eq!(
+        verdict(layer(&report, "VK_LAYER_doctor_implicit")),
+        (
+            false,
+            "enable_environment ENABLE_DOCTOR_IMPLICIT=1 is not set"
+        )
+    );
+    assert_eq!(inserted_layers(&env), 0);
+
+    env.set_env("ENABLE_DOCTOR_IMPLICIT", "1");
+    let report = vkloader_doctor();
+    assert_eq!(
+        verdict(layer(&report, "VK_LAYER_doctor_implicit")),
+        (true, "enable_environment ENABLE_DOCTOR_IMPLICIT=1 is set")
+    );
+    // The loader agrees.
+    assert_eq!(inserted_layers(&env), 1);
+}
@@ -0,0 +1,150 @@
+//! Runs the vklog binary on a hand-written log, and on the real output of the loader.
+
+#![cfg(all(feature = "tools", target_os = "linux"))]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+use serde_json::Value;
+
+fn vklog(args: &[&str], log: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vklog"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(log).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+const LOG: &str = r#"INFO:              Vulkan Loader Version 1.3.238
+DRIVER:            Searching for driver manifest files
+DRIVER:               In following locations:
+DRIVER:                  /etc/vulkan/icd.d
+DRIVER:               Found the following files:
+DRIVER:                  /etc/vulkan/icd.d/broken.json
+DRIVER:            Found ICD manifest file /etc/vulkan/icd.d/broken.json, version "1.0.0"
+ERROR | DRIVER:    loader_parse_icd_manifest: Failed to find 'library_path' object in ICD JSON file /etc/vulkan/icd.d/broken.json. Skipping ICD JSON.
+unrelated application output
+INFO:              Found manifest file /etc/vulkan/explicit_layer.d/meta.json (file version "1.1.0")
+WARNING:           Layer missing both library_path and component_layers fields.  One or the other MUST be defined.  Skipping this layer
+INFO | LAYER:      Insert instance layer "VK_LAYER_test" (/opt/libVkLayer_test.so)
+LAYER:             vkCreateInstance layer callstack setup to:
+LAYER:                <Application>
+LAYER:                  ||
+LAYER:                <Loader>
+LAYER:                  ||
+LAYER:                VK_LAYER_test
+LAYER:                        Type: Implicit
+LAYER:                            Disable Env Var:  DISABLE_TEST
+LAYER:                        Manifest: /etc/vulkan/implicit_layer.d/test.json
+LAYER:                        Library:  /opt/libVkLayer_test.so
+LAYER:                  ||
+LAYER:                <Drivers>
+ERROR | LAYER:     Requested layer "VK_LAYER_missing" failed to load!
+"#;
+
+#[test]
+fn summary_of_a_log() {
+    let output = vklog(&["--summary"], LOG.as_bytes());
+    assert!(output.status.success());
+
+    let summary: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["loader_version"], "1.3.238");
+    assert_eq!(
+        summary["drivers"]["search_locations"][0],
+        "/etc/vulkan/icd.d"
+    );
+
+    let skipped = &summary["drivers"]["skipped"][0];
+    assert_eq!(skipped["path"], "/etc/vulkan/icd.d/broken.json");
+    assert_eq!(skipped["reason"], "the manifest has no library_path");
+    assert_eq!(skipped["line"], 8);
+
+    // The loader doesn't say which manifest it means, it is the one found just before.
+    let skipped = &summary["layers"]["skipped"][0];
+    assert_eq!(
+        skipped["manifest"],
+        "/etc/vulkan/explicit_layer.d/meta.json"
+    );
+    assert_eq!(summary["layers"]["skipped"][1]["name"], "VK_LAYER_missing");
+
+    let chain = &summary["call_chains"][0];
+    assert_eq!(chain["device"], false);
+    assert_eq!(chain["layers"][0]["name"], "VK_LAYER_test");
+    assert_eq!(chain["layers"][0]["implicit"], true);
+    assert_eq!(chain["layers"][0]["disable_environment"], "DISABLE_TEST");
+    assert_eq!(chain["layers"][0]["library"], "/opt/libVkLayer_test.so");
+
+    assert_eq!(summary["errors"].as_array().unwrap().len(), 2);
+    assert_eq!(summary["warnings"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn timeline_and_json() {
+    let output = vklog(&[], LOG.as_bytes());
+    assert!(output.status.success());
+    let timeline = String::from_utf8(output.stdout).unwrap();
+    assert!(timeline.contains(
+        "skipped driver /etc/vulkan/icd.d/broken.json: the manifest has no library_path"
+    ));
+    assert!(timeline
+        .contains("instance chain: <Application> -> <Loader> -> VK_LAYER_test -> <Drivers>"));
+    assert!(!timeline.contains("unrelated application output"));
+
+    let output = vklog(&["--json"], LOG.as_bytes());
+    let records: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let records = records.as_array().unwrap();
+    // Every line of the multi-line messages ends up in a single record.
+    assert_eq!(records.len(), 9);
+    assert_eq!(records[1]["event"]["type"], "manifest_search");
+    assert_eq!(records[1]["categories"], "DRIVER");
+}
+
+#[cfg(feature = "loader-tests")]
+#[test]
+fn real_loader_output() {
+    use vulkan_loader_sys_test_support::*;
+
+    let mut env = FrameworkEnvironment::new();
+    env.add_icd(TestIcdDetails::new(
+        test_icd_path("test_icd_version_7").unwrap(),
+    ));
+    env.add_icd(
+        TestIcdDetails::new(env.root().join("libmissing.so"))
+            .json_name("missing")
+            .is_fake(true),
+    );
+    env.add_explicit_layer(TestLayerDetails::new(
+        LayerManifest::new("VK_LAYER_test_log")
+            .library_path(test_layer_path("test_layer_export_version_2").unwrap()),
+        "test_log_layer",
+    ));
+
+    let vkinfo = Command::new(env!("CARGO_BIN_EXE_vkinfo"))
+        .arg("--json")
+        .env("VK_LOADER_DEBUG", "all")
+        .env("VK_INSTANCE_LAYERS", "VK_LAYER_test_log")
+        .output()
+        .unwrap();
+
+    let output = vklog(&["--summary"], &vkinfo.stderr);
+    let summary: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(summary["loader_version"].is_string());
+    assert!(summary["drivers"]["skipped"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|skipped| skipped["path"]
+            .as_str()
+            .is_some_and(|path| path.ends_with("libmissing.so"))));
+    assert_eq!(summary["layers"]["inserted"][0], "VK_LAYER_test_log");
+    assert_eq!(
+        summary["call_chains"][0]["layers"][0]["name"],
+        "VK_LAYER_test_log"
+    );
+}
@@ -0,0 +1,133 @@
+//! Runs the vkmanifest binary on manifests written into a `FrameworkEnvironment`'s scratch directory.
+
+#![cfg(all(feature = "tools", target_os = "linux"))]
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+
+use vulkan_loader_sys_test_support::*;
+
+fn vkmanifest(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_vkmanifest"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn write(dir: &Path, name: &str, contents: &str) -> String {
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn lint_reports_what_the_loader_rejects() {
+    let env = FrameworkEnvironment::new();
+    let manifest = write(
+        env.root(),
+        "broken.json",
+        r#"{
+            "file_format_version": "1.1.2",
+            "layer": {
+                "name": "VK_LAYER_broken",
+                "type": "GLOBAL",
+                "api_version": "1.3.0",
+                "implementation_version": "1",
+                "description": "no library"
+            }
+        }"#,
+    );
+
+    let output = vkmanifest(&["lint", "--explicit", &manifest]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("error: layer VK_LAYER_broken: Layer missing both library_path and component_layers fields."));
+}
+
+#[test]
+fn lint_checks_the_schema() {
+    let env = FrameworkEnvironment::new();
+    let manifest = write(
+        env.root(),
+        "meta.json",
+        r#"{
+            "file_format_version": "1.1.2",
+            "layer": {
+                "name": "VK_LAYER_meta",
+                "type": "GLOBAL",
+                "api_version": "1.3",
+                "implementation_version": "1",
+                "description": "a meta-layer",
+                "component_layers": ["VK_LAYER_a"],
+                "library_arch": "64"
+            }
+        }"#,
+    );
+
+    // Nothing the loader cares about, so only --strict fails.
+    let output = vkmanifest(&["lint", "--explicit", &manifest]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("'api_version' should be a \"major.minor.patch\" string"));
+    assert!(stdout.contains("'library_arch' needs file_format_version 1.2.1 or later"));
+
+    assert!(!vkmanifest(&["lint", "--strict", "--explicit", &manifest])
+        .status
+        .success());
+}
+
+#[cfg(feature = "loader-tests")]
+#[test]
+fn generated_manifests_pass_lint() {
+    let env = FrameworkEnvironment::new();
+    let driver_manifest = env.root().join("driver.json");
+    let layer_manifest = env.root().join("implicit_layer.d").join("layer.json");
+    fs::create_dir_all(layer_manifest.parent().unwrap()).unwrap();
+
+    let icd = test_icd_path("test_icd_version_7").unwrap();
+    let output = vkmanifest(&[
+        "generate",
+        "driver",
+        icd.to_str().unwrap(),
+        "--output",
+        driver_manifest.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let layer = test_layer_path("test_layer_export_version_2").unwrap();
+    let output = vkmanifest(&[
+        "generate",
+        "layer",
+        layer.to_str().unwrap(),
+        "--name",
+        "VK_LAYER_generated",
+        "--implicit",
+        "--disable-environment",
+        "DISABLE_GENERATED_LAYER=1",
+        "--output",
+        layer_manifest.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = vkmanifest(&[
+        "lint",
+        "--strict",
+        driver_manifest.to_str().unwrap(),
+        layer_manifest.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("interface version 7"));
+    assert!(stdout.contains("interface version 2"));
+}